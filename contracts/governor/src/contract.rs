@@ -0,0 +1,325 @@
+use crate::{errors::Error, storage, votes::VotesClient};
+use soroban_sdk::{
+  contract, contractimpl, Address, BytesN, Env, String, Symbol, TryFromVal, Val, Vec,
+};
+use storage::{GovernorRegistry, ProposalState, VoteType};
+
+#[contract]
+pub struct Governor;
+
+#[contractimpl]
+impl Governor {
+  // only the account that deployed the governor can initialize it. The contract address is
+  // derived from the deployer and the salt, so nobody else can pass the check with their own
+  // address in between the deploy and this call
+  pub fn initialize(
+    env: Env,
+    deployer: Address,
+    salt: BytesN<32>,
+    config: storage::GovernorConfig,
+  ) -> Result<(), Error> {
+    deployer.require_auth();
+
+    if env
+      .deployer()
+      .with_address(deployer, salt)
+      .deployed_address()
+      != env.current_contract_address()
+    {
+      return Err(Error::NotDeployer);
+    }
+
+    if env.storage().instance().has(&storage::GOVERNOR_CONFIG) {
+      return Err(Error::AlreadyInitialized);
+    }
+
+    Self::write_config(env.clone(), &config)
+  }
+
+  pub fn propose(
+    env: Env,
+    proposer: Address,
+    calls: Vec<storage::Call>,
+    description: String,
+  ) -> Result<u32, Error> {
+    proposer.require_auth();
+
+    let config = Self::get_config(env.clone())?;
+
+    if calls.is_empty() {
+      return Err(Error::InvalidProposal);
+    }
+
+    // the only call a proposal can make on the governor itself is update_config
+    for call in calls.iter() {
+      if call.contract == env.current_contract_address()
+        && call.function != Symbol::new(&env, "update_config")
+      {
+        return Err(Error::InvalidProposal);
+      }
+    }
+
+    let votes = VotesClient::new(&env, &config.vote_token).get_votes(&proposer);
+
+    if votes < config.proposal_threshold {
+      return Err(Error::BelowProposalThreshold);
+    }
+
+    let id: u32 = env
+      .storage()
+      .instance()
+      .get(&GovernorRegistry::ProposalCount)
+      .unwrap_or(0);
+
+    // votes are counted as of the proposal creation, so buying stXLM afterwards does not help
+    let now = env.ledger().timestamp();
+
+    let proposal = storage::Proposal {
+      id,
+      proposer,
+      calls,
+      description,
+      snapshot: now,
+      end: now + config.voting_period,
+      for_votes: 0,
+      against_votes: 0,
+      abstain_votes: 0,
+      eta: 0,
+      executed: false,
+      canceled: false,
+    };
+
+    Self::write_proposal(env.clone(), &proposal);
+
+    env
+      .storage()
+      .instance()
+      .set(&GovernorRegistry::ProposalCount, &(id + 1));
+
+    Ok(id)
+  }
+
+  pub fn cast_vote(
+    env: Env,
+    voter: Address,
+    proposal_id: u32,
+    support: VoteType,
+  ) -> Result<i128, Error> {
+    voter.require_auth();
+
+    let config = Self::get_config(env.clone())?;
+
+    let mut proposal = Self::get_proposal(env.clone(), proposal_id)?;
+
+    let now = env.ledger().timestamp();
+
+    if proposal.canceled || now <= proposal.snapshot || now > proposal.end {
+      return Err(Error::VotingClosed);
+    }
+
+    let key = GovernorRegistry::Voted(proposal_id, voter.clone());
+
+    if Self::has_voted(env.clone(), proposal_id, voter.clone()) {
+      return Err(Error::AlreadyVoted);
+    }
+
+    let weight =
+      VotesClient::new(&env, &config.vote_token).get_past_votes(&voter, &proposal.snapshot);
+
+    if weight == 0 {
+      return Err(Error::NoVotingPower);
+    }
+
+    match support {
+      VoteType::Against => proposal.against_votes += weight,
+      VoteType::For => proposal.for_votes += weight,
+      VoteType::Abstain => proposal.abstain_votes += weight,
+    }
+
+    env.storage().persistent().set(&key, &support);
+
+    Self::extend_persistent(&env, &key);
+
+    Self::write_proposal(env.clone(), &proposal);
+
+    Ok(weight)
+  }
+
+  // starts the timelock of a proposal that passed
+  pub fn queue(env: Env, proposal_id: u32) -> Result<u64, Error> {
+    let config = Self::get_config(env.clone())?;
+
+    if Self::state(env.clone(), proposal_id)? != ProposalState::Succeeded {
+      return Err(Error::NotSucceeded);
+    }
+
+    let mut proposal = Self::get_proposal(env.clone(), proposal_id)?;
+
+    proposal.eta = env.ledger().timestamp() + config.timelock_delay;
+
+    Self::write_proposal(env.clone(), &proposal);
+
+    Ok(proposal.eta)
+  }
+
+  // anyone can execute once the timelock is over, any failing call reverts the whole proposal
+  pub fn execute(env: Env, proposal_id: u32) -> Result<(), Error> {
+    if Self::state(env.clone(), proposal_id)? != ProposalState::Queued {
+      return Err(Error::NotQueued);
+    }
+
+    let mut proposal = Self::get_proposal(env.clone(), proposal_id)?;
+
+    if env.ledger().timestamp() < proposal.eta {
+      return Err(Error::TimelockNotExpired);
+    }
+
+    // marked before the calls so a call cannot execute the proposal again
+    proposal.executed = true;
+
+    Self::write_proposal(env.clone(), &proposal);
+
+    for call in proposal.calls.iter() {
+      if call.contract == env.current_contract_address() {
+        Self::update_config(env.clone(), &call)?;
+      } else {
+        env.invoke_contract::<Val>(&call.contract, &call.function, call.args);
+      }
+    }
+
+    Ok(())
+  }
+
+  pub fn cancel(env: Env, proposer: Address, proposal_id: u32) -> Result<(), Error> {
+    proposer.require_auth();
+
+    let mut proposal = Self::get_proposal(env.clone(), proposal_id)?;
+
+    if proposal.proposer != proposer {
+      return Err(Error::NotProposer);
+    }
+
+    if proposal.executed || proposal.canceled {
+      return Err(Error::InvalidProposal);
+    }
+
+    proposal.canceled = true;
+
+    Self::write_proposal(env.clone(), &proposal);
+
+    Ok(())
+  }
+
+  pub fn state(env: Env, proposal_id: u32) -> Result<ProposalState, Error> {
+    let config = Self::get_config(env.clone())?;
+
+    let proposal = Self::get_proposal(env.clone(), proposal_id)?;
+
+    if proposal.canceled {
+      return Ok(ProposalState::Canceled);
+    }
+
+    if proposal.executed {
+      return Ok(ProposalState::Executed);
+    }
+
+    if env.ledger().timestamp() <= proposal.end {
+      return Ok(ProposalState::Active);
+    }
+
+    let total_supply =
+      VotesClient::new(&env, &config.vote_token).get_past_total_supply(&proposal.snapshot);
+
+    let quorum = total_supply * config.quorum_bps as i128 / storage::BPS_DENOMINATOR;
+
+    let reached_quorum = proposal.for_votes + proposal.abstain_votes >= quorum;
+
+    if !reached_quorum || proposal.for_votes <= proposal.against_votes {
+      return Ok(ProposalState::Defeated);
+    }
+
+    if proposal.eta > 0 {
+      return Ok(ProposalState::Queued);
+    }
+
+    Ok(ProposalState::Succeeded)
+  }
+
+  pub fn has_voted(env: Env, proposal_id: u32, voter: Address) -> bool {
+    let key = GovernorRegistry::Voted(proposal_id, voter);
+
+    if !env.storage().persistent().has(&key) {
+      return false;
+    }
+
+    Self::extend_persistent(&env, &key);
+
+    true
+  }
+
+  pub fn get_proposal(env: Env, proposal_id: u32) -> Result<storage::Proposal, Error> {
+    let key = GovernorRegistry::Proposal(proposal_id);
+    let proposal: storage::Proposal = env
+      .storage()
+      .persistent()
+      .get(&key)
+      .ok_or(Error::ProposalNotFound)?;
+
+    Self::extend_persistent(&env, &key);
+
+    Ok(proposal)
+  }
+
+  pub fn get_config(env: Env) -> Result<storage::GovernorConfig, Error> {
+    env
+      .storage()
+      .instance()
+      .get(&storage::GOVERNOR_CONFIG)
+      .ok_or(Error::NotInitialized)
+  }
+
+  // soroban does not let a contract call itself, so config changes are applied in place
+  fn update_config(env: Env, call: &storage::Call) -> Result<(), Error> {
+    let config = call
+      .args
+      .get(0)
+      .and_then(|arg| storage::GovernorConfig::try_from_val(&env, &arg).ok())
+      .ok_or(Error::InvalidProposal)?;
+
+    Self::write_config(env.clone(), &config)
+  }
+
+  fn write_proposal(env: Env, proposal: &storage::Proposal) {
+    let key = GovernorRegistry::Proposal(proposal.id);
+
+    env.storage().persistent().set(&key, proposal);
+
+    Self::extend_persistent(&env, &key);
+  }
+
+  fn extend_persistent(env: &Env, key: &GovernorRegistry) {
+    env.storage().persistent().extend_ttl(
+      key,
+      storage::PROPOSAL_LIFETIME_THRESHOLD,
+      storage::PROPOSAL_BUMP_AMOUNT,
+    );
+
+    env.storage().instance().extend_ttl(
+      storage::INSTANCE_LIFETIME_THRESHOLD,
+      storage::INSTANCE_BUMP_AMOUNT,
+    );
+  }
+
+  fn write_config(env: Env, config: &storage::GovernorConfig) -> Result<(), Error> {
+    if config.voting_period == 0 || config.quorum_bps as i128 > storage::BPS_DENOMINATOR {
+      return Err(Error::InvalidConfig);
+    }
+
+    env
+      .storage()
+      .instance()
+      .set(&storage::GOVERNOR_CONFIG, config);
+
+    Ok(())
+  }
+}
@@ -0,0 +1,21 @@
+use soroban_sdk::contracterror;
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum Error {
+  AlreadyInitialized = 1,
+  NotInitialized = 2,
+  InvalidConfig = 3,
+  InvalidProposal = 4,
+  ProposalNotFound = 5,
+  BelowProposalThreshold = 6,
+  VotingClosed = 7,
+  AlreadyVoted = 8,
+  NoVotingPower = 9,
+  NotSucceeded = 10,
+  NotQueued = 11,
+  TimelockNotExpired = 12,
+  NotProposer = 13,
+  NotDeployer = 14,
+}
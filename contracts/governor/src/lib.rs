@@ -0,0 +1,7 @@
+#![no_std]
+
+mod contract;
+mod errors;
+mod storage;
+mod test;
+mod votes;
@@ -0,0 +1,76 @@
+#![allow(unused)]
+use soroban_sdk::{contracttype, symbol_short, Address, Env, String, Symbol, Val, Vec};
+
+pub const BPS_DENOMINATOR: i128 = 10_000;
+
+pub(crate) const DAY_IN_LEDGERS: u32 = 17280;
+pub(crate) const INSTANCE_BUMP_AMOUNT: u32 = 7 * DAY_IN_LEDGERS;
+pub(crate) const INSTANCE_LIFETIME_THRESHOLD: u32 = INSTANCE_BUMP_AMOUNT - DAY_IN_LEDGERS;
+
+// proposals and vote receipts have to outlive the voting period and the timelock, an archived
+// receipt would let the same account vote twice
+pub(crate) const PROPOSAL_BUMP_AMOUNT: u32 = 30 * DAY_IN_LEDGERS;
+pub(crate) const PROPOSAL_LIFETIME_THRESHOLD: u32 = PROPOSAL_BUMP_AMOUNT - DAY_IN_LEDGERS;
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct GovernorConfig {
+  pub vote_token: Address, // s_voteToken - stXLM, voting power comes from its checkpoints
+  pub voting_period: u64,  // s_votingPeriod - seconds a proposal stays open for votes
+  pub quorum_bps: u32,     // s_quorumBps - part of the total supply that has to vote for or abstain
+  pub proposal_threshold: i128, // s_proposalThreshold - votes needed to create a proposal
+  pub timelock_delay: u64, // s_timelockDelay - seconds between queue and execute
+}
+
+pub const GOVERNOR_CONFIG: Symbol = symbol_short!("config");
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Call {
+  pub contract: Address, // contract invoked by the governor
+  pub function: Symbol,  // function name
+  pub args: Vec<Val>,    // function arguments
+}
+
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum VoteType {
+  Against,
+  For,
+  Abstain,
+}
+
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ProposalState {
+  Active,
+  Defeated,
+  Succeeded,
+  Queued,
+  Executed,
+  Canceled,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Proposal {
+  pub id: u32,
+  pub proposer: Address,   // account that created the proposal
+  pub calls: Vec<Call>,    // calls made in order on execute
+  pub description: String, // human readable description
+  pub snapshot: u64,       // voting power is read at this timestamp
+  pub end: u64,            // last timestamp votes are accepted at
+  pub for_votes: i128,
+  pub against_votes: i128,
+  pub abstain_votes: i128,
+  pub eta: u64, // timestamp the proposal can be executed at, 0 if not queued
+  pub executed: bool,
+  pub canceled: bool,
+}
+
+#[contracttype]
+pub enum GovernorRegistry {
+  ProposalCount,
+  Proposal(u32),
+  Voted(u32, Address),
+}
@@ -0,0 +1,615 @@
+#![cfg(test)]
+extern crate std;
+
+use super::*;
+use soroban_sdk::testutils::{Address as _, Ledger, MockAuth, MockAuthInvoke};
+use soroban_sdk::{token, vec, Address, BytesN, Env, IntoVal, String, Symbol, Vec};
+
+use crate::errors::Error;
+use crate::storage::{Call, GovernorConfig, ProposalState, VoteType};
+
+mod share_token {
+  soroban_sdk::contractimport!(
+    file = "../liquid-staking-contract/token/soroban_token_contract.wasm"
+  );
+}
+
+mod staking {
+  soroban_sdk::contractimport!(
+    file = "../liquid-staking-contract/wasm/liquid_staking_contract.wasm"
+  );
+}
+
+struct Setup<'a> {
+  env: Env,
+  owner: Address,
+  alice: Address,
+  bob: Address,
+  carol: Address,
+  base_token: token::StellarAssetClient<'a>,
+  staking_pool: staking::Client<'a>,
+  governor: contract::GovernorClient<'a>,
+}
+
+fn create_token_contract<'a>(e: &Env, admin: &Address) -> token::StellarAssetClient<'a> {
+  let asset_contract_registration = e.register_stellar_asset_contract_v2(admin.clone());
+
+  token::StellarAssetClient::new(e, &asset_contract_registration.address())
+}
+
+fn salt(e: &Env) -> BytesN<32> {
+  BytesN::from_array(e, &[0; 32])
+}
+
+// registers the governor at the address `deployer` gets when deploying it with `salt`
+fn create_governor_contract<'a>(e: &Env, deployer: &Address) -> contract::GovernorClient<'a> {
+  let address = e
+    .deployer()
+    .with_address(deployer.clone(), salt(e))
+    .deployed_address();
+
+  contract::GovernorClient::new(
+    e,
+    &e.register_contract(Some(&address), contract::Governor {}),
+  )
+}
+
+// alice, bob and carol stake 600, 300 and 100, then the governor takes over the pool
+fn setup<'a>() -> Setup<'a> {
+  let env = Env::default();
+  env.mock_all_auths();
+  env.budget().reset_unlimited();
+
+  let owner = Address::generate(&env);
+  let alice = Address::generate(&env);
+  let bob = Address::generate(&env);
+  let carol = Address::generate(&env);
+
+  let base_token = create_token_contract(&env, &owner);
+  let reward_token = create_token_contract(&env, &owner);
+
+  let staking_pool = staking::Client::new(&env, &env.register_contract_wasm(None, staking::WASM));
+  staking_pool.initialize(
+    &base_token.address,
+    &reward_token.address,
+    &owner,
+    &env.deployer().upload_contract_wasm(share_token::WASM),
+  );
+
+  env.ledger().with_mut(|li| {
+    li.timestamp = 100;
+  });
+
+  for (staker, amount) in [(&alice, 600), (&bob, 300), (&carol, 100)] {
+    base_token.mint(staker, &1000);
+    staking_pool.stake(staker, &amount);
+  }
+
+  let governor = create_governor_contract(&env, &owner);
+  governor.initialize(
+    &owner,
+    &salt(&env),
+    &GovernorConfig {
+      vote_token: staking_pool.get_staking_state().share_token,
+      voting_period: 1000,
+      quorum_bps: 4000,
+      proposal_threshold: 100,
+      timelock_delay: 500,
+    },
+  );
+
+  // mocked here, test_ownership_handover_to_governor runs the handover with real signatures
+  staking_pool.set_owner(&governor.address, &owner);
+  staking_pool.accept_owner(&governor.address);
+
+  env.ledger().with_mut(|li| {
+    li.timestamp = 200;
+  });
+
+  Setup {
+    env,
+    owner,
+    alice,
+    bob,
+    carol,
+    base_token,
+    staking_pool,
+    governor,
+  }
+}
+
+fn set_reward_fee_call(setup: &Setup, fee_bps: u32) -> Vec<Call> {
+  vec![
+    &setup.env,
+    Call {
+      contract: setup.staking_pool.address.clone(),
+      function: Symbol::new(&setup.env, "set_reward_fee"),
+      args: (setup.governor.address.clone(), fee_bps).into_val(&setup.env),
+    },
+  ]
+}
+
+fn jump_to(env: &Env, timestamp: u64) {
+  env.ledger().with_mut(|li| {
+    li.timestamp = timestamp;
+  });
+}
+
+// INITIALIZE
+#[test]
+fn test_initialize_twice() {
+  let setup = setup();
+
+  let config = setup.governor.get_config();
+
+  assert_eq!(
+    setup
+      .governor
+      .try_initialize(&setup.owner, &salt(&setup.env), &config),
+    Err(Ok(Error::AlreadyInitialized))
+  );
+}
+
+#[test]
+fn test_initialize_invalid_config() {
+  let env = Env::default();
+  env.mock_all_auths();
+
+  let deployer = Address::generate(&env);
+  let governor = create_governor_contract(&env, &deployer);
+
+  assert_eq!(
+    governor.try_initialize(
+      &deployer,
+      &salt(&env),
+      &GovernorConfig {
+        vote_token: Address::generate(&env),
+        voting_period: 1000,
+        quorum_bps: 10_001,
+        proposal_threshold: 0,
+        timelock_delay: 0,
+      },
+    ),
+    Err(Ok(Error::InvalidConfig))
+  );
+}
+
+#[test]
+#[should_panic(expected = "HostError: Error(Auth, InvalidAction)")]
+fn test_initialize_requires_auth() {
+  let env = Env::default();
+  let deployer = Address::generate(&env);
+  let governor = create_governor_contract(&env, &deployer);
+
+  governor.initialize(
+    &deployer,
+    &salt(&env),
+    &GovernorConfig {
+      vote_token: Address::generate(&env),
+      voting_period: 1000,
+      quorum_bps: 4000,
+      proposal_threshold: 0,
+      timelock_delay: 0,
+    },
+  );
+}
+
+#[test]
+fn test_initialize_by_non_deployer() {
+  let env = Env::default();
+  env.mock_all_auths();
+
+  let deployer = Address::generate(&env);
+  let attacker = Address::generate(&env);
+  let governor = create_governor_contract(&env, &deployer);
+
+  let config = GovernorConfig {
+    vote_token: Address::generate(&env),
+    voting_period: 1000,
+    quorum_bps: 4000,
+    proposal_threshold: 0,
+    timelock_delay: 0,
+  };
+
+  // signing with another address does not help, the governor was not deployed from it
+  assert_eq!(
+    governor.try_initialize(&attacker, &salt(&env), &config),
+    Err(Ok(Error::NotDeployer))
+  );
+  assert_eq!(
+    governor.try_initialize(&attacker, &BytesN::from_array(&env, &[1; 32]), &config),
+    Err(Ok(Error::NotDeployer))
+  );
+
+  governor.initialize(&deployer, &salt(&env), &config);
+
+  assert_eq!(governor.get_config(), config);
+}
+
+// PROPOSAL LIFECYCLE
+#[test]
+fn test_proposal_lifecycle() {
+  let setup = setup();
+  let governor = &setup.governor;
+
+  let id = governor.propose(
+    &setup.alice,
+    &set_reward_fee_call(&setup, 1000),
+    &String::from_str(&setup.env, "Take 10% of the rewards as protocol fee"),
+  );
+
+  // voting opens after the snapshot
+  assert_eq!(
+    governor.try_cast_vote(&setup.alice, &id, &VoteType::For),
+    Err(Ok(Error::VotingClosed))
+  );
+
+  jump_to(&setup.env, 201);
+
+  assert_eq!(governor.cast_vote(&setup.alice, &id, &VoteType::For), 600);
+  assert_eq!(governor.cast_vote(&setup.bob, &id, &VoteType::Against), 300);
+  assert!(governor.has_voted(&id, &setup.alice));
+  assert_eq!(
+    governor.try_cast_vote(&setup.alice, &id, &VoteType::For),
+    Err(Ok(Error::AlreadyVoted))
+  );
+  assert_eq!(governor.state(&id), ProposalState::Active);
+
+  jump_to(&setup.env, 1201);
+
+  assert_eq!(governor.state(&id), ProposalState::Succeeded);
+  assert_eq!(governor.try_execute(&id), Err(Ok(Error::NotQueued)));
+
+  assert_eq!(governor.queue(&id), 1701);
+  assert_eq!(governor.state(&id), ProposalState::Queued);
+
+  jump_to(&setup.env, 1700);
+  assert_eq!(
+    governor.try_execute(&id),
+    Err(Ok(Error::TimelockNotExpired))
+  );
+
+  jump_to(&setup.env, 1701);
+  governor.execute(&id);
+
+  assert_eq!(governor.state(&id), ProposalState::Executed);
+  assert_eq!(setup.staking_pool.get_staking_state().reward_fee_bps, 1000);
+  assert_eq!(governor.try_execute(&id), Err(Ok(Error::NotQueued)));
+}
+
+#[test]
+fn test_proposal_and_receipt_ttl_are_extended() {
+  use soroban_sdk::testutils::storage::Persistent as _;
+
+  let setup = setup();
+  let governor = &setup.governor;
+
+  let id = governor.propose(
+    &setup.alice,
+    &set_reward_fee_call(&setup, 1000),
+    &String::from_str(&setup.env, "Take 10% of the rewards as protocol fee"),
+  );
+
+  jump_to(&setup.env, 201);
+  governor.cast_vote(&setup.bob, &id, &VoteType::For);
+
+  let ttl = |key: storage::GovernorRegistry| {
+    setup.env.as_contract(&governor.address, || {
+      setup.env.storage().persistent().get_ttl(&key)
+    })
+  };
+
+  assert_eq!(
+    ttl(storage::GovernorRegistry::Proposal(id)),
+    storage::PROPOSAL_BUMP_AMOUNT
+  );
+  assert_eq!(
+    ttl(storage::GovernorRegistry::Voted(id, setup.bob.clone())),
+    storage::PROPOSAL_BUMP_AMOUNT
+  );
+
+  // reading the receipt keeps it alive, otherwise bob could vote again once it is archived
+  setup.env.ledger().with_mut(|li| {
+    li.sequence_number += 2 * storage::DAY_IN_LEDGERS;
+  });
+  assert!(governor.has_voted(&id, &setup.bob));
+  assert_eq!(
+    ttl(storage::GovernorRegistry::Voted(id, setup.bob.clone())),
+    storage::PROPOSAL_BUMP_AMOUNT
+  );
+}
+
+#[test]
+fn test_votes_are_read_at_the_snapshot() {
+  let setup = setup();
+  let governor = &setup.governor;
+
+  let id = governor.propose(
+    &setup.alice,
+    &set_reward_fee_call(&setup, 1000),
+    &String::from_str(&setup.env, "snapshot"),
+  );
+
+  // stake bought after the proposal does not count
+  jump_to(&setup.env, 300);
+  setup.staking_pool.stake(&setup.carol, &900);
+
+  assert_eq!(
+    governor.cast_vote(&setup.carol, &id, &VoteType::Against),
+    100
+  );
+
+  let outsider = Address::generate(&setup.env);
+  setup.base_token.mint(&outsider, &1000);
+  setup.staking_pool.stake(&outsider, &1000);
+
+  assert_eq!(
+    governor.try_cast_vote(&outsider, &id, &VoteType::Against),
+    Err(Ok(Error::NoVotingPower))
+  );
+}
+
+#[test]
+fn test_proposal_defeated() {
+  let setup = setup();
+  let governor = &setup.governor;
+
+  // carol alone is below the 40% quorum
+  let below_quorum = governor.propose(
+    &setup.carol,
+    &set_reward_fee_call(&setup, 1000),
+    &String::from_str(&setup.env, "below quorum"),
+  );
+
+  // more against than for
+  let voted_down = governor.propose(
+    &setup.bob,
+    &set_reward_fee_call(&setup, 2000),
+    &String::from_str(&setup.env, "voted down"),
+  );
+
+  jump_to(&setup.env, 201);
+
+  governor.cast_vote(&setup.carol, &below_quorum, &VoteType::For);
+
+  governor.cast_vote(&setup.bob, &voted_down, &VoteType::For);
+  governor.cast_vote(&setup.alice, &voted_down, &VoteType::Against);
+
+  jump_to(&setup.env, 1201);
+
+  assert_eq!(governor.state(&below_quorum), ProposalState::Defeated);
+  assert_eq!(governor.state(&voted_down), ProposalState::Defeated);
+  assert_eq!(
+    governor.try_queue(&below_quorum),
+    Err(Ok(Error::NotSucceeded))
+  );
+  assert_eq!(
+    governor.try_queue(&voted_down),
+    Err(Ok(Error::NotSucceeded))
+  );
+  assert_eq!(
+    governor.try_cast_vote(&setup.bob, &below_quorum, &VoteType::For),
+    Err(Ok(Error::VotingClosed))
+  );
+}
+
+#[test]
+fn test_cancel_proposal() {
+  let setup = setup();
+  let governor = &setup.governor;
+
+  let id = governor.propose(
+    &setup.alice,
+    &set_reward_fee_call(&setup, 1000),
+    &String::from_str(&setup.env, "cancel me"),
+  );
+
+  assert_eq!(
+    governor.try_cancel(&setup.bob, &id),
+    Err(Ok(Error::NotProposer))
+  );
+
+  governor.cancel(&setup.alice, &id);
+
+  jump_to(&setup.env, 201);
+
+  assert_eq!(governor.state(&id), ProposalState::Canceled);
+  assert_eq!(
+    governor.try_cast_vote(&setup.alice, &id, &VoteType::For),
+    Err(Ok(Error::VotingClosed))
+  );
+}
+
+#[test]
+fn test_proposal_threshold() {
+  let setup = setup();
+
+  let nobody = Address::generate(&setup.env);
+
+  assert_eq!(
+    setup.governor.try_propose(
+      &nobody,
+      &set_reward_fee_call(&setup, 1000),
+      &String::from_str(&setup.env, "no votes"),
+    ),
+    Err(Ok(Error::BelowProposalThreshold))
+  );
+
+  assert_eq!(
+    setup.governor.try_propose(
+      &setup.alice,
+      &Vec::new(&setup.env),
+      &String::from_str(&setup.env, "no calls"),
+    ),
+    Err(Ok(Error::InvalidProposal))
+  );
+}
+
+#[test]
+fn test_failing_call_reverts_execute() {
+  let setup = setup();
+  let governor = &setup.governor;
+
+  // above the staking contract's maximum reward fee
+  let id = governor.propose(
+    &setup.alice,
+    &set_reward_fee_call(&setup, 5000),
+    &String::from_str(&setup.env, "fee too high"),
+  );
+
+  jump_to(&setup.env, 201);
+  governor.cast_vote(&setup.alice, &id, &VoteType::For);
+
+  jump_to(&setup.env, 1201);
+  governor.queue(&id);
+
+  jump_to(&setup.env, 1701);
+  assert!(governor.try_execute(&id).is_err());
+  assert_eq!(governor.state(&id), ProposalState::Queued);
+  assert_eq!(setup.staking_pool.get_staking_state().reward_fee_bps, 0);
+}
+
+#[test]
+fn test_governor_updates_its_own_config() {
+  let setup = setup();
+  let governor = &setup.governor;
+
+  let mut config = governor.get_config();
+  config.quorum_bps = 5000;
+
+  let id = governor.propose(
+    &setup.alice,
+    &vec![
+      &setup.env,
+      Call {
+        contract: governor.address.clone(),
+        function: Symbol::new(&setup.env, "update_config"),
+        args: (config.clone(),).into_val(&setup.env),
+      },
+    ],
+    &String::from_str(&setup.env, "raise quorum"),
+  );
+
+  jump_to(&setup.env, 201);
+  governor.cast_vote(&setup.alice, &id, &VoteType::For);
+
+  jump_to(&setup.env, 1201);
+  governor.queue(&id);
+
+  jump_to(&setup.env, 1701);
+  governor.execute(&id);
+
+  assert_eq!(governor.get_config(), config);
+
+  // nothing else can be called on the governor
+  assert_eq!(
+    governor.try_propose(
+      &setup.alice,
+      &vec![
+        &setup.env,
+        Call {
+          contract: governor.address.clone(),
+          function: Symbol::new(&setup.env, "initialize"),
+          args: (config.clone(),).into_val(&setup.env),
+        },
+      ],
+      &String::from_str(&setup.env, "reinitialize"),
+    ),
+    Err(Ok(Error::InvalidProposal))
+  );
+}
+
+#[test]
+fn test_governor_hands_ownership_back() {
+  let setup = setup();
+  let governor = &setup.governor;
+
+  let id = governor.propose(
+    &setup.alice,
+    &vec![
+      &setup.env,
+      Call {
+        contract: setup.staking_pool.address.clone(),
+        function: Symbol::new(&setup.env, "set_owner"),
+        args: (setup.owner.clone(), governor.address.clone()).into_val(&setup.env),
+      },
+    ],
+    &String::from_str(&setup.env, "hand ownership back"),
+  );
+
+  jump_to(&setup.env, 201);
+  governor.cast_vote(&setup.alice, &id, &VoteType::For);
+
+  jump_to(&setup.env, 1201);
+  governor.queue(&id);
+
+  jump_to(&setup.env, 1701);
+  governor.execute(&id);
+
+  assert_eq!(
+    setup.staking_pool.get_staking_state().pending_owner,
+    Some(setup.owner.clone())
+  );
+
+  setup.staking_pool.accept_owner(&setup.owner);
+
+  assert_eq!(setup.staking_pool.get_staking_state().owner, setup.owner);
+}
+
+#[test]
+fn test_ownership_handover_to_governor() {
+  let setup = setup();
+  let env = &setup.env;
+  let governor = &setup.governor;
+
+  let pool = staking::Client::new(env, &env.register_contract_wasm(None, staking::WASM));
+  pool.initialize(
+    &setup.base_token.address,
+    &create_token_contract(env, &setup.owner).address,
+    &setup.owner,
+    &env.deployer().upload_contract_wasm(share_token::WASM),
+  );
+
+  // only the old owner signs
+  env.mock_auths(&[MockAuth {
+    address: &setup.owner,
+    invoke: &MockAuthInvoke {
+      contract: &pool.address,
+      fn_name: "set_owner",
+      args: (governor.address.clone(), setup.owner.clone()).into_val(env),
+      sub_invokes: &[],
+    },
+  }]);
+  pool.set_owner(&governor.address, &setup.owner);
+
+  env.mock_all_auths();
+
+  let id = governor.propose(
+    &setup.alice,
+    &vec![
+      env,
+      Call {
+        contract: pool.address.clone(),
+        function: Symbol::new(env, "accept_owner"),
+        args: (governor.address.clone(),).into_val(env),
+      },
+    ],
+    &String::from_str(env, "take over the new pool"),
+  );
+
+  jump_to(env, 201);
+  governor.cast_vote(&setup.alice, &id, &VoteType::For);
+
+  jump_to(env, 1201);
+  governor.queue(&id);
+
+  jump_to(env, 1701);
+
+  // the governor accepts from its own call, no signature is mocked
+  env.set_auths(&[]);
+  governor.execute(&id);
+
+  let state = pool.get_staking_state();
+  assert_eq!(state.owner, governor.address);
+  assert_eq!(state.pending_owner, None);
+}
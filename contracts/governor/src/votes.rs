@@ -0,0 +1,10 @@
+#![allow(unused)]
+use soroban_sdk::{contractclient, Address, Env};
+
+// the part of the stXLM share token interface the governor reads voting power from
+#[contractclient(name = "VotesClient")]
+pub trait Votes {
+  fn get_votes(env: Env, account: Address) -> i128;
+  fn get_past_votes(env: Env, account: Address, timestamp: u64) -> i128;
+  fn get_past_total_supply(env: Env, timestamp: u64) -> i128;
+}
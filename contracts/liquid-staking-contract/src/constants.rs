@@ -4,3 +4,29 @@
 #![allow(unused)]
 
 pub const REWARD_RATE: i128 = 100;
+
+// fixed point precision used for reward per token and ratios (7 decimals, same as stXLM)
+pub const DECIMALS: i128 = reward_calculator::DECIMALS;
+
+pub const SECONDS_PER_YEAR: i128 = 365 * 24 * 60 * 60;
+
+pub const BPS_DENOMINATOR: i128 = reward_calculator::BPS_DENOMINATOR;
+
+// protocol fee on rewards can never go above 20%
+pub const MAX_REWARD_FEE_BPS: u32 = 2_000;
+
+// stake and unstake fees can never go above 5%
+pub const MAX_STAKE_FEE_BPS: u32 = 500;
+pub const MAX_UNSTAKE_FEE_BPS: u32 = 500;
+
+// early unstake penalty can never go above 10%
+pub const MAX_EARLY_UNSTAKE_PENALTY_BPS: u32 = 1_000;
+
+// exiting a reward vesting schedule early can never forfeit more than 50% of the unvested part
+pub const MAX_VESTING_EXIT_PENALTY_BPS: u32 = 5_000;
+
+// at least 10% of the staked base tokens always stay in the pool for instant unstakes
+pub const MAX_DEPLOY_BPS: u32 = 9_000;
+
+// keepers can never get more than 10% of a harvested gain
+pub const MAX_KEEPER_BOUNTY_BPS: u32 = 1_000;
@@ -1,6 +1,6 @@
-use crate::{constants, errors::Error, storage, token};
+use crate::{constants, errors::Error, storage, strategy, token};
 use core::panic;
-use soroban_sdk::{contract, contractimpl, Address, BytesN, Env, IntoVal, String};
+use soroban_sdk::{contract, contractimpl, Address, BytesN, Env, IntoVal, String, Vec};
 use storage::UserInfoRegistry;
 
 #[contract]
@@ -38,12 +38,15 @@ impl LiquidStakingContract {
       &7u32,
       &token_name,
       &token_symbol,
+      &None,
     );
 
     state.base_token = base_token;
     state.reward_token = reward_token;
     state.owner = owner.clone();
     state.share_token = token_contract;
+    state.treasury = owner.clone();
+    state.compliance = owner.clone();
     state.initialized = true;
 
     env
@@ -54,9 +57,10 @@ impl LiquidStakingContract {
     return Ok(state);
   }
 
+  // the new owner takes over once it calls accept_owner. A contract such as the governor or a
+  // timelock can only sign for calls it makes itself, so it could not co-sign this call
   pub fn set_owner(env: Env, new_owner: Address, current_owner: Address) -> Result<(), Error> {
     current_owner.require_auth();
-    new_owner.require_auth();
 
     let mut state = Self::get_staking_state(env.clone())
       .unwrap_or(storage::StorageClient::get_default_state(env.clone()));
@@ -69,7 +73,7 @@ impl LiquidStakingContract {
       return Err(Error::NotOwner);
     }
 
-    state.owner = new_owner;
+    state.pending_owner = Some(new_owner);
 
     env
       .storage()
@@ -79,299 +83,1716 @@ impl LiquidStakingContract {
     Ok(())
   }
 
-  pub fn add_reward_funds(env: Env, owner: Address, amount: i128) -> Result<(), Error> {
-    owner.require_auth();
+  pub fn accept_owner(env: Env, new_owner: Address) -> Result<(), Error> {
+    new_owner.require_auth();
 
-    let state = Self::get_staking_state(env.clone())
-      .unwrap_or(storage::StorageClient::get_default_state(env.clone()));
+    let mut state = Self::get_staking_state(env.clone())?;
 
-    if state.initialized == false {
-      return Err(Error::NotInitialized);
+    if state.pending_owner != Some(new_owner.clone()) {
+      return Err(Error::NotPendingOwner);
     }
 
+    state.owner = new_owner;
+    state.pending_owner = None;
+
+    env
+      .storage()
+      .instance()
+      .set(&storage::STAKING_STATE, &state);
+
+    Ok(())
+  }
+
+  // replaces the contract code, storage is kept
+  pub fn upgrade(env: Env, owner: Address, new_wasm_hash: BytesN<32>) -> Result<(), Error> {
+    owner.require_auth();
+
+    let state = Self::get_staking_state(env.clone())?;
+
     if state.owner != owner {
       return Err(Error::NotOwner);
     }
 
-    let reward_token = token::Client::new(&env, &state.reward_token);
-
-    reward_token.transfer(&owner, &env.current_contract_address(), &amount);
+    env.deployer().update_current_contract_wasm(new_wasm_hash);
 
-    return Ok(());
+    Ok(())
   }
 
-  pub fn earned(env: Env, user: Address) -> i128 {
-    let state = Self::get_staking_state(env.clone())
-      .unwrap_or(storage::StorageClient::get_default_state(env.clone()));
+  pub fn set_treasury(env: Env, owner: Address, treasury: Address) -> Result<(), Error> {
+    owner.require_auth();
 
-    if state.initialized == false {
-      return 0;
+    let mut state = Self::get_staking_state(env.clone())?;
+
+    if state.owner != owner {
+      return Err(Error::NotOwner);
     }
 
-    let key = UserInfoRegistry::UserRecord(user.clone());
+    state.treasury = treasury;
 
-    let user_info = env
+    env
       .storage()
       .instance()
-      .get(&key)
-      .unwrap_or(storage::StorageClient::get_default_user(env.clone(), user));
+      .set(&storage::STAKING_STATE, &state);
 
-    let current_balance = user_info.balance;
+    Ok(())
+  }
 
-    let amount_paid = user_info.rewards_per_token_paid;
+  pub fn set_reward_fee(env: Env, owner: Address, fee_bps: u32) -> Result<(), Error> {
+    owner.require_auth();
 
-    let current_reward_per_token = Self::reward_per_token(env.clone());
+    let mut state = Self::get_staking_state(env.clone())?;
 
-    let past_rewards = user_info.rewards_to_claim;
+    if state.owner != owner {
+      return Err(Error::NotOwner);
+    }
 
-    let decimals = 1e7 as i128;
+    if fee_bps > constants::MAX_REWARD_FEE_BPS {
+      return Err(Error::FeeTooHigh);
+    }
 
-    let earned =
-      ((current_balance * (current_reward_per_token - amount_paid)) / decimals) + past_rewards;
+    // rewards emitted so far are split with the old fee
+    Self::update_global_reward(env.clone());
 
-    return earned;
-  }
+    state.reward_fee_bps = fee_bps;
 
-  fn reward_per_token(env: Env) -> i128 {
-    let e = env.clone();
-    let state = env
+    env
       .storage()
       .instance()
-      .get(&storage::STAKING_STATE)
-      .unwrap_or(storage::StorageClient::get_default_state(e.clone()));
+      .set(&storage::STAKING_STATE, &state);
 
-    if state.initialized == false {
-      return 0;
-    }
+    Ok(())
+  }
 
-    let global_state = env
-      .storage()
-      .instance()
-      .get(&storage::STAKING_GLOBALS)
-      .unwrap_or(storage::StorageClient::get_default_global_state(e.clone()));
+  // sends the accrued protocol fees to the treasury, anyone can trigger it
+  pub fn collect_fees(env: Env) -> Result<i128, Error> {
+    let state = Self::get_staking_state(env.clone())?;
 
-    if global_state.token_supply == 0 {
-      return global_state.reward_per_token_stored;
-    } else {
-      let current_timestamp = e.ledger().timestamp() as i128;
-      let last_timestamp = global_state.last_updated_time as i128;
+    let mut global_state = Self::update_global_reward(env.clone());
 
-      let decimals = 1e7 as i128;
+    let fees = global_state.protocol_fees;
 
-      return global_state.reward_per_token_stored
-        + (((current_timestamp - last_timestamp) * constants::REWARD_RATE * decimals)
-          / global_state.token_supply);
+    if fees == 0 {
+      return Ok(0);
     }
-  }
-
-  fn update_reward(env: Env, user: Address) {
-    let mut global_state = env
-      .storage()
-      .instance()
-      .get(&storage::STAKING_GLOBALS)
-      .unwrap_or(storage::StorageClient::get_default_global_state(
-        env.clone(),
-      ));
-
-    global_state.reward_per_token_stored = Self::reward_per_token(env.clone());
-    global_state.last_updated_time = env.ledger().timestamp();
-
-    let key = UserInfoRegistry::UserRecord(user.clone());
-    let mut user_info =
-      env
-        .storage()
-        .instance()
-        .get(&key)
-        .unwrap_or(storage::StorageClient::get_default_user(
-          env.clone(),
-          user.clone(),
-        ));
 
-    user_info.rewards_to_claim = Self::earned(env.clone(), user); // TODO: Add Earned function and call it here
-    user_info.rewards_per_token_paid = global_state.reward_per_token_stored;
+    if Self::reward_balance(env.clone(), &state) < fees {
+      return Err(Error::InsufficientRewardFunds);
+    }
 
-    env.storage().instance().set(&key, &user_info);
+    global_state.protocol_fees = 0;
 
     env
       .storage()
       .instance()
       .set(&storage::STAKING_GLOBALS, &global_state);
+
+    let reward_token = token::Client::new(&env, &state.reward_token);
+
+    reward_token.transfer(&env.current_contract_address(), &state.treasury, &fees);
+
+    Ok(fees)
   }
 
-  pub fn unstake(env: Env, user: Address, amount: i128) -> Result<(), Error> {
-    user.require_auth();
+  pub fn accrued_protocol_fees(env: Env) -> Result<i128, Error> {
+    let state = Self::get_staking_state(env.clone())?;
 
-    Self::update_reward(env.clone(), user.clone());
+    let global_state = Self::get_global_state(env.clone());
 
-    if amount < 0 {
-      return Err(Error::InvalidAmount);
-    }
+    Ok(Self::accrued_global_state(env.clone(), &state, global_state).protocol_fees)
+  }
 
-    let state = Self::get_staking_state(env.clone())
-      .unwrap_or(storage::StorageClient::get_default_state(env.clone()));
+  pub fn set_stake_fees(
+    env: Env,
+    owner: Address,
+    stake_fee_bps: u32,
+    unstake_fee_bps: u32,
+    redistribute_fees: bool,
+  ) -> Result<(), Error> {
+    owner.require_auth();
 
-    if state.initialized == false {
-      return Err(Error::NotInitialized);
-    }
+    let mut state = Self::get_staking_state(env.clone())?;
 
-    let share_token_client = token::Client::new(&env, &state.share_token);
+    if state.owner != owner {
+      return Err(Error::NotOwner);
+    }
 
-    if share_token_client.balance(&user) < amount {
-      return Err(Error::NotEnoughFunds);
+    if stake_fee_bps > constants::MAX_STAKE_FEE_BPS
+      || unstake_fee_bps > constants::MAX_UNSTAKE_FEE_BPS
+    {
+      return Err(Error::FeeTooHigh);
     }
 
-    let base_token_client = token::Client::new(&env, &state.base_token);
+    state.stake_fee_bps = stake_fee_bps;
+    state.unstake_fee_bps = unstake_fee_bps;
+    state.redistribute_fees = redistribute_fees;
 
-    let mut global_state = env
+    env
       .storage()
       .instance()
-      .get(&storage::STAKING_GLOBALS)
-      .unwrap_or(storage::StorageClient::get_default_global_state(
-        env.clone(),
-      ));
+      .set(&storage::STAKING_STATE, &state);
 
-    let key = UserInfoRegistry::UserRecord(user.clone());
+    Ok(())
+  }
 
-    let mut user_record = env.storage().instance().get(&key).unwrap_or({
-      storage::UserRecord {
-        balance: 0,
-        rewards_per_token_paid: 0,
-        rewards_to_claim: 0,
-        address: user.clone(),
-      }
-    });
+  pub fn set_early_unstake_penalty(
+    env: Env,
+    owner: Address,
+    penalty_bps: u32,
+    window: u64,
+  ) -> Result<(), Error> {
+    owner.require_auth();
 
-    if user_record.balance < amount {
-      return Err(Error::NotEnoughFunds);
+    let mut state = Self::get_staking_state(env.clone())?;
+
+    if state.owner != owner {
+      return Err(Error::NotOwner);
     }
 
-    user_record.balance -= amount;
-    global_state.token_supply -= amount;
+    if penalty_bps > constants::MAX_EARLY_UNSTAKE_PENALTY_BPS {
+      return Err(Error::FeeTooHigh);
+    }
 
-    env.storage().instance().set(&key, &user_record);
+    state.early_unstake_penalty_bps = penalty_bps;
+    state.early_unstake_window = window;
 
     env
       .storage()
       .instance()
-      .set(&storage::STAKING_GLOBALS, &global_state);
+      .set(&storage::STAKING_STATE, &state);
 
-    Self::update_reward(env.clone(), user.clone());
+    Ok(())
+  }
 
-    share_token_client.burn(&user, &amount);
+  // zero disables the per user or the pool wide cap
+  pub fn set_stake_limits(
+    env: Env,
+    owner: Address,
+    min_stake: i128,
+    max_stake_per_user: i128,
+    pool_cap: i128,
+  ) -> Result<(), Error> {
+    owner.require_auth();
 
-    base_token_client.transfer(&env.current_contract_address(), &user, &amount);
+    let mut state = Self::get_staking_state(env.clone())?;
 
-    if user_record.balance == 0 {
-      env.storage().instance().remove(&key);
+    if state.owner != owner {
+      return Err(Error::NotOwner);
+    }
+
+    if min_stake < 0 || max_stake_per_user < 0 || pool_cap < 0 {
+      return Err(Error::InvalidAmount);
     }
 
+    state.min_stake = min_stake;
+    state.max_stake_per_user = max_stake_per_user;
+    state.pool_cap = pool_cap;
+
+    env
+      .storage()
+      .instance()
+      .set(&storage::STAKING_STATE, &state);
+
     Ok(())
   }
 
-  pub fn stake(env: Env, user: Address, amount: i128) -> Result<(), Error> {
-    user.require_auth();
-
-    Self::update_reward(env.clone(), user.clone());
+  // how much more can be staked before the pool cap is reached, none when the pool is uncapped
+  pub fn remaining_capacity(env: Env) -> Result<Option<i128>, Error> {
+    let state = Self::get_staking_state(env.clone())?;
 
-    if amount < 0 {
-      return Err(Error::InvalidAmount);
+    if state.pool_cap == 0 {
+      return Ok(None);
     }
-    let state = Self::get_staking_state(env.clone())
-      .unwrap_or(storage::StorageClient::get_default_state(env.clone()));
 
-    if state.initialized == false {
-      return Err(Error::NotInitialized);
-    }
+    let global_state = Self::get_global_state(env.clone());
 
-    let share_token_client = token::Client::new(&env, &state.share_token);
+    Ok(Some((state.pool_cap - global_state.token_supply).max(0)))
+  }
 
-    let base_token_client = token::Client::new(&env, &state.base_token);
+  pub fn set_compliance(env: Env, owner: Address, compliance: Address) -> Result<(), Error> {
+    owner.require_auth();
 
-    if base_token_client.balance(&user) < amount {
-      return Err(Error::NotEnoughFunds);
+    let mut state = Self::get_staking_state(env.clone())?;
+
+    if state.owner != owner {
+      return Err(Error::NotOwner);
     }
 
-    let mut global_state = Self::get_global_state(env.clone());
+    state.compliance = compliance;
 
-    let key = UserInfoRegistry::UserRecord(user.clone());
+    env
+      .storage()
+      .instance()
+      .set(&storage::STAKING_STATE, &state);
 
-    let mut user_record =
-      env
-        .storage()
-        .instance()
-        .get(&key)
-        .unwrap_or(storage::StorageClient::get_default_user(
-          env.clone(),
-          user.clone(),
-        ));
+    Ok(())
+  }
+
+  // when enabled only allowlisted addresses can stake or receive stXLM, unstaking is never gated
+  pub fn set_allowlist_enabled(env: Env, owner: Address, enabled: bool) -> Result<(), Error> {
+    owner.require_auth();
+
+    let mut state = Self::get_staking_state(env.clone())?;
+
+    if state.owner != owner {
+      return Err(Error::NotOwner);
+    }
 
-    global_state.token_supply += amount;
-    user_record.balance += amount;
+    state.allowlist_enabled = enabled;
 
     env
       .storage()
       .instance()
-      .set(&storage::STAKING_GLOBALS, &global_state);
+      .set(&storage::STAKING_STATE, &state);
 
-    env.storage().instance().set(&key, &user_record);
+    let share_token_client = token::Client::new(&env, &state.share_token);
 
-    base_token_client.transfer(&user, &env.current_contract_address(), &amount);
-    share_token_client.mint(&user, &amount);
+    share_token_client.set_allowlist_enabled(&enabled);
 
-    return Ok(());
+    Ok(())
   }
 
-  pub fn claim_rewards(env: Env, user: Address) -> Result<(), Error> {
-    user.require_auth();
+  pub fn add_to_allowlist(env: Env, caller: Address, user: Address) -> Result<(), Error> {
+    Self::add_to_allowlist_batch(env.clone(), caller, Vec::from_array(&env, [user]))
+  }
 
-    Self::update_reward(env.clone(), user.clone()); // update rewards before claim
+  pub fn remove_from_allowlist(env: Env, caller: Address, user: Address) -> Result<(), Error> {
+    Self::remove_from_allowlist_batch(env.clone(), caller, Vec::from_array(&env, [user]))
+  }
 
-    let state = Self::get_staking_state(env.clone())
-      .unwrap_or(storage::StorageClient::get_default_state(env.clone()));
+  pub fn add_to_allowlist_batch(
+    env: Env,
+    caller: Address,
+    users: Vec<Address>,
+  ) -> Result<(), Error> {
+    Self::write_allowlist(env, caller, users, true)
+  }
 
-    if state.initialized == false {
-      return Err(Error::NotInitialized);
-    }
+  pub fn remove_from_allowlist_batch(
+    env: Env,
+    caller: Address,
+    users: Vec<Address>,
+  ) -> Result<(), Error> {
+    Self::write_allowlist(env, caller, users, false)
+  }
 
-    let reward_token = token::Client::new(&env, &state.reward_token);
+  // the share token keeps the allowlist, the pool only writes to it as its admin
+  pub fn is_allowlisted(env: Env, user: Address) -> Result<bool, Error> {
+    let state = Self::get_staking_state(env.clone())?;
 
-    let key = UserInfoRegistry::UserRecord(user.clone());
+    let share_token_client = token::Client::new(&env, &state.share_token);
 
-    let mut user_record = env.storage().instance().get(&key).unwrap_or({
-      storage::UserRecord {
-        balance: 0,
-        rewards_per_token_paid: 0,
-        rewards_to_claim: 0,
-        address: user.clone(),
-      }
-    });
+    Ok(share_token_client.allowlisted(&user))
+  }
 
-    if user_record.rewards_to_claim == 0 {
-      return Err(Error::ThereIsNoRewardToClaim);
-    }
+  // the owner and the compliance role can both manage the allowlist
+  fn write_allowlist(
+    env: Env,
+    caller: Address,
+    users: Vec<Address>,
+    allowed: bool,
+  ) -> Result<(), Error> {
+    caller.require_auth();
 
-    reward_token.transfer(
-      &env.current_contract_address(),
-      &user,
-      &user_record.rewards_to_claim,
-    );
+    let state = Self::get_staking_state(env.clone())?;
 
-    user_record.rewards_to_claim = 0;
+    if caller != state.owner && caller != state.compliance {
+      return Err(Error::Unauthorized);
+    }
 
-    if user_record.balance == 0 {
-      env.storage().instance().remove(&key);
+    let share_token_client = token::Client::new(&env, &state.share_token);
+
+    for user in users.iter() {
+      share_token_client.set_allowlisted(&user, &allowed);
     }
 
     Ok(())
   }
 
-  pub fn get_user_position(env: Env, user: Address) -> storage::UserRecord {
-    user.require_auth();
+  // the pool is the stXLM admin, freezing goes through it. A frozen holder can neither move,
+  // receive nor burn stXLM, so it cannot stake or unstake either until unfrozen
+  pub fn freeze_shares(env: Env, owner: Address, user: Address) -> Result<(), Error> {
+    Self::write_frozen(env, owner, user, true)
+  }
 
-    let key = UserInfoRegistry::UserRecord(user.clone());
+  pub fn unfreeze_shares(env: Env, owner: Address, user: Address) -> Result<(), Error> {
+    Self::write_frozen(env, owner, user, false)
+  }
 
-    let user_record = env
-      .storage()
+  fn write_frozen(env: Env, owner: Address, user: Address, frozen: bool) -> Result<(), Error> {
+    owner.require_auth();
+
+    let state = Self::get_staking_state(env.clone())?;
+
+    if state.owner != owner {
+      return Err(Error::NotOwner);
+    }
+
+    let share_token_client = token::Client::new(&env, &state.share_token);
+
+    if frozen {
+      share_token_client.freeze(&user);
+    } else {
+      share_token_client.unfreeze(&user);
+    }
+
+    Ok(())
+  }
+
+  // emergency stop for the user facing flows. The owner can still rebalance, so funds can be pulled
+  // back from a strategy while paused, and clawback and freezing keep working
+  pub fn set_paused(env: Env, owner: Address, paused: bool) -> Result<(), Error> {
+    owner.require_auth();
+
+    let mut state = Self::get_staking_state(env.clone())?;
+
+    if state.owner != owner {
+      return Err(Error::NotOwner);
+    }
+
+    state.paused = paused;
+
+    env
+      .storage()
+      .instance()
+      .set(&storage::STAKING_STATE, &state);
+
+    Ok(())
+  }
+
+  pub fn add_reward_funds(env: Env, owner: Address, amount: i128) -> Result<(), Error> {
+    owner.require_auth();
+
+    let state = Self::get_staking_state(env.clone())
+      .unwrap_or(storage::StorageClient::get_default_state(env.clone()));
+
+    if state.initialized == false {
+      return Err(Error::NotInitialized);
+    }
+
+    if state.owner != owner {
+      return Err(Error::NotOwner);
+    }
+
+    if amount < 0 {
+      return Err(Error::InvalidAmount);
+    }
+
+    // settle emissions at the old reserve before the new funds can be emitted
+    let mut global_state = Self::update_global_reward(env.clone());
+
+    let reward_token = token::Client::new(&env, &state.reward_token);
+
+    reward_token.transfer(&owner, &env.current_contract_address(), &amount);
+
+    global_state.reward_reserve += amount;
+
+    env
+      .storage()
+      .instance()
+      .set(&storage::STAKING_GLOBALS, &global_state);
+
+    return Ok(());
+  }
+
+  pub fn recover_token(
+    env: Env,
+    owner: Address,
+    token: Address,
+    amount: i128,
+    to: Address,
+  ) -> Result<(), Error> {
+    owner.require_auth();
+
+    let state = Self::get_staking_state(env.clone())?;
+
+    if state.owner != owner {
+      return Err(Error::NotOwner);
+    }
+
+    if amount < 0 {
+      return Err(Error::InvalidAmount);
+    }
+
+    // settle emissions so committed rewards include everything owed up to now
+    let mut global_state = Self::update_global_reward(env.clone());
+
+    let token_client = token::Client::new(&env, &token);
+
+    let balance = token_client.balance(&env.current_contract_address());
+
+    let mut protected = 0;
+
+    if token == state.base_token {
+      let strategy_state = Self::get_strategy_state(env.clone());
+
+      // principal deployed to the strategy or lost by it is not part of the balance
+      protected += global_state.token_supply - strategy_state.deployed - strategy_state.loss
+        + global_state.redistributed_fees;
+    }
+
+    if token == state.reward_token {
+      protected += global_state.committed_rewards + global_state.protocol_fees;
+
+      // unemitted rewards can be taken back, but only after any untracked surplus
+      let surplus = balance - protected - global_state.reward_reserve;
+
+      if amount > surplus.max(0) {
+        let from_reserve = (amount - surplus.max(0)).min(global_state.reward_reserve);
+
+        global_state.reward_reserve -= from_reserve;
+      }
+    }
+
+    if balance - amount < protected {
+      return Err(Error::ExceedsRecoverableBalance);
+    }
+
+    env
+      .storage()
+      .instance()
+      .set(&storage::STAKING_GLOBALS, &global_state);
+
+    token_client.transfer(&env.current_contract_address(), &to, &amount);
+
+    Ok(())
+  }
+
+  // claws stXLM back from a holder and releases the matching staked base tokens to `to`, keeping
+  // the user record and the token supply in line with the share token. Only stXLM backed by the
+  // holder's own staked balance can be clawed back here, stXLM that reached `from` by transfer
+  // has no user record behind it and is refused with NotEnoughFunds, freeze that holder instead
+  pub fn clawback(
+    env: Env,
+    owner: Address,
+    from: Address,
+    amount: i128,
+    to: Address,
+  ) -> Result<(), Error> {
+    owner.require_auth();
+
+    let state = Self::get_staking_state(env.clone())?;
+
+    if state.owner != owner {
+      return Err(Error::NotOwner);
+    }
+
+    if amount <= 0 {
+      return Err(Error::InvalidAmount);
+    }
+
+    Self::update_reward(env.clone(), from.clone());
+
+    let share_token_client = token::Client::new(&env, &state.share_token);
+
+    let key = UserInfoRegistry::UserRecord(from.clone());
+
+    let mut user_record = Self::get_user_position(env.clone(), from.clone());
+
+    if user_record.balance < amount || share_token_client.balance(&from) < amount {
+      return Err(Error::NotEnoughFunds);
+    }
+
+    let mut global_state = Self::get_global_state(env.clone());
+
+    Self::ensure_liquidity(env.clone(), &global_state, amount)?;
+
+    user_record.balance -= amount;
+    global_state.token_supply -= amount;
+
+    env.storage().instance().set(&key, &user_record);
+
+    env
+      .storage()
+      .instance()
+      .set(&storage::STAKING_GLOBALS, &global_state);
+
+    share_token_client.clawback(&from, &amount);
+
+    let base_token_client = token::Client::new(&env, &state.base_token);
+
+    base_token_client.transfer(&env.current_contract_address(), &to, &amount);
+
+    Ok(())
+  }
+
+  // moves everything out of the current strategy before switching, `None` stops deploying. A
+  // loss of the current strategy is written off first, so whatever is left can still be withdrawn
+  pub fn set_strategy(
+    env: Env,
+    owner: Address,
+    strategy: Option<Address>,
+    deploy_bps: u32,
+  ) -> Result<(), Error> {
+    owner.require_auth();
+
+    let state = Self::get_staking_state(env.clone())?;
+
+    if state.owner != owner {
+      return Err(Error::NotOwner);
+    }
+
+    if deploy_bps > constants::MAX_DEPLOY_BPS {
+      return Err(Error::DeployRatioTooHigh);
+    }
+
+    let mut strategy_state = Self::get_strategy_state(env.clone());
+
+    if strategy_state.strategy.is_some() && strategy_state.strategy != strategy {
+      Self::harvest(env.clone())?;
+
+      strategy_state = Self::get_strategy_state(env.clone());
+
+      Self::write_down(env.clone(), &mut strategy_state)?;
+
+      let deployed = strategy_state.deployed;
+
+      Self::withdraw_from_strategy(env.clone(), &state, &mut strategy_state, deployed)?;
+    }
+
+    strategy_state.strategy = strategy;
+    strategy_state.deploy_bps = deploy_bps;
+
+    env
+      .storage()
+      .instance()
+      .set(&storage::STRATEGY_STATE, &strategy_state);
+
+    Ok(())
+  }
+
+  // moves staked base tokens in or out of the strategy until it holds its target share
+  pub fn rebalance(env: Env) -> Result<i128, Error> {
+    let state = Self::get_staking_state(env.clone())?;
+
+    let mut strategy_state = Self::get_strategy_state(env.clone());
+
+    let strategy = strategy_state
+      .strategy
+      .clone()
+      .ok_or(Error::StrategyNotSet)?;
+
+    let global_state = Self::get_global_state(env.clone());
+
+    let target = (global_state.token_supply - strategy_state.loss)
+      * strategy_state.deploy_bps as i128
+      / constants::BPS_DENOMINATOR;
+
+    if target > strategy_state.deployed {
+      let amount = target - strategy_state.deployed;
+
+      let base_token_client = token::Client::new(&env, &state.base_token);
+
+      base_token_client.transfer(&env.current_contract_address(), &strategy, &amount);
+
+      strategy::Client::new(&env, &strategy).deposit(&amount);
+
+      strategy_state.deployed += amount;
+    } else if target < strategy_state.deployed {
+      let amount = strategy_state.deployed - target;
+
+      Self::withdraw_from_strategy(env.clone(), &state, &mut strategy_state, amount)?;
+    }
+
+    env
+      .storage()
+      .instance()
+      .set(&storage::STRATEGY_STATE, &strategy_state);
+
+    Ok(strategy_state.deployed)
+  }
+
+  // pulls the strategy's gains back to the pool and shares them between the stakers, like
+  // redistributed fees they are paid in base tokens by claim_rewards
+  pub fn harvest(env: Env) -> Result<i128, Error> {
+    let (gain, _) = Self::realize_gain(env, None)?;
+
+    Ok(gain)
+  }
+
+  // settles the pending emission so reward_per_token_stored keeps up even when nobody stakes or
+  // claims, and harvests the strategy paying the keeper its bounty out of the gain
+  pub fn checkpoint(env: Env, keeper: Address) -> Result<i128, Error> {
+    keeper.require_auth();
+
+    Self::get_staking_state(env.clone())?;
+
+    Self::update_global_reward(env.clone());
+
+    if Self::get_strategy_state(env.clone()).strategy.is_none() {
+      return Ok(0);
+    }
+
+    let (_, bounty) = Self::realize_gain(env, Some(keeper))?;
+
+    Ok(bounty)
+  }
+
+  pub fn set_keeper_bounty(env: Env, owner: Address, bounty_bps: u32) -> Result<(), Error> {
+    owner.require_auth();
+
+    let state = Self::get_staking_state(env.clone())?;
+
+    if state.owner != owner {
+      return Err(Error::NotOwner);
+    }
+
+    if bounty_bps > constants::MAX_KEEPER_BOUNTY_BPS {
+      return Err(Error::FeeTooHigh);
+    }
+
+    let mut strategy_state = Self::get_strategy_state(env.clone());
+
+    strategy_state.keeper_bounty_bps = bounty_bps;
+
+    env
+      .storage()
+      .instance()
+      .set(&storage::STRATEGY_STATE, &strategy_state);
+
+    Ok(())
+  }
+
+  // writes the principal the strategy lost off, the stakers at that moment bear it in proportion
+  // to their balance. Left to the owner, a temporary drop of the strategy should not be final
+  pub fn realize_loss(env: Env, owner: Address) -> Result<i128, Error> {
+    owner.require_auth();
+
+    let state = Self::get_staking_state(env.clone())?;
+
+    if state.owner != owner {
+      return Err(Error::NotOwner);
+    }
+
+    let mut strategy_state = Self::get_strategy_state(env.clone());
+
+    let loss = Self::write_down(env.clone(), &mut strategy_state)?;
+
+    env
+      .storage()
+      .instance()
+      .set(&storage::STRATEGY_STATE, &strategy_state);
+
+    Ok(loss)
+  }
+
+  // lowers the deployed principal to what the strategy still holds, each staker's balance is
+  // reduced by its share of the difference the next time it is updated
+  fn write_down(env: Env, strategy_state: &mut storage::StrategyState) -> Result<i128, Error> {
+    let strategy = strategy_state
+      .strategy
+      .clone()
+      .ok_or(Error::StrategyNotSet)?;
+
+    let total_assets = strategy::Client::new(&env, &strategy).total_assets();
+
+    let loss = strategy_state.deployed - total_assets;
+
+    if loss <= 0 {
+      return Ok(0);
+    }
+
+    let mut global_state = Self::get_global_state(env.clone());
+
+    let supply = global_state.token_supply;
+
+    // rounded up, the balances never end up backed by more than the pool holds
+    global_state.loss_per_token_stored += (loss * constants::DECIMALS + supply - 1) / supply;
+
+    strategy_state.deployed -= loss;
+    strategy_state.loss += loss;
+
+    env
+      .storage()
+      .instance()
+      .set(&storage::STAKING_GLOBALS, &global_state);
+
+    Ok(loss)
+  }
+
+  // returns the harvested gain and the part of it paid to the keeper, if there is one
+  fn realize_gain(env: Env, keeper: Option<Address>) -> Result<(i128, i128), Error> {
+    let state = Self::get_staking_state(env.clone())?;
+
+    let strategy_state = Self::get_strategy_state(env.clone());
+
+    let strategy = strategy_state
+      .strategy
+      .clone()
+      .ok_or(Error::StrategyNotSet)?;
+
+    let total_assets = strategy::Client::new(&env, &strategy).total_assets();
+
+    // a loss stays accounted as deployed until the owner calls realize_loss
+    let gain = total_assets - strategy_state.deployed;
+
+    if gain <= 0 {
+      return Ok((0, 0));
+    }
+
+    Self::pull_from_strategy(env.clone(), &state, &strategy, gain)?;
+
+    let bounty = match keeper {
+      Some(keeper) => {
+        let bounty = (gain * strategy_state.keeper_bounty_bps as i128) / constants::BPS_DENOMINATOR;
+
+        if bounty > 0 {
+          let base_token_client = token::Client::new(&env, &state.base_token);
+
+          base_token_client.transfer(&env.current_contract_address(), &keeper, &bounty);
+        }
+
+        bounty
+      }
+      None => 0,
+    };
+
+    let mut global_state = Self::get_global_state(env.clone());
+
+    Self::route_base_fee(
+      env.clone(),
+      &state,
+      &mut global_state,
+      gain - bounty,
+      true,
+      None,
+    );
+
+    env
+      .storage()
+      .instance()
+      .set(&storage::STAKING_GLOBALS, &global_state);
+
+    Ok((gain, bounty))
+  }
+
+  pub fn get_strategy_state(env: Env) -> storage::StrategyState {
+    env
+      .storage()
+      .instance()
+      .get(&storage::STRATEGY_STATE)
+      .unwrap_or(storage::StorageClient::get_default_strategy_state(
+        env.clone(),
+      ))
+  }
+
+  // makes sure `amount` of staked principal is in the pool, pulling the missing part from the
+  // strategy when the liquidity buffer is not enough
+  fn ensure_liquidity(
+    env: Env,
+    global_state: &storage::StakingGlobals,
+    amount: i128,
+  ) -> Result<(), Error> {
+    let mut strategy_state = Self::get_strategy_state(env.clone());
+
+    let held = global_state.token_supply - strategy_state.deployed - strategy_state.loss;
+
+    if amount <= held {
+      return Ok(());
+    }
+
+    let state = Self::get_staking_state(env.clone())?;
+
+    Self::withdraw_from_strategy(env.clone(), &state, &mut strategy_state, amount - held)?;
+
+    env
+      .storage()
+      .instance()
+      .set(&storage::STRATEGY_STATE, &strategy_state);
+
+    Ok(())
+  }
+
+  fn withdraw_from_strategy(
+    env: Env,
+    state: &storage::StakingContractState,
+    strategy_state: &mut storage::StrategyState,
+    amount: i128,
+  ) -> Result<(), Error> {
+    if amount == 0 {
+      return Ok(());
+    }
+
+    let strategy = strategy_state
+      .strategy
+      .clone()
+      .ok_or(Error::StrategyNotSet)?;
+
+    Self::pull_from_strategy(env.clone(), state, &strategy, amount)?;
+
+    strategy_state.deployed -= amount;
+
+    Ok(())
+  }
+
+  // the strategy is not trusted to send what it was asked for, the balance has to go up
+  fn pull_from_strategy(
+    env: Env,
+    state: &storage::StakingContractState,
+    strategy: &Address,
+    amount: i128,
+  ) -> Result<(), Error> {
+    let base_token_client = token::Client::new(&env, &state.base_token);
+
+    let balance_before = base_token_client.balance(&env.current_contract_address());
+
+    strategy::Client::new(&env, strategy).withdraw(&amount);
+
+    let received = base_token_client.balance(&env.current_contract_address()) - balance_before;
+
+    if received < amount {
+      return Err(Error::NotEnoughFunds);
+    }
+
+    Ok(())
+  }
+
+  // base tokens from redistributed stake and unstake fees that the user can claim
+  pub fn earned_fees(env: Env, user: Address) -> i128 {
+    let global_state = Self::get_global_state(env.clone());
+
+    let user_info = Self::get_user_position(env.clone(), user);
+
+    ((user_info.balance * (global_state.fee_per_token_stored - user_info.fee_per_token_paid))
+      / constants::DECIMALS)
+      + user_info.fees_to_claim
+  }
+
+  pub fn earned(env: Env, user: Address) -> i128 {
+    let state = Self::get_staking_state(env.clone())
+      .unwrap_or(storage::StorageClient::get_default_state(env.clone()));
+
+    if state.initialized == false {
+      return 0;
+    }
+
+    let key = UserInfoRegistry::UserRecord(user.clone());
+
+    let user_info = env
+      .storage()
+      .instance()
+      .get(&key)
+      .unwrap_or(storage::StorageClient::get_default_user(env.clone(), user));
+
+    reward_calculator::earned(
+      user_info.balance,
+      Self::reward_per_token(env.clone()),
+      user_info.rewards_per_token_paid,
+      user_info.rewards_to_claim,
+    )
+  }
+
+  fn reward_per_token(env: Env) -> i128 {
+    let e = env.clone();
+    let state = env
+      .storage()
+      .instance()
+      .get(&storage::STAKING_STATE)
+      .unwrap_or(storage::StorageClient::get_default_state(e.clone()));
+
+    if state.initialized == false {
+      return 0;
+    }
+
+    let global_state = env
+      .storage()
+      .instance()
+      .get(&storage::STAKING_GLOBALS)
+      .unwrap_or(storage::StorageClient::get_default_global_state(e.clone()));
+
+    Self::accrued_global_state(e.clone(), &state, global_state).reward_per_token_stored
+  }
+
+  // share of an emission that goes to the treasury instead of the stakers, plus the new rounding
+  // remainder
+  fn protocol_fee(
+    state: &storage::StakingContractState,
+    global_state: &storage::StakingGlobals,
+    emission: i128,
+  ) -> (i128, i128) {
+    reward_calculator::protocol_fee(emission, state.reward_fee_bps, global_state.fee_remainder)
+  }
+
+  // the globals with the pending emission settled, without writing them. What rounding drops is
+  // carried in the remainders, so settling more often (checkpoint) does not change what is earned
+  fn accrued_global_state(
+    env: Env,
+    state: &storage::StakingContractState,
+    mut global_state: storage::StakingGlobals,
+  ) -> storage::StakingGlobals {
+    let emission = Self::pending_emission(env.clone(), &global_state);
+    let (fee, fee_remainder) = Self::protocol_fee(state, &global_state, emission);
+
+    let (reward_per_token, reward_remainder) = reward_calculator::reward_per_token(
+      global_state.reward_per_token_stored,
+      global_state.token_supply,
+      emission - fee,
+      global_state.reward_remainder,
+    );
+
+    global_state.reward_per_token_stored = reward_per_token;
+    global_state.reward_remainder = reward_remainder;
+    global_state.fee_remainder = fee_remainder;
+    global_state.reward_reserve -= emission;
+    global_state.committed_rewards += emission - fee;
+    global_state.protocol_fees += fee;
+    global_state.last_updated_time = env.ledger().timestamp();
+
+    global_state
+  }
+
+  // rewards emitted since the last update, capped at the funded reserve
+  fn pending_emission(env: Env, global_state: &storage::StakingGlobals) -> i128 {
+    reward_calculator::pending_emission(
+      global_state.token_supply,
+      global_state.reward_reserve,
+      global_state.last_updated_time,
+      env.ledger().timestamp(),
+      constants::REWARD_RATE,
+    )
+  }
+
+  fn update_global_reward(env: Env) -> storage::StakingGlobals {
+    let state = env
+      .storage()
+      .instance()
+      .get(&storage::STAKING_STATE)
+      .unwrap_or(storage::StorageClient::get_default_state(env.clone()));
+
+    let global_state =
+      Self::accrued_global_state(env.clone(), &state, Self::get_global_state(env.clone()));
+
+    env
+      .storage()
+      .instance()
+      .set(&storage::STAKING_GLOBALS, &global_state);
+
+    global_state
+  }
+
+  fn update_reward(env: Env, user: Address) {
+    let mut global_state = Self::update_global_reward(env.clone());
+
+    let key = UserInfoRegistry::UserRecord(user.clone());
+    let mut user_info =
+      env
+        .storage()
+        .instance()
+        .get(&key)
+        .unwrap_or(storage::StorageClient::get_default_user(
+          env.clone(),
+          user.clone(),
+        ));
+
+    user_info.rewards_to_claim = Self::earned(env.clone(), user.clone());
+    user_info.rewards_per_token_paid = global_state.reward_per_token_stored;
+
+    user_info.fees_to_claim = Self::earned_fees(env.clone(), user.clone());
+    user_info.fee_per_token_paid = global_state.fee_per_token_stored;
+
+    let loss = Self::pending_loss(&global_state, &user_info);
+    user_info.loss_per_token_paid = global_state.loss_per_token_stored;
+
+    if loss > 0 {
+      user_info.balance -= loss;
+
+      Self::settle_loss(env.clone(), &mut global_state, &user, loss);
+    }
+
+    env.storage().instance().set(&key, &user_info);
+  }
+
+  // part of the user's balance written off since it was last updated
+  fn pending_loss(global_state: &storage::StakingGlobals, user_info: &storage::UserRecord) -> i128 {
+    let loss =
+      user_info.balance * (global_state.loss_per_token_stored - user_info.loss_per_token_paid);
+
+    ((loss + constants::DECIMALS - 1) / constants::DECIMALS).min(user_info.balance)
+  }
+
+  // takes a written off part of the user's balance out of the token supply and burns the stXLM
+  // that was backed by it, as far as the user still holds it
+  fn settle_loss(env: Env, global_state: &mut storage::StakingGlobals, user: &Address, loss: i128) {
+    let state = env
+      .storage()
+      .instance()
+      .get(&storage::STAKING_STATE)
+      .unwrap_or(storage::StorageClient::get_default_state(env.clone()));
+
+    let mut strategy_state = Self::get_strategy_state(env.clone());
+
+    global_state.token_supply -= loss;
+    strategy_state.loss -= loss.min(strategy_state.loss);
+
+    env
+      .storage()
+      .instance()
+      .set(&storage::STAKING_GLOBALS, global_state);
+
+    env
+      .storage()
+      .instance()
+      .set(&storage::STRATEGY_STATE, &strategy_state);
+
+    let share_token_client = token::Client::new(&env, &state.share_token);
+
+    let shares = share_token_client.balance(user).min(loss);
+
+    if shares > 0 {
+      share_token_client.clawback(user, &shares);
+    }
+  }
+
+  // sends a fee held by the contract to the treasury, or shares it between the current stakers
+  // when it should be redistributed and there is someone left to receive it. The payer's own
+  // balance is left out of the split, so a fee or penalty never flows back to whoever paid it
+  fn route_base_fee(
+    env: Env,
+    state: &storage::StakingContractState,
+    global_state: &mut storage::StakingGlobals,
+    fee: i128,
+    redistribute: bool,
+    payer: Option<&Address>,
+  ) {
+    if fee == 0 {
+      return;
+    }
+
+    let payer_record = payer.map(|payer| Self::get_user_position(env.clone(), payer.clone()));
+
+    let payer_balance = payer_record.as_ref().map_or(0, |record| record.balance);
+
+    let receiving_supply = global_state.token_supply - payer_balance;
+
+    if redistribute && receiving_supply > 0 {
+      global_state.fee_per_token_stored += (fee * constants::DECIMALS) / receiving_supply;
+      global_state.redistributed_fees += fee;
+
+      // the payer is settled already, moving its checkpoint skips the share it just paid in
+      if let Some(mut record) = payer_record.filter(|record| record.balance > 0) {
+        record.fee_per_token_paid = global_state.fee_per_token_stored;
+
+        env.storage().instance().set(
+          &UserInfoRegistry::UserRecord(record.address.clone()),
+          &record,
+        );
+      }
+    } else {
+      let base_token_client = token::Client::new(&env, &state.base_token);
+
+      base_token_client.transfer(&env.current_contract_address(), &state.treasury, &fee);
+    }
+  }
+
+  pub fn unstake(env: Env, user: Address, amount: i128) -> Result<(), Error> {
+    user.require_auth();
+
+    Self::update_reward(env.clone(), user.clone());
+
+    if amount < 0 {
+      return Err(Error::InvalidAmount);
+    }
+
+    let state = Self::get_staking_state(env.clone())
+      .unwrap_or(storage::StorageClient::get_default_state(env.clone()));
+
+    if state.initialized == false {
+      return Err(Error::NotInitialized);
+    }
+
+    if state.paused {
+      return Err(Error::Paused);
+    }
+
+    let share_token_client = token::Client::new(&env, &state.share_token);
+
+    if share_token_client.balance(&user) < amount {
+      return Err(Error::NotEnoughFunds);
+    }
+
+    let base_token_client = token::Client::new(&env, &state.base_token);
+
+    let mut global_state = env
+      .storage()
+      .instance()
+      .get(&storage::STAKING_GLOBALS)
+      .unwrap_or(storage::StorageClient::get_default_global_state(
+        env.clone(),
+      ));
+
+    let key = UserInfoRegistry::UserRecord(user.clone());
+
+    let mut user_record =
+      env
+        .storage()
+        .instance()
+        .get(&key)
+        .unwrap_or(storage::StorageClient::get_default_user(
+          env.clone(),
+          user.clone(),
+        ));
+
+    if user_record.balance < amount {
+      return Err(Error::NotEnoughFunds);
+    }
+
+    let preview = Self::preview_unstake(env.clone(), user.clone(), amount)?;
+
+    Self::ensure_liquidity(env.clone(), &global_state, amount)?;
+
+    user_record.balance -= amount;
+    global_state.token_supply -= amount;
+
+    env.storage().instance().set(&key, &user_record);
+
+    share_token_client.burn(&user, &amount);
+
+    base_token_client.transfer(&env.current_contract_address(), &user, &preview.net);
+
+    Self::route_base_fee(
+      env.clone(),
+      &state,
+      &mut global_state,
+      preview.fee,
+      state.redistribute_fees,
+      Some(&user),
+    );
+
+    // the early unstake penalty always goes to the stakers that remain
+    Self::route_base_fee(
+      env.clone(),
+      &state,
+      &mut global_state,
+      preview.penalty,
+      true,
+      Some(&user),
+    );
+
+    env
+      .storage()
+      .instance()
+      .set(&storage::STAKING_GLOBALS, &global_state);
+
+    Self::update_reward(env.clone(), user.clone());
+
+    let user_record = Self::get_user_position(env.clone(), user.clone());
+
+    if user_record.balance == 0
+      && user_record.rewards_to_claim == 0
+      && user_record.fees_to_claim == 0
+    {
+      env.storage().instance().remove(&key);
+    }
+
+    Ok(())
+  }
+
+  pub fn stake(env: Env, user: Address, amount: i128) -> Result<(), Error> {
+    user.require_auth();
+
+    Self::update_reward(env.clone(), user.clone());
+
+    if amount <= 0 {
+      return Err(Error::InvalidAmount);
+    }
+    let state = Self::get_staking_state(env.clone())
+      .unwrap_or(storage::StorageClient::get_default_state(env.clone()));
+
+    if state.initialized == false {
+      return Err(Error::NotInitialized);
+    }
+
+    if state.paused {
+      return Err(Error::Paused);
+    }
+
+    let share_token_client = token::Client::new(&env, &state.share_token);
+
+    let base_token_client = token::Client::new(&env, &state.base_token);
+
+    if base_token_client.balance(&user) < amount {
+      return Err(Error::NotEnoughFunds);
+    }
+
+    if state.allowlist_enabled && !Self::is_allowlisted(env.clone(), user.clone())? {
+      return Err(Error::NotAllowlisted);
+    }
+
+    let preview = Self::preview_stake(env.clone(), amount)?;
+
+    if amount < state.min_stake {
+      return Err(Error::BelowMinimum);
+    }
+
+    let current_balance = Self::get_user_position(env.clone(), user.clone()).balance;
+
+    if state.max_stake_per_user > 0 && current_balance + preview.net > state.max_stake_per_user {
+      return Err(Error::ExceedsUserCap);
+    }
+
+    if let Some(remaining_capacity) = Self::remaining_capacity(env.clone())? {
+      if preview.net > remaining_capacity {
+        return Err(Error::ExceedsPoolCap);
+      }
+    }
+
+    base_token_client.transfer(&user, &env.current_contract_address(), &amount);
+
+    // the fee goes to the other stakers already in the pool, then the user is settled on the
+    // balance it had before this stake
+    let mut global_state = Self::get_global_state(env.clone());
+
+    Self::route_base_fee(
+      env.clone(),
+      &state,
+      &mut global_state,
+      preview.fee,
+      state.redistribute_fees,
+      Some(&user),
+    );
+
+    env
+      .storage()
+      .instance()
+      .set(&storage::STAKING_GLOBALS, &global_state);
+
+    Self::update_reward(env.clone(), user.clone());
+
+    let mut global_state = Self::get_global_state(env.clone());
+
+    let key = UserInfoRegistry::UserRecord(user.clone());
+
+    let mut user_record =
+      env
+        .storage()
+        .instance()
+        .get(&key)
+        .unwrap_or(storage::StorageClient::get_default_user(
+          env.clone(),
+          user.clone(),
+        ));
+
+    global_state.token_supply += preview.net;
+    user_record.balance += preview.net;
+    user_record.last_deposit_time = env.ledger().timestamp();
+
+    env
+      .storage()
+      .instance()
+      .set(&storage::STAKING_GLOBALS, &global_state);
+
+    env.storage().instance().set(&key, &user_record);
+
+    share_token_client.mint(&user, &preview.net);
+
+    return Ok(());
+  }
+
+  pub fn claim_rewards(env: Env, user: Address) -> Result<(), Error> {
+    user.require_auth();
+
+    Self::update_reward(env.clone(), user.clone()); // update rewards before claim
+
+    let state = Self::get_staking_state(env.clone())
+      .unwrap_or(storage::StorageClient::get_default_state(env.clone()));
+
+    if state.initialized == false {
+      return Err(Error::NotInitialized);
+    }
+
+    if state.paused {
+      return Err(Error::Paused);
+    }
+
+    let reward_token = token::Client::new(&env, &state.reward_token);
+
+    let key = UserInfoRegistry::UserRecord(user.clone());
+
+    let mut user_record =
+      env
+        .storage()
+        .instance()
+        .get(&key)
+        .unwrap_or(storage::StorageClient::get_default_user(
+          env.clone(),
+          user.clone(),
+        ));
+
+    if user_record.rewards_to_claim == 0 && user_record.fees_to_claim == 0 {
+      return Err(Error::ThereIsNoRewardToClaim);
+    }
+
+    let vesting_config = Self::get_vesting_config(env.clone());
+
+    // with vesting on, rewards go into a new schedule and only what already unlocked is paid
+    let payout = if vesting_config.enabled && user_record.rewards_to_claim > 0 {
+      Self::add_vesting_schedule(
+        env.clone(),
+        &user,
+        &vesting_config,
+        user_record.rewards_to_claim,
+      )
+    } else {
+      user_record.rewards_to_claim
+    };
+
+    if Self::reward_balance(env.clone(), &state) < payout {
+      return Err(Error::InsufficientRewardFunds);
+    }
+
+    let mut global_state = Self::get_global_state(env.clone());
+
+    if payout > 0 {
+      reward_token.transfer(&env.current_contract_address(), &user, &payout);
+
+      global_state.committed_rewards -= payout;
+    }
+
+    // redistributed stake and unstake fees are paid in base tokens
+    if user_record.fees_to_claim > 0 {
+      let base_token = token::Client::new(&env, &state.base_token);
+
+      base_token.transfer(
+        &env.current_contract_address(),
+        &user,
+        &user_record.fees_to_claim,
+      );
+
+      global_state.redistributed_fees -= user_record.fees_to_claim;
+    }
+
+    env
+      .storage()
+      .instance()
+      .set(&storage::STAKING_GLOBALS, &global_state);
+
+    user_record.rewards_to_claim = 0;
+    user_record.fees_to_claim = 0;
+
+    if user_record.balance == 0 {
+      env.storage().instance().remove(&key);
+    } else {
+      env.storage().instance().set(&key, &user_record);
+    }
+
+    Ok(())
+  }
+
+  pub fn set_reward_vesting(
+    env: Env,
+    owner: Address,
+    enabled: bool,
+    cliff: u64,
+    duration: u64,
+    early_exit_penalty_bps: u32,
+  ) -> Result<(), Error> {
+    owner.require_auth();
+
+    let state = Self::get_staking_state(env.clone())?;
+
+    if state.owner != owner {
+      return Err(Error::NotOwner);
+    }
+
+    if cliff > duration {
+      return Err(Error::InvalidSchedule);
+    }
+
+    if early_exit_penalty_bps > constants::MAX_VESTING_EXIT_PENALTY_BPS {
+      return Err(Error::FeeTooHigh);
+    }
+
+    // only applies to future claims, running schedules keep their own cliff, duration and penalty
+    let vesting_config = storage::VestingConfig {
+      enabled,
+      cliff,
+      duration,
+      early_exit_penalty_bps,
+    };
+
+    env
+      .storage()
+      .instance()
+      .set(&storage::VESTING_CONFIG, &vesting_config);
+
+    Ok(())
+  }
+
+  pub fn get_vesting_config(env: Env) -> storage::VestingConfig {
+    env
+      .storage()
+      .instance()
+      .get(&storage::VESTING_CONFIG)
+      .unwrap_or(storage::StorageClient::get_default_vesting_config(
+        env.clone(),
+      ))
+  }
+
+  // one schedule per vesting claim, oldest first
+  pub fn get_vesting_schedules(env: Env, user: Address) -> Vec<storage::VestingSchedule> {
+    env
+      .storage()
+      .instance()
+      .get(&UserInfoRegistry::Vesting(user))
+      .unwrap_or(Vec::new(&env))
+  }
+
+  // pays out the unlocked part of all the user's vesting schedules
+  pub fn release_vested(env: Env, user: Address) -> Result<i128, Error> {
+    user.require_auth();
+
+    let state = Self::get_staking_state(env.clone())?;
+
+    if state.paused {
+      return Err(Error::Paused);
+    }
+
+    let mut schedules = Self::get_vesting_schedules(env.clone(), user.clone());
+
+    let releasable = Self::release_schedules(env.clone(), &mut schedules);
+
+    if releasable == 0 {
+      return Err(Error::ThereIsNoRewardToClaim);
+    }
+
+    if Self::reward_balance(env.clone(), &state) < releasable {
+      return Err(Error::InsufficientRewardFunds);
+    }
+
+    let reward_token = token::Client::new(&env, &state.reward_token);
+
+    reward_token.transfer(&env.current_contract_address(), &user, &releasable);
+
+    let mut global_state = Self::get_global_state(env.clone());
+
+    global_state.committed_rewards -= releasable;
+
+    env
+      .storage()
+      .instance()
+      .set(&storage::STAKING_GLOBALS, &global_state);
+
+    Self::write_vesting_schedules(env.clone(), &user, &schedules);
+
+    Ok(releasable)
+  }
+
+  // pays out every schedule right away, forfeiting a part of what has not vested yet. Each
+  // schedule forfeits at the penalty it started with
+  pub fn exit_vesting(env: Env, user: Address) -> Result<i128, Error> {
+    user.require_auth();
+
+    let state = Self::get_staking_state(env.clone())?;
+
+    if state.paused {
+      return Err(Error::Paused);
+    }
+
+    let schedules = Self::get_vesting_schedules(env.clone(), user.clone());
+
+    if schedules.is_empty() {
+      return Err(Error::ThereIsNoRewardToClaim);
+    }
+
+    let mut payout = 0;
+    let mut penalty = 0;
+
+    for schedule in schedules.iter() {
+      let unvested = schedule.total - Self::vested_amount(env.clone(), &schedule);
+
+      let forfeited =
+        unvested * schedule.early_exit_penalty_bps as i128 / constants::BPS_DENOMINATOR;
+
+      payout += schedule.total - schedule.released - forfeited;
+      penalty += forfeited;
+    }
+
+    if Self::reward_balance(env.clone(), &state) < payout {
+      return Err(Error::InsufficientRewardFunds);
+    }
+
+    // the forfeited part goes back to the reserve and is emitted to stakers again
+    let mut global_state = Self::update_global_reward(env.clone());
+
+    global_state.committed_rewards -= payout + penalty;
+    global_state.reward_reserve += penalty;
+
+    env
+      .storage()
+      .instance()
+      .set(&storage::STAKING_GLOBALS, &global_state);
+
+    env
+      .storage()
+      .instance()
+      .remove(&UserInfoRegistry::Vesting(user.clone()));
+
+    if payout > 0 {
+      let reward_token = token::Client::new(&env, &state.reward_token);
+
+      reward_token.transfer(&env.current_contract_address(), &user, &payout);
+    }
+
+    Ok(payout)
+  }
+
+  // unlocked so far, including what was already released
+  pub fn vested_rewards(env: Env, user: Address) -> i128 {
+    Self::get_vesting_schedules(env.clone(), user)
+      .iter()
+      .map(|schedule| Self::vested_amount(env.clone(), &schedule))
+      .sum()
+  }
+
+  pub fn unvested_rewards(env: Env, user: Address) -> i128 {
+    Self::get_vesting_schedules(env.clone(), user)
+      .iter()
+      .map(|schedule| schedule.total - Self::vested_amount(env.clone(), &schedule))
+      .sum()
+  }
+
+  pub fn releasable_rewards(env: Env, user: Address) -> i128 {
+    Self::get_vesting_schedules(env.clone(), user)
+      .iter()
+      .map(|schedule| Self::vested_amount(env.clone(), &schedule) - schedule.released)
+      .sum()
+  }
+
+  fn vested_amount(env: Env, schedule: &storage::VestingSchedule) -> i128 {
+    let elapsed = env.ledger().timestamp().saturating_sub(schedule.start);
+
+    if elapsed < schedule.cliff {
+      return 0;
+    }
+
+    if elapsed >= schedule.duration {
+      return schedule.total;
+    }
+
+    schedule.total * elapsed as i128 / schedule.duration as i128
+  }
+
+  // vests newly claimed rewards on a schedule of their own and returns what can be paid right away.
+  // earlier schedules keep their timeline, their unlocked part is paid along
+  fn add_vesting_schedule(
+    env: Env,
+    user: &Address,
+    vesting_config: &storage::VestingConfig,
+    amount: i128,
+  ) -> i128 {
+    let mut schedules = Self::get_vesting_schedules(env.clone(), user.clone());
+
+    schedules.push_back(storage::VestingSchedule {
+      total: amount,
+      released: 0,
+      start: env.ledger().timestamp(),
+      cliff: vesting_config.cliff,
+      duration: vesting_config.duration,
+      early_exit_penalty_bps: vesting_config.early_exit_penalty_bps,
+    });
+
+    // a zero duration vests at once, so the whole amount is paid like a normal claim
+    let releasable = Self::release_schedules(env.clone(), &mut schedules);
+
+    Self::write_vesting_schedules(env.clone(), user, &schedules);
+
+    releasable
+  }
+
+  // marks everything unlocked so far as released and returns how much that is
+  fn release_schedules(env: Env, schedules: &mut Vec<storage::VestingSchedule>) -> i128 {
+    let mut releasable = 0;
+
+    for (index, mut schedule) in schedules.clone().iter().enumerate() {
+      let vested = Self::vested_amount(env.clone(), &schedule);
+
+      releasable += vested - schedule.released;
+      schedule.released = vested;
+
+      schedules.set(index as u32, schedule);
+    }
+
+    releasable
+  }
+
+  // fully released schedules are dropped
+  fn write_vesting_schedules(env: Env, user: &Address, schedules: &Vec<storage::VestingSchedule>) {
+    let key = UserInfoRegistry::Vesting(user.clone());
+
+    let mut running = Vec::new(&env);
+
+    for schedule in schedules.iter() {
+      if schedule.released < schedule.total {
+        running.push_back(schedule);
+      }
+    }
+
+    if running.is_empty() {
+      env.storage().instance().remove(&key);
+    } else {
+      env.storage().instance().set(&key, &running);
+    }
+  }
+
+  pub fn preview_stake(env: Env, amount: i128) -> Result<storage::FeePreview, Error> {
+    let state = Self::get_staking_state(env.clone())?;
+
+    let fee = (amount * state.stake_fee_bps as i128) / constants::BPS_DENOMINATOR;
+
+    Ok(storage::FeePreview {
+      amount,
+      fee,
+      penalty: 0,
+      net: amount - fee,
+    })
+  }
+
+  pub fn preview_unstake(
+    env: Env,
+    user: Address,
+    amount: i128,
+  ) -> Result<storage::FeePreview, Error> {
+    let state = Self::get_staking_state(env.clone())?;
+
+    let user_record = Self::get_user_position(env.clone(), user);
+
+    let fee = (amount * state.unstake_fee_bps as i128) / constants::BPS_DENOMINATOR;
+    let penalty = Self::early_unstake_penalty(env.clone(), &state, &user_record, amount);
+
+    Ok(storage::FeePreview {
+      amount,
+      fee,
+      penalty,
+      net: amount - fee - penalty,
+    })
+  }
+
+  // the penalty decays linearly from early_unstake_penalty_bps right after the last deposit to
+  // zero once early_unstake_window seconds have passed
+  fn early_unstake_penalty(
+    env: Env,
+    state: &storage::StakingContractState,
+    user_record: &storage::UserRecord,
+    amount: i128,
+  ) -> i128 {
+    let window = state.early_unstake_window;
+
+    let elapsed = env.ledger().timestamp() - user_record.last_deposit_time;
+
+    if window == 0 || elapsed >= window {
+      return 0;
+    }
+
+    let remaining = (window - elapsed) as i128;
+
+    (amount * state.early_unstake_penalty_bps as i128 * remaining)
+      / (constants::BPS_DENOMINATOR * window as i128)
+  }
+
+  pub fn get_user_position(env: Env, user: Address) -> storage::UserRecord {
+    let key = UserInfoRegistry::UserRecord(user.clone());
+
+    let user_record = env
+      .storage()
       .instance()
       .get(&key)
       .unwrap_or(storage::StorageClient::get_default_user(env.clone(), user));
@@ -379,6 +1800,130 @@ impl LiquidStakingContract {
     return user_record;
   }
 
+  pub fn position_summary(env: Env, user: Address) -> Result<storage::PositionSummary, Error> {
+    let state = Self::get_staking_state(env.clone())?;
+
+    let user_record = Self::get_user_position(env.clone(), user.clone());
+    let global_state = Self::get_global_state(env.clone());
+
+    let share_token_client = token::Client::new(&env, &state.share_token);
+
+    let pool_share = if global_state.token_supply == 0 {
+      0
+    } else {
+      (user_record.balance * constants::DECIMALS) / global_state.token_supply
+    };
+
+    Ok(storage::PositionSummary {
+      address: user.clone(),
+      staked_balance: user_record.balance,
+      share_balance: share_token_client.balance(&user),
+      earned: Self::earned(env.clone(), user.clone()),
+      earned_fees: Self::earned_fees(env.clone(), user),
+      pool_share,
+      // a written off loss is taken from the balance before anything is unstaked
+      withdrawable: user_record.balance - Self::pending_loss(&global_state, &user_record),
+      early_unstake_penalty: Self::early_unstake_penalty(
+        env.clone(),
+        &state,
+        &user_record,
+        user_record.balance,
+      ),
+      penalty_ends_at: user_record.last_deposit_time + state.early_unstake_window,
+    })
+  }
+
+  // annual reward tokens paid per staked base token net of protocol fees, scaled by constants::DECIMALS
+  // zero while the funded reserve is used up, nothing is emitted until the pool is funded again
+  pub fn reward_rate_per_token(env: Env) -> Result<i128, Error> {
+    let state = Self::get_staking_state(env.clone())?;
+
+    let global_state = Self::get_global_state(env.clone());
+
+    // with nothing staked there is no per token rate, the first staker gets all the emissions
+    if global_state.token_supply == 0 {
+      return Err(Error::PoolIsEmpty);
+    }
+
+    if global_state.reward_reserve - Self::pending_emission(env.clone(), &global_state) <= 0 {
+      return Ok(0);
+    }
+
+    let annual_emission = constants::REWARD_RATE * constants::SECONDS_PER_YEAR;
+    let (annual_fee, _) = reward_calculator::protocol_fee(annual_emission, state.reward_fee_bps, 0);
+    let net_annual_emission = annual_emission - annual_fee;
+
+    Ok((net_annual_emission * constants::DECIMALS) / global_state.token_supply)
+  }
+
+  // seconds of emissions that the reward tokens held by the contract can still pay for, once the
+  // rewards already owed to stakers and the treasury are set aside, tokens sent without
+  // add_reward_funds count here even if they are never emitted
+  pub fn reward_runway(env: Env) -> Result<u64, Error> {
+    let state = Self::get_staking_state(env.clone())?;
+
+    let global_state = Self::get_global_state(env.clone());
+
+    let owed = global_state.committed_rewards
+      + global_state.protocol_fees
+      + Self::pending_emission(env.clone(), &global_state);
+
+    let available = Self::reward_balance(env.clone(), &state) - owed;
+
+    if available <= 0 {
+      return Ok(0);
+    }
+
+    Ok((available / constants::REWARD_RATE) as u64)
+  }
+
+  // seconds until the funded reward reserve is fully emitted, only counts down while there are
+  // tokens staked
+  pub fn time_until_emissions_end(env: Env) -> Result<u64, Error> {
+    Self::get_staking_state(env.clone())?;
+
+    let global_state = Self::get_global_state(env.clone());
+
+    if global_state.token_supply == 0 {
+      return Err(Error::PoolIsEmpty);
+    }
+
+    let remaining_rewards =
+      global_state.reward_reserve - Self::pending_emission(env.clone(), &global_state);
+
+    Ok((remaining_rewards.max(0) / constants::REWARD_RATE) as u64)
+  }
+
+  // rewards owed to stakers or still to be emitted that the contract balance does not cover
+  pub fn reward_deficit(env: Env) -> Result<i128, Error> {
+    let state = Self::get_staking_state(env.clone())?;
+
+    let global_state = Self::get_global_state(env.clone());
+
+    let obligations =
+      global_state.committed_rewards + global_state.protocol_fees + global_state.reward_reserve;
+
+    Ok((obligations - Self::reward_balance(env.clone(), &state)).max(0))
+  }
+
+  fn reward_balance(env: Env, state: &storage::StakingContractState) -> i128 {
+    let reward_token = token::Client::new(&env, &state.reward_token);
+
+    let mut balance = reward_token.balance(&env.current_contract_address());
+
+    // staked principal and redistributed fees are not reward funds when both tokens are the same
+    if state.reward_token == state.base_token {
+      let global_state = Self::get_global_state(env.clone());
+
+      let strategy_state = Self::get_strategy_state(env.clone());
+
+      balance -= global_state.token_supply - strategy_state.deployed - strategy_state.loss
+        + global_state.redistributed_fees;
+    }
+
+    balance
+  }
+
   pub fn get_staking_state(env: Env) -> Result<storage::StakingContractState, Error> {
     let state = env
       .storage()
@@ -11,4 +11,18 @@ pub enum Error {
   InvalidAmount = 5,
   NotEnoughFunds = 6,
   ThereIsNoRewardToClaim = 7,
+  PoolIsEmpty = 8,
+  InsufficientRewardFunds = 9,
+  ExceedsRecoverableBalance = 10,
+  FeeTooHigh = 11,
+  BelowMinimum = 12,
+  ExceedsUserCap = 13,
+  ExceedsPoolCap = 14,
+  NotAllowlisted = 15,
+  Unauthorized = 16,
+  InvalidSchedule = 17,
+  StrategyNotSet = 18,
+  DeployRatioTooHigh = 19,
+  Paused = 20,
+  NotPendingOwner = 21,
 }
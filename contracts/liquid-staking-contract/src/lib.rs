@@ -4,5 +4,6 @@ mod constants;
 mod contract;
 mod errors;
 mod storage;
+mod strategy;
 mod test;
 mod token;
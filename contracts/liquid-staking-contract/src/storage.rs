@@ -6,7 +6,15 @@ use soroban_sdk::{contracttype, symbol_short, Address, Env, Symbol};
 pub struct StakingGlobals {
   pub token_supply: i128,            // token supply
   pub reward_per_token_stored: i128, // reward per token
-  pub last_updated_time: u64,
+  pub last_updated_time: u64,        // last time the reward per token was updated
+  pub reward_reserve: i128,          // funded rewards that have not been emitted yet
+  pub committed_rewards: i128,       // emitted rewards that have not been paid out yet
+  pub protocol_fees: i128,           // emitted rewards kept as protocol fees, not collected yet
+  pub fee_per_token_stored: i128,    // redistributed stake and unstake fees per staked token
+  pub redistributed_fees: i128,      // redistributed fees that have not been claimed yet
+  pub fee_remainder: i128,           // protocol fee lost to rounding, carried to the next update
+  pub reward_remainder: i128,        // scaled rewards the reward per token could not hand out yet
+  pub loss_per_token_stored: i128,   // written off strategy losses per staked token
 }
 
 pub const STAKING_GLOBALS: Symbol = symbol_short!("globals");
@@ -18,6 +26,20 @@ pub struct StakingContractState {
   pub base_token: Address,   // s_baseToken - is the token that will be locked for staking
   pub share_token: Address,  // s_shareToken - is the token that will be sent to staking contract
   pub owner: Address,        // s_owner - is the owner of the contract, that can change it's state
+  pub pending_owner: Option<Address>, // s_pendingOwner - set by set_owner, owner once it accepts
+  pub treasury: Address,     // s_treasury - receives the protocol fees
+  pub reward_fee_bps: u32,   // s_rewardFeeBps - fee on emitted rewards, in basis points
+  pub stake_fee_bps: u32,    // s_stakeFeeBps - fee on the staked amount, in basis points
+  pub unstake_fee_bps: u32,  // s_unstakeFeeBps - fee on the unstaked amount, in basis points
+  pub redistribute_fees: bool, // s_redistributeFees - stake fees go to stakers
+  pub early_unstake_penalty_bps: u32, // s_earlyUnstakePenaltyBps - penalty right after a deposit
+  pub early_unstake_window: u64, // s_earlyUnstakeWindow - seconds until the penalty reaches zero
+  pub min_stake: i128,       // s_minStake - smallest amount accepted by stake
+  pub max_stake_per_user: i128, // s_maxStakePerUser - largest balance a user can have, 0 is no cap
+  pub pool_cap: i128,        // s_poolCap - largest token supply of the pool, 0 is no cap
+  pub compliance: Address,   // s_compliance - can manage the allowlist next to the owner
+  pub allowlist_enabled: bool, // s_allowlistEnabled - only allowlisted users can stake
+  pub paused: bool,          // s_paused - stops staking, unstaking and every reward payout
   pub initialized: bool,
 }
 
@@ -26,6 +48,7 @@ pub const STAKING_STATE: Symbol = symbol_short!("state");
 #[contracttype]
 pub enum UserInfoRegistry {
   UserRecord(Address),
+  Vesting(Address),
 }
 
 #[contracttype]
@@ -35,8 +58,69 @@ pub struct UserRecord {
   pub balance: i128,                // s_userBalance - balance of user
   pub rewards_per_token_paid: i128, // s_userRewardPerTokenPaid - rewards per token that have been paid
   pub rewards_to_claim: i128,       // s_rewards - rewards that can be claimed
+  pub fee_per_token_paid: i128,     // s_userFeePerTokenPaid - fees per token that have been paid
+  pub fees_to_claim: i128,          // s_fees - redistributed fees that can be claimed
+  pub loss_per_token_paid: i128, // s_userLossPerTokenPaid - losses per token taken from the balance
+  pub last_deposit_time: u64,    // s_lastDepositTime - timestamp of the user's last stake
 }
 
+#[contracttype]
+#[derive(Clone)]
+pub struct PositionSummary {
+  pub address: Address,            // user's address
+  pub staked_balance: i128,        // base tokens staked by the user
+  pub share_balance: i128,         // stXLM held by the user
+  pub earned: i128,                // rewards accrued up to the current ledger timestamp
+  pub earned_fees: i128,           // redistributed stake and unstake fees, in base tokens
+  pub pool_share: i128,            // staked_balance / token_supply, scaled by constants::DECIMALS
+  pub withdrawable: i128,          // amount that can be unstaked right now, there is no hard lockup
+  pub early_unstake_penalty: i128, // penalty paid if the whole balance is unstaked right now
+  pub penalty_ends_at: u64,        // timestamp after which unstaking has no penalty
+}
+
+#[contracttype]
+#[derive(Clone)]
+pub struct FeePreview {
+  pub amount: i128,  // amount sent to stake or unstake
+  pub fee: i128,     // part of the amount taken as fee
+  pub penalty: i128, // part of the amount forfeited for unstaking early
+  pub net: i128,     // amount actually staked or received
+}
+
+#[contracttype]
+#[derive(Clone)]
+pub struct VestingConfig {
+  pub enabled: bool, // s_vestingEnabled - claimed rewards vest instead of being paid at once
+  pub cliff: u64,    // s_vestingCliff - seconds after a claim before anything unlocks
+  pub duration: u64, // s_vestingDuration - seconds after a claim until everything unlocks
+  pub early_exit_penalty_bps: u32, // s_earlyExitPenaltyBps - unvested share forfeited on exit
+}
+
+pub const VESTING_CONFIG: Symbol = symbol_short!("vesting");
+
+#[contracttype]
+#[derive(Clone)]
+pub struct VestingSchedule {
+  pub total: i128,                 // rewards locked in the schedule
+  pub released: i128,              // rewards already paid out of the schedule
+  pub start: u64,                  // timestamp of the claim that started the schedule
+  pub cliff: u64,                  // seconds after start before anything unlocks
+  pub duration: u64,               // seconds after start until everything unlocks
+  pub early_exit_penalty_bps: u32, // unvested share forfeited on exit, fixed when the schedule starts
+}
+
+#[contracttype]
+#[derive(Clone)]
+pub struct StrategyState {
+  pub strategy: Option<Address>, // s_strategy - yield source the staked base tokens are deployed to
+  pub deploy_bps: u32, // s_deployBps - target part of the staked base tokens kept in the strategy
+  pub deployed: i128,  // s_deployed - staked base tokens currently sitting in the strategy
+  pub keeper_bounty_bps: u32, // s_keeperBountyBps - part of a checkpoint harvest paid to the caller
+  pub loss: i128,      // s_loss - written off principal not taken from a staker balance yet
+}
+
+pub const STRATEGY_STATE: Symbol = symbol_short!("strategy");
+
 pub struct StorageClient;
 
 impl StorageClient {
@@ -45,6 +129,14 @@ impl StorageClient {
       token_supply: 0,
       reward_per_token_stored: 0,
       last_updated_time: 0,
+      reward_reserve: 0,
+      committed_rewards: 0,
+      protocol_fees: 0,
+      fee_per_token_stored: 0,
+      redistributed_fees: 0,
+      fee_remainder: 0,
+      reward_remainder: 0,
+      loss_per_token_stored: 0,
     }
   }
 
@@ -54,15 +146,52 @@ impl StorageClient {
       base_token: env.current_contract_address().clone(),
       share_token: env.current_contract_address().clone(),
       owner: env.current_contract_address().clone(),
+      pending_owner: None,
+      treasury: env.current_contract_address().clone(),
+      reward_fee_bps: 0,
+      stake_fee_bps: 0,
+      unstake_fee_bps: 0,
+      redistribute_fees: false,
+      early_unstake_penalty_bps: 0,
+      early_unstake_window: 0,
+      min_stake: 0,
+      max_stake_per_user: 0,
+      pool_cap: 0,
+      compliance: env.current_contract_address().clone(),
+      allowlist_enabled: false,
+      paused: false,
       initialized: false,
     }
   }
 
+  pub fn get_default_vesting_config(env: Env) -> VestingConfig {
+    VestingConfig {
+      enabled: false,
+      cliff: 0,
+      duration: 0,
+      early_exit_penalty_bps: 0,
+    }
+  }
+
+  pub fn get_default_strategy_state(env: Env) -> StrategyState {
+    StrategyState {
+      strategy: None,
+      deploy_bps: 0,
+      deployed: 0,
+      keeper_bounty_bps: 0,
+      loss: 0,
+    }
+  }
+
   pub fn get_default_user(env: Env, user: Address) -> UserRecord {
     UserRecord {
       balance: 0,
       rewards_per_token_paid: 0,
       rewards_to_claim: 0,
+      fee_per_token_paid: 0,
+      fees_to_claim: 0,
+      loss_per_token_paid: 0,
+      last_deposit_time: 0,
       address: user.clone(),
     }
   }
@@ -0,0 +1,15 @@
+#![allow(unused)]
+use soroban_sdk::{contractclient, Env};
+
+// interface a yield source implements to take base tokens from the pool
+#[contractclient(name = "Client")]
+pub trait Strategy {
+  // called right after the pool transferred `amount` base tokens to the strategy
+  fn deposit(env: Env, amount: i128);
+
+  // sends `amount` base tokens back to the pool
+  fn withdraw(env: Env, amount: i128);
+
+  // base tokens held for the pool, including yield that was not harvested yet
+  fn total_assets(env: Env) -> i128;
+}
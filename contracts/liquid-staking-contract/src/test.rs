@@ -5,7 +5,7 @@ use core::ops::Add;
 
 use super::*;
 use soroban_sdk::testutils::{Address as _, Ledger};
-use soroban_sdk::{log, Address, BytesN, Env};
+use soroban_sdk::{contract, contractimpl, contracttype, log, Address, BytesN, Env};
 
 use crate::token;
 
@@ -132,6 +132,27 @@ fn test_set_owner() {
   let new_owner = Address::generate(&env);
 
   liquid_staking_contract_client.set_owner(&new_owner, &owner);
+
+  // nothing changes until the new owner accepts
+  let state = liquid_staking_contract_client.get_staking_state();
+  assert_eq!(state.owner, owner);
+  assert_eq!(state.pending_owner, Some(new_owner.clone()));
+
+  assert_eq!(
+    liquid_staking_contract_client.try_accept_owner(&owner),
+    Err(Ok(errors::Error::NotPendingOwner))
+  );
+
+  liquid_staking_contract_client.accept_owner(&new_owner);
+
+  let state = liquid_staking_contract_client.get_staking_state();
+  assert_eq!(state.owner, new_owner);
+  assert_eq!(state.pending_owner, None);
+
+  assert_eq!(
+    liquid_staking_contract_client.try_accept_owner(&new_owner),
+    Err(Ok(errors::Error::NotPendingOwner))
+  );
 }
 
 #[test]
@@ -176,6 +197,32 @@ fn test_set_owner_not_initialized() {
   liquid_staking_contract_client.set_owner(&new_owner, &owner);
 }
 
+#[test]
+fn test_upgrade_not_owner() {
+  let env = Env::default();
+  env.mock_all_auths();
+
+  let owner = Address::generate(&env);
+  let not_owner = Address::generate(&env);
+
+  let liquid_staking_contract_client = create_liquid_staking_contract(&env);
+
+  let base_token = create_token_contract(&env, &owner);
+  let reward_token = create_token_contract(&env, &owner);
+
+  liquid_staking_contract_client.initialize(
+    &base_token.address.clone(),
+    &reward_token.address.clone(),
+    &owner,
+    &install_contract_wasm(&env),
+  );
+
+  assert_eq!(
+    liquid_staking_contract_client.try_upgrade(&not_owner, &install_contract_wasm(&env)),
+    Err(Ok(errors::Error::NotOwner))
+  );
+}
+
 // OWNER ADD FUNDS
 #[test]
 fn test_contract_owner_add_funds() {
@@ -399,3 +446,3200 @@ pub fn test_claim_rewards() {
 
   assert!(user_rewards_balance > 0);
 }
+
+// POSITION VIEWS
+#[test]
+fn test_get_user_position_without_auth() {
+  let env = Env::default();
+
+  env.mock_all_auths();
+
+  let owner = Address::generate(&env);
+
+  let staker = Address::generate(&env);
+
+  let base_token = create_token_contract(&env, &owner);
+  let reward_token = create_token_contract(&env, &owner);
+
+  let liquid_staking_contract_client = create_liquid_staking_contract(&env);
+
+  liquid_staking_contract_client.initialize(
+    &base_token.address.clone(),
+    &reward_token.address.clone(),
+    &owner,
+    &install_contract_wasm(&env),
+  );
+
+  base_token.mint(&staker, &1000);
+
+  liquid_staking_contract_client.stake(&staker, &1000);
+
+  env.set_auths(&[]);
+
+  let user_position = liquid_staking_contract_client.get_user_position(&staker);
+
+  assert_eq!(user_position.balance, 1000);
+  assert!(env.auths().is_empty());
+}
+
+#[test]
+fn test_position_summary() {
+  let env = Env::default();
+
+  env.mock_all_auths();
+
+  let owner = Address::generate(&env);
+
+  let staker = Address::generate(&env);
+  let other_staker = Address::generate(&env);
+
+  let base_token = create_token_contract(&env, &owner);
+  let reward_token = create_token_contract(&env, &owner);
+
+  let liquid_staking_contract_client = create_liquid_staking_contract(&env);
+
+  liquid_staking_contract_client.initialize(
+    &base_token.address.clone(),
+    &reward_token.address.clone(),
+    &owner,
+    &install_contract_wasm(&env),
+  );
+
+  reward_token.mint(&owner, &100000);
+
+  liquid_staking_contract_client.add_reward_funds(&owner, &100000);
+
+  base_token.mint(&staker, &1000);
+  base_token.mint(&other_staker, &3000);
+
+  liquid_staking_contract_client.stake(&staker, &1000);
+  liquid_staking_contract_client.stake(&other_staker, &3000);
+
+  env.ledger().with_mut(|li| {
+    li.timestamp += 100;
+  });
+
+  env.set_auths(&[]);
+
+  let summary = liquid_staking_contract_client.position_summary(&staker);
+
+  assert_eq!(summary.address, staker);
+  assert_eq!(summary.staked_balance, 1000);
+  assert_eq!(summary.share_balance, 1000);
+  assert_eq!(
+    summary.earned,
+    liquid_staking_contract_client.earned(&staker)
+  );
+  assert_eq!(summary.earned, 2500);
+  assert_eq!(summary.pool_share, 2_500_000);
+  assert_eq!(summary.withdrawable, 1000);
+}
+
+#[test]
+#[should_panic(expected = "HostError: Error(Contract, #2)")]
+fn test_position_summary_not_initialized() {
+  let env = Env::default();
+
+  let staker = Address::generate(&env);
+
+  let liquid_staking_contract_client = create_liquid_staking_contract(&env);
+
+  liquid_staking_contract_client.position_summary(&staker);
+}
+
+// YIELD VIEWS
+#[test]
+fn test_reward_rate_and_runway() {
+  let env = Env::default();
+
+  env.mock_all_auths();
+
+  let owner = Address::generate(&env);
+
+  let staker = Address::generate(&env);
+
+  let base_token = create_token_contract(&env, &owner);
+  let reward_token = create_token_contract(&env, &owner);
+
+  let liquid_staking_contract_client = create_liquid_staking_contract(&env);
+
+  liquid_staking_contract_client.initialize(
+    &base_token.address.clone(),
+    &reward_token.address.clone(),
+    &owner,
+    &install_contract_wasm(&env),
+  );
+
+  reward_token.mint(&owner, &100000);
+
+  liquid_staking_contract_client.add_reward_funds(&owner, &100000);
+
+  base_token.mint(&staker, &1000);
+
+  liquid_staking_contract_client.stake(&staker, &1000);
+
+  // 100 rewards per second over 1000 staked tokens for a year, with 7 decimals
+  assert_eq!(
+    liquid_staking_contract_client.reward_rate_per_token(),
+    100 * 31_536_000 * 10_000_000 / 1000
+  );
+
+  assert_eq!(liquid_staking_contract_client.reward_runway(), 1000);
+  assert_eq!(
+    liquid_staking_contract_client.time_until_emissions_end(),
+    1000
+  );
+}
+
+#[test]
+fn test_runway_counts_held_rewards_not_reserve() {
+  let env = Env::default();
+
+  env.mock_all_auths();
+
+  let owner = Address::generate(&env);
+
+  let staker = Address::generate(&env);
+
+  let base_token = create_token_contract(&env, &owner);
+  let reward_token = create_token_contract(&env, &owner);
+
+  let liquid_staking_contract_client = create_liquid_staking_contract(&env);
+
+  liquid_staking_contract_client.initialize(
+    &base_token.address.clone(),
+    &reward_token.address.clone(),
+    &owner,
+    &install_contract_wasm(&env),
+  );
+
+  reward_token.mint(&owner, &100000);
+
+  liquid_staking_contract_client.add_reward_funds(&owner, &100000);
+
+  base_token.mint(&staker, &1000);
+
+  liquid_staking_contract_client.stake(&staker, &1000);
+
+  env.ledger().with_mut(|li| {
+    li.timestamp += 100;
+  });
+
+  // the 10000 already emitted are owed to the staker, neither view counts them
+  assert_eq!(liquid_staking_contract_client.reward_runway(), 900);
+  assert_eq!(
+    liquid_staking_contract_client.time_until_emissions_end(),
+    900
+  );
+
+  // tokens sent straight to the pool can pay rewards but are never emitted
+  reward_token.mint(&liquid_staking_contract_client.address, &5000);
+
+  assert_eq!(liquid_staking_contract_client.reward_runway(), 950);
+  assert_eq!(
+    liquid_staking_contract_client.time_until_emissions_end(),
+    900
+  );
+}
+
+#[test]
+fn test_reward_rate_unfunded_pool() {
+  let env = Env::default();
+
+  env.mock_all_auths();
+
+  let owner = Address::generate(&env);
+
+  let staker = Address::generate(&env);
+
+  let base_token = create_token_contract(&env, &owner);
+  let reward_token = create_token_contract(&env, &owner);
+
+  let liquid_staking_contract_client = create_liquid_staking_contract(&env);
+
+  liquid_staking_contract_client.initialize(
+    &base_token.address.clone(),
+    &reward_token.address.clone(),
+    &owner,
+    &install_contract_wasm(&env),
+  );
+
+  base_token.mint(&staker, &1000);
+
+  liquid_staking_contract_client.stake(&staker, &1000);
+
+  // nothing funded, nothing will be paid
+  assert_eq!(liquid_staking_contract_client.reward_rate_per_token(), 0);
+
+  reward_token.mint(&owner, &100000);
+
+  liquid_staking_contract_client.add_reward_funds(&owner, &100000);
+
+  assert_eq!(
+    liquid_staking_contract_client.reward_rate_per_token(),
+    100 * 31_536_000 * 10_000_000 / 1000
+  );
+
+  // the reserve is used up after 1000 seconds, even before anyone claims
+  env.ledger().with_mut(|li| {
+    li.timestamp += 1000;
+  });
+
+  assert_eq!(liquid_staking_contract_client.reward_rate_per_token(), 0);
+}
+
+#[test]
+#[should_panic(expected = "HostError: Error(Contract, #8)")]
+fn test_reward_rate_empty_pool() {
+  let env = Env::default();
+
+  env.mock_all_auths();
+
+  let owner = Address::generate(&env);
+
+  let base_token = create_token_contract(&env, &owner);
+  let reward_token = create_token_contract(&env, &owner);
+
+  let liquid_staking_contract_client = create_liquid_staking_contract(&env);
+
+  liquid_staking_contract_client.initialize(
+    &base_token.address.clone(),
+    &reward_token.address.clone(),
+    &owner,
+    &install_contract_wasm(&env),
+  );
+
+  liquid_staking_contract_client.reward_rate_per_token();
+}
+
+#[test]
+fn test_reward_runway_empty_pool() {
+  let env = Env::default();
+
+  env.mock_all_auths();
+
+  let owner = Address::generate(&env);
+
+  let base_token = create_token_contract(&env, &owner);
+  let reward_token = create_token_contract(&env, &owner);
+
+  let liquid_staking_contract_client = create_liquid_staking_contract(&env);
+
+  liquid_staking_contract_client.initialize(
+    &base_token.address.clone(),
+    &reward_token.address.clone(),
+    &owner,
+    &install_contract_wasm(&env),
+  );
+
+  reward_token.mint(&owner, &1000);
+
+  liquid_staking_contract_client.add_reward_funds(&owner, &1000);
+
+  assert_eq!(liquid_staking_contract_client.reward_runway(), 10);
+  assert_eq!(
+    liquid_staking_contract_client.try_time_until_emissions_end(),
+    Err(Ok(errors::Error::PoolIsEmpty))
+  );
+}
+
+// REWARD SOLVENCY
+#[test]
+fn test_accrual_capped_at_reward_funds() {
+  let env = Env::default();
+
+  env.mock_all_auths();
+
+  let owner = Address::generate(&env);
+
+  let staker = Address::generate(&env);
+
+  let base_token = create_token_contract(&env, &owner);
+  let reward_token = create_token_contract(&env, &owner);
+
+  let liquid_staking_contract_client = create_liquid_staking_contract(&env);
+
+  liquid_staking_contract_client.initialize(
+    &base_token.address.clone(),
+    &reward_token.address.clone(),
+    &owner,
+    &install_contract_wasm(&env),
+  );
+
+  reward_token.mint(&owner, &1000);
+
+  liquid_staking_contract_client.add_reward_funds(&owner, &1000);
+
+  base_token.mint(&staker, &1000);
+
+  liquid_staking_contract_client.stake(&staker, &1000);
+
+  // 100 seconds would emit 10000 rewards but only 1000 have been funded
+  env.ledger().with_mut(|li| {
+    li.timestamp += 100;
+  });
+
+  assert_eq!(liquid_staking_contract_client.earned(&staker), 1000);
+
+  liquid_staking_contract_client.claim_rewards(&staker);
+
+  let global_state = liquid_staking_contract_client.get_global_state();
+
+  assert_eq!(reward_token.balance(&staker), 1000);
+  assert_eq!(global_state.reward_reserve, 0);
+  assert_eq!(global_state.committed_rewards, 0);
+  assert_eq!(liquid_staking_contract_client.reward_deficit(), 0);
+  assert_eq!(liquid_staking_contract_client.earned(&staker), 0);
+}
+
+#[test]
+#[should_panic(expected = "HostError: Error(Contract, #7)")]
+fn test_claim_rewards_twice() {
+  let env = Env::default();
+
+  env.mock_all_auths();
+
+  let owner = Address::generate(&env);
+
+  let staker = Address::generate(&env);
+
+  let base_token = create_token_contract(&env, &owner);
+  let reward_token = create_token_contract(&env, &owner);
+
+  let liquid_staking_contract_client = create_liquid_staking_contract(&env);
+
+  liquid_staking_contract_client.initialize(
+    &base_token.address.clone(),
+    &reward_token.address.clone(),
+    &owner,
+    &install_contract_wasm(&env),
+  );
+
+  reward_token.mint(&owner, &100000);
+
+  liquid_staking_contract_client.add_reward_funds(&owner, &100000);
+
+  base_token.mint(&staker, &1000);
+
+  liquid_staking_contract_client.stake(&staker, &1000);
+
+  env.ledger().with_mut(|li| {
+    li.timestamp += 10;
+  });
+
+  liquid_staking_contract_client.claim_rewards(&staker);
+  liquid_staking_contract_client.claim_rewards(&staker);
+}
+
+#[test]
+fn test_claim_rewards_insufficient_reward_funds() {
+  let env = Env::default();
+
+  env.mock_all_auths();
+
+  let owner = Address::generate(&env);
+
+  let staker = Address::generate(&env);
+
+  let base_token = create_token_contract(&env, &owner);
+
+  let reward_token_registration = env.register_stellar_asset_contract_v2(owner.clone());
+  reward_token_registration
+    .issuer()
+    .set_flag(soroban_sdk::testutils::IssuerFlags::ClawbackEnabledFlag);
+
+  let reward_token = token::Client::new(&env, &reward_token_registration.address());
+
+  let liquid_staking_contract_client = create_liquid_staking_contract(&env);
+
+  liquid_staking_contract_client.initialize(
+    &base_token.address.clone(),
+    &reward_token.address.clone(),
+    &owner,
+    &install_contract_wasm(&env),
+  );
+
+  reward_token.mint(&owner, &1000);
+
+  liquid_staking_contract_client.add_reward_funds(&owner, &1000);
+
+  base_token.mint(&staker, &1000);
+
+  liquid_staking_contract_client.stake(&staker, &1000);
+
+  env.ledger().with_mut(|li| {
+    li.timestamp += 10;
+  });
+
+  // funds leave the contract behind its back, e.g. an issuer clawback
+  soroban_sdk::token::StellarAssetClient::new(&env, &reward_token.address)
+    .clawback(&liquid_staking_contract_client.address, &500);
+
+  assert_eq!(liquid_staking_contract_client.reward_deficit(), 500);
+  assert_eq!(
+    liquid_staking_contract_client.try_claim_rewards(&staker),
+    Err(Ok(errors::Error::InsufficientRewardFunds))
+  );
+}
+
+// OWNER RECOVER TOKEN
+#[test]
+fn test_recover_stray_token() {
+  let env = Env::default();
+
+  env.mock_all_auths();
+
+  let owner = Address::generate(&env);
+
+  let receiver = Address::generate(&env);
+
+  let base_token = create_token_contract(&env, &owner);
+  let reward_token = create_token_contract(&env, &owner);
+  let stray_token = create_token_contract(&env, &owner);
+
+  let liquid_staking_contract_client = create_liquid_staking_contract(&env);
+
+  liquid_staking_contract_client.initialize(
+    &base_token.address.clone(),
+    &reward_token.address.clone(),
+    &owner,
+    &install_contract_wasm(&env),
+  );
+
+  stray_token.mint(&liquid_staking_contract_client.address, &500);
+
+  liquid_staking_contract_client.recover_token(&owner, &stray_token.address, &500, &receiver);
+
+  assert_eq!(stray_token.balance(&receiver), 500);
+  assert_eq!(
+    stray_token.balance(&liquid_staking_contract_client.address),
+    0
+  );
+}
+
+#[test]
+fn test_recover_base_token_surplus_only() {
+  let env = Env::default();
+
+  env.mock_all_auths();
+
+  let owner = Address::generate(&env);
+
+  let staker = Address::generate(&env);
+  let receiver = Address::generate(&env);
+
+  let base_token = create_token_contract(&env, &owner);
+  let reward_token = create_token_contract(&env, &owner);
+
+  let liquid_staking_contract_client = create_liquid_staking_contract(&env);
+
+  liquid_staking_contract_client.initialize(
+    &base_token.address.clone(),
+    &reward_token.address.clone(),
+    &owner,
+    &install_contract_wasm(&env),
+  );
+
+  base_token.mint(&staker, &1000);
+
+  liquid_staking_contract_client.stake(&staker, &1000);
+
+  // sent by mistake on top of the staked principal
+  base_token.mint(&liquid_staking_contract_client.address, &200);
+
+  assert_eq!(
+    liquid_staking_contract_client.try_recover_token(&owner, &base_token.address, &201, &receiver),
+    Err(Ok(errors::Error::ExceedsRecoverableBalance))
+  );
+
+  liquid_staking_contract_client.recover_token(&owner, &base_token.address, &200, &receiver);
+
+  assert_eq!(base_token.balance(&receiver), 200);
+  assert_eq!(
+    base_token.balance(&liquid_staking_contract_client.address),
+    1000
+  );
+
+  liquid_staking_contract_client.unstake(&staker, &1000);
+
+  assert_eq!(base_token.balance(&staker), 1000);
+}
+
+#[test]
+#[should_panic(expected = "HostError: Error(Contract, #10)")]
+fn test_recover_token_cannot_drain_principal() {
+  let env = Env::default();
+
+  env.mock_all_auths();
+
+  let owner = Address::generate(&env);
+
+  let staker = Address::generate(&env);
+
+  let base_token = create_token_contract(&env, &owner);
+  let reward_token = create_token_contract(&env, &owner);
+
+  let liquid_staking_contract_client = create_liquid_staking_contract(&env);
+
+  liquid_staking_contract_client.initialize(
+    &base_token.address.clone(),
+    &reward_token.address.clone(),
+    &owner,
+    &install_contract_wasm(&env),
+  );
+
+  base_token.mint(&staker, &1000);
+
+  liquid_staking_contract_client.stake(&staker, &1000);
+
+  liquid_staking_contract_client.recover_token(&owner, &base_token.address, &1, &owner);
+}
+
+#[test]
+#[should_panic(expected = "HostError: Error(Contract, #10)")]
+fn test_recover_token_cannot_drain_principal_when_reward_is_base() {
+  let env = Env::default();
+
+  env.mock_all_auths();
+
+  let owner = Address::generate(&env);
+
+  let staker = Address::generate(&env);
+
+  let base_token = create_token_contract(&env, &owner);
+
+  let liquid_staking_contract_client = create_liquid_staking_contract(&env);
+
+  liquid_staking_contract_client.initialize(
+    &base_token.address.clone(),
+    &base_token.address.clone(),
+    &owner,
+    &install_contract_wasm(&env),
+  );
+
+  base_token.mint(&owner, &1000);
+
+  liquid_staking_contract_client.add_reward_funds(&owner, &1000);
+
+  base_token.mint(&staker, &1000);
+
+  liquid_staking_contract_client.stake(&staker, &1000);
+
+  env.ledger().with_mut(|li| {
+    li.timestamp += 5;
+  });
+
+  // 500 rewards are committed and 1000 are principal, only the 500 unemitted can go
+  liquid_staking_contract_client.recover_token(&owner, &base_token.address, &501, &owner);
+}
+
+#[test]
+fn test_recover_unemitted_rewards() {
+  let env = Env::default();
+
+  env.mock_all_auths();
+
+  let owner = Address::generate(&env);
+
+  let staker = Address::generate(&env);
+
+  let base_token = create_token_contract(&env, &owner);
+  let reward_token = create_token_contract(&env, &owner);
+
+  let liquid_staking_contract_client = create_liquid_staking_contract(&env);
+
+  liquid_staking_contract_client.initialize(
+    &base_token.address.clone(),
+    &reward_token.address.clone(),
+    &owner,
+    &install_contract_wasm(&env),
+  );
+
+  reward_token.mint(&owner, &1000);
+
+  liquid_staking_contract_client.add_reward_funds(&owner, &1000);
+
+  base_token.mint(&staker, &1000);
+
+  liquid_staking_contract_client.stake(&staker, &1000);
+
+  env.ledger().with_mut(|li| {
+    li.timestamp += 4;
+  });
+
+  assert_eq!(
+    liquid_staking_contract_client.try_recover_token(&owner, &reward_token.address, &601, &owner),
+    Err(Ok(errors::Error::ExceedsRecoverableBalance))
+  );
+
+  liquid_staking_contract_client.recover_token(&owner, &reward_token.address, &600, &owner);
+
+  let global_state = liquid_staking_contract_client.get_global_state();
+
+  assert_eq!(global_state.reward_reserve, 0);
+  assert_eq!(global_state.committed_rewards, 400);
+
+  liquid_staking_contract_client.claim_rewards(&staker);
+
+  assert_eq!(reward_token.balance(&staker), 400);
+  assert_eq!(liquid_staking_contract_client.reward_deficit(), 0);
+}
+
+#[test]
+#[should_panic(expected = "HostError: Error(Contract, #3)")]
+fn test_recover_token_not_owner() {
+  let env = Env::default();
+
+  env.mock_all_auths();
+
+  let owner = Address::generate(&env);
+
+  let base_token = create_token_contract(&env, &owner);
+  let reward_token = create_token_contract(&env, &owner);
+
+  let liquid_staking_contract_client = create_liquid_staking_contract(&env);
+
+  liquid_staking_contract_client.initialize(
+    &base_token.address.clone(),
+    &reward_token.address.clone(),
+    &owner,
+    &install_contract_wasm(&env),
+  );
+
+  base_token.mint(&liquid_staking_contract_client.address, &100);
+
+  let fake_owner = Address::generate(&env);
+
+  liquid_staking_contract_client.recover_token(&fake_owner, &base_token.address, &100, &fake_owner);
+}
+
+// PROTOCOL FEES
+#[test]
+fn test_reward_fee_and_collect_fees() {
+  let env = Env::default();
+
+  env.mock_all_auths();
+
+  let owner = Address::generate(&env);
+
+  let staker = Address::generate(&env);
+  let treasury = Address::generate(&env);
+
+  let base_token = create_token_contract(&env, &owner);
+  let reward_token = create_token_contract(&env, &owner);
+
+  let liquid_staking_contract_client = create_liquid_staking_contract(&env);
+
+  liquid_staking_contract_client.initialize(
+    &base_token.address.clone(),
+    &reward_token.address.clone(),
+    &owner,
+    &install_contract_wasm(&env),
+  );
+
+  liquid_staking_contract_client.set_treasury(&owner, &treasury);
+  liquid_staking_contract_client.set_reward_fee(&owner, &1000);
+
+  let state = liquid_staking_contract_client.get_staking_state();
+
+  assert_eq!(state.treasury, treasury);
+  assert_eq!(state.reward_fee_bps, 1000);
+
+  reward_token.mint(&owner, &100000);
+
+  liquid_staking_contract_client.add_reward_funds(&owner, &100000);
+
+  base_token.mint(&staker, &1000);
+
+  liquid_staking_contract_client.stake(&staker, &1000);
+
+  env.ledger().with_mut(|li| {
+    li.timestamp += 10;
+  });
+
+  // 1000 emitted, 10% of it goes to the treasury
+  assert_eq!(liquid_staking_contract_client.earned(&staker), 900);
+  assert_eq!(liquid_staking_contract_client.accrued_protocol_fees(), 100);
+
+  liquid_staking_contract_client.claim_rewards(&staker);
+
+  assert_eq!(reward_token.balance(&staker), 900);
+
+  assert_eq!(liquid_staking_contract_client.collect_fees(), 100);
+  assert_eq!(reward_token.balance(&treasury), 100);
+  assert_eq!(liquid_staking_contract_client.accrued_protocol_fees(), 0);
+  assert_eq!(liquid_staking_contract_client.collect_fees(), 0);
+  assert_eq!(liquid_staking_contract_client.reward_deficit(), 0);
+}
+
+#[test]
+fn test_reward_fee_change_is_not_retroactive() {
+  let env = Env::default();
+
+  env.mock_all_auths();
+
+  let owner = Address::generate(&env);
+
+  let staker = Address::generate(&env);
+
+  let base_token = create_token_contract(&env, &owner);
+  let reward_token = create_token_contract(&env, &owner);
+
+  let liquid_staking_contract_client = create_liquid_staking_contract(&env);
+
+  liquid_staking_contract_client.initialize(
+    &base_token.address.clone(),
+    &reward_token.address.clone(),
+    &owner,
+    &install_contract_wasm(&env),
+  );
+
+  reward_token.mint(&owner, &100000);
+
+  liquid_staking_contract_client.add_reward_funds(&owner, &100000);
+
+  base_token.mint(&staker, &1000);
+
+  liquid_staking_contract_client.stake(&staker, &1000);
+
+  env.ledger().with_mut(|li| {
+    li.timestamp += 10;
+  });
+
+  liquid_staking_contract_client.set_reward_fee(&owner, &2000);
+
+  env.ledger().with_mut(|li| {
+    li.timestamp += 10;
+  });
+
+  assert_eq!(liquid_staking_contract_client.earned(&staker), 1000 + 800);
+  assert_eq!(liquid_staking_contract_client.accrued_protocol_fees(), 200);
+}
+
+#[test]
+#[should_panic(expected = "HostError: Error(Contract, #11)")]
+fn test_reward_fee_too_high() {
+  let env = Env::default();
+
+  env.mock_all_auths();
+
+  let owner = Address::generate(&env);
+
+  let base_token = create_token_contract(&env, &owner);
+  let reward_token = create_token_contract(&env, &owner);
+
+  let liquid_staking_contract_client = create_liquid_staking_contract(&env);
+
+  liquid_staking_contract_client.initialize(
+    &base_token.address.clone(),
+    &reward_token.address.clone(),
+    &owner,
+    &install_contract_wasm(&env),
+  );
+
+  liquid_staking_contract_client.set_reward_fee(&owner, &2001);
+}
+
+#[test]
+#[should_panic(expected = "HostError: Error(Contract, #3)")]
+fn test_reward_fee_not_owner() {
+  let env = Env::default();
+
+  env.mock_all_auths();
+
+  let owner = Address::generate(&env);
+
+  let base_token = create_token_contract(&env, &owner);
+  let reward_token = create_token_contract(&env, &owner);
+
+  let liquid_staking_contract_client = create_liquid_staking_contract(&env);
+
+  liquid_staking_contract_client.initialize(
+    &base_token.address.clone(),
+    &reward_token.address.clone(),
+    &owner,
+    &install_contract_wasm(&env),
+  );
+
+  let fake_owner = Address::generate(&env);
+
+  liquid_staking_contract_client.set_reward_fee(&fake_owner, &100);
+}
+
+// STAKE AND UNSTAKE FEES
+#[test]
+fn test_stake_and_unstake_fees_to_treasury() {
+  let env = Env::default();
+
+  env.mock_all_auths();
+
+  let owner = Address::generate(&env);
+
+  let staker = Address::generate(&env);
+  let treasury = Address::generate(&env);
+
+  let base_token = create_token_contract(&env, &owner);
+  let reward_token = create_token_contract(&env, &owner);
+
+  let liquid_staking_contract_client = create_liquid_staking_contract(&env);
+
+  liquid_staking_contract_client.initialize(
+    &base_token.address.clone(),
+    &reward_token.address.clone(),
+    &owner,
+    &install_contract_wasm(&env),
+  );
+
+  liquid_staking_contract_client.set_treasury(&owner, &treasury);
+  liquid_staking_contract_client.set_stake_fees(&owner, &100, &200, &false);
+
+  let stake_preview = liquid_staking_contract_client.preview_stake(&1000);
+
+  assert_eq!(stake_preview.fee, 10);
+  assert_eq!(stake_preview.net, 990);
+
+  base_token.mint(&staker, &1000);
+
+  liquid_staking_contract_client.stake(&staker, &1000);
+
+  let state = liquid_staking_contract_client.get_staking_state();
+  let share_token = token::Client::new(&env, &state.share_token);
+
+  assert_eq!(
+    liquid_staking_contract_client
+      .get_user_position(&staker)
+      .balance,
+    990
+  );
+  assert_eq!(share_token.balance(&staker), 990);
+  assert_eq!(base_token.balance(&treasury), 10);
+
+  let unstake_preview = liquid_staking_contract_client.preview_unstake(&staker, &990);
+
+  assert_eq!(unstake_preview.fee, 19);
+  assert_eq!(unstake_preview.net, 971);
+
+  liquid_staking_contract_client.unstake(&staker, &990);
+
+  assert_eq!(base_token.balance(&staker), 971);
+  assert_eq!(base_token.balance(&treasury), 29);
+  assert_eq!(share_token.balance(&staker), 0);
+  assert_eq!(
+    base_token.balance(&liquid_staking_contract_client.address),
+    0
+  );
+}
+
+#[test]
+fn test_stake_and_unstake_fees_redistributed() {
+  let env = Env::default();
+
+  env.mock_all_auths();
+
+  let owner = Address::generate(&env);
+
+  let first_staker = Address::generate(&env);
+  let second_staker = Address::generate(&env);
+  let treasury = Address::generate(&env);
+
+  let base_token = create_token_contract(&env, &owner);
+  let reward_token = create_token_contract(&env, &owner);
+
+  let liquid_staking_contract_client = create_liquid_staking_contract(&env);
+
+  liquid_staking_contract_client.initialize(
+    &base_token.address.clone(),
+    &reward_token.address.clone(),
+    &owner,
+    &install_contract_wasm(&env),
+  );
+
+  liquid_staking_contract_client.set_treasury(&owner, &treasury);
+  liquid_staking_contract_client.set_stake_fees(&owner, &100, &100, &true);
+
+  base_token.mint(&first_staker, &1000);
+  base_token.mint(&second_staker, &1000);
+
+  // nobody is staked yet, so the first fee has no one to go to
+  liquid_staking_contract_client.stake(&first_staker, &1000);
+
+  assert_eq!(base_token.balance(&treasury), 10);
+
+  liquid_staking_contract_client.stake(&second_staker, &1000);
+
+  assert_eq!(liquid_staking_contract_client.earned_fees(&first_staker), 9);
+  assert_eq!(
+    liquid_staking_contract_client.earned_fees(&second_staker),
+    0
+  );
+
+  liquid_staking_contract_client.unstake(&second_staker, &990);
+
+  assert_eq!(base_token.balance(&second_staker), 981);
+  assert_eq!(
+    liquid_staking_contract_client.earned_fees(&first_staker),
+    18
+  );
+
+  liquid_staking_contract_client.claim_rewards(&first_staker);
+
+  assert_eq!(base_token.balance(&first_staker), 18);
+  assert_eq!(liquid_staking_contract_client.earned_fees(&first_staker), 0);
+
+  liquid_staking_contract_client.unstake(&first_staker, &990);
+
+  // everything staked or owed has left, only rounding dust stays behind
+  assert!(base_token.balance(&liquid_staking_contract_client.address) <= 1);
+}
+
+#[test]
+fn test_fees_skip_the_payer_remaining_balance() {
+  let env = Env::default();
+
+  env.mock_all_auths();
+
+  let owner = Address::generate(&env);
+
+  let other_staker = Address::generate(&env);
+  let payer = Address::generate(&env);
+
+  let base_token = create_token_contract(&env, &owner);
+  let reward_token = create_token_contract(&env, &owner);
+
+  let liquid_staking_contract_client = create_liquid_staking_contract(&env);
+
+  liquid_staking_contract_client.initialize(
+    &base_token.address.clone(),
+    &reward_token.address.clone(),
+    &owner,
+    &install_contract_wasm(&env),
+  );
+
+  base_token.mint(&other_staker, &1000);
+  base_token.mint(&payer, &2000);
+
+  liquid_staking_contract_client.stake(&other_staker, &1000);
+  liquid_staking_contract_client.stake(&payer, &1000);
+
+  liquid_staking_contract_client.set_stake_fees(&owner, &100, &100, &true);
+  liquid_staking_contract_client.set_early_unstake_penalty(&owner, &1000, &1000);
+
+  // topping up, the fee goes to the other staker only
+  liquid_staking_contract_client.stake(&payer, &1000);
+
+  assert_eq!(
+    liquid_staking_contract_client.earned_fees(&other_staker),
+    10
+  );
+  assert_eq!(liquid_staking_contract_client.earned_fees(&payer), 0);
+
+  // a partial unstake keeps a balance, the fee and the penalty still only reach the other staker
+  let preview = liquid_staking_contract_client.preview_unstake(&payer, &990);
+
+  assert!(preview.fee > 0 && preview.penalty > 0);
+
+  liquid_staking_contract_client.unstake(&payer, &990);
+
+  assert_eq!(
+    liquid_staking_contract_client
+      .get_user_position(&payer)
+      .balance,
+    1000
+  );
+  assert_eq!(liquid_staking_contract_client.earned_fees(&payer), 0);
+  assert_eq!(
+    liquid_staking_contract_client.earned_fees(&other_staker),
+    10 + preview.fee + preview.penalty
+  );
+}
+
+#[test]
+#[should_panic(expected = "HostError: Error(Contract, #11)")]
+fn test_stake_fee_too_high() {
+  let env = Env::default();
+
+  env.mock_all_auths();
+
+  let owner = Address::generate(&env);
+
+  let base_token = create_token_contract(&env, &owner);
+  let reward_token = create_token_contract(&env, &owner);
+
+  let liquid_staking_contract_client = create_liquid_staking_contract(&env);
+
+  liquid_staking_contract_client.initialize(
+    &base_token.address.clone(),
+    &reward_token.address.clone(),
+    &owner,
+    &install_contract_wasm(&env),
+  );
+
+  liquid_staking_contract_client.set_stake_fees(&owner, &0, &501, &false);
+}
+
+// EARLY UNSTAKE PENALTY
+#[test]
+fn test_early_unstake_penalty_decay() {
+  let env = Env::default();
+
+  env.mock_all_auths();
+
+  let owner = Address::generate(&env);
+
+  let staker = Address::generate(&env);
+
+  let base_token = create_token_contract(&env, &owner);
+  let reward_token = create_token_contract(&env, &owner);
+
+  let liquid_staking_contract_client = create_liquid_staking_contract(&env);
+
+  liquid_staking_contract_client.initialize(
+    &base_token.address.clone(),
+    &reward_token.address.clone(),
+    &owner,
+    &install_contract_wasm(&env),
+  );
+
+  // 10% right after the deposit, nothing after 1000 seconds
+  liquid_staking_contract_client.set_early_unstake_penalty(&owner, &1000, &1000);
+
+  env.ledger().with_mut(|li| {
+    li.timestamp = 5000;
+  });
+
+  base_token.mint(&staker, &10000);
+
+  liquid_staking_contract_client.stake(&staker, &10000);
+
+  let summary = liquid_staking_contract_client.position_summary(&staker);
+
+  assert_eq!(summary.early_unstake_penalty, 1000);
+  assert_eq!(summary.penalty_ends_at, 6000);
+
+  let preview = liquid_staking_contract_client.preview_unstake(&staker, &10000);
+
+  assert_eq!(preview.penalty, 1000);
+  assert_eq!(preview.net, 9000);
+
+  env.ledger().with_mut(|li| {
+    li.timestamp = 5500;
+  });
+
+  assert_eq!(
+    liquid_staking_contract_client
+      .preview_unstake(&staker, &10000)
+      .penalty,
+    500
+  );
+
+  env.ledger().with_mut(|li| {
+    li.timestamp = 5999;
+  });
+
+  assert_eq!(
+    liquid_staking_contract_client
+      .preview_unstake(&staker, &10000)
+      .penalty,
+    1
+  );
+
+  env.ledger().with_mut(|li| {
+    li.timestamp = 6000;
+  });
+
+  assert_eq!(
+    liquid_staking_contract_client
+      .preview_unstake(&staker, &10000)
+      .penalty,
+    0
+  );
+
+  // a new deposit restarts the window for the whole position
+  base_token.mint(&staker, &1);
+
+  liquid_staking_contract_client.stake(&staker, &1);
+
+  assert_eq!(
+    liquid_staking_contract_client
+      .preview_unstake(&staker, &10001)
+      .penalty,
+    1000
+  );
+}
+
+#[test]
+fn test_early_unstake_penalty_goes_to_remaining_stakers() {
+  let env = Env::default();
+
+  env.mock_all_auths();
+
+  let owner = Address::generate(&env);
+
+  let early_staker = Address::generate(&env);
+  let leaving_staker = Address::generate(&env);
+  let treasury = Address::generate(&env);
+
+  let base_token = create_token_contract(&env, &owner);
+  let reward_token = create_token_contract(&env, &owner);
+
+  let liquid_staking_contract_client = create_liquid_staking_contract(&env);
+
+  liquid_staking_contract_client.initialize(
+    &base_token.address.clone(),
+    &reward_token.address.clone(),
+    &owner,
+    &install_contract_wasm(&env),
+  );
+
+  liquid_staking_contract_client.set_treasury(&owner, &treasury);
+  liquid_staking_contract_client.set_early_unstake_penalty(&owner, &1000, &1000);
+
+  base_token.mint(&early_staker, &1000);
+  base_token.mint(&leaving_staker, &1000);
+
+  liquid_staking_contract_client.stake(&early_staker, &1000);
+
+  env.ledger().with_mut(|li| {
+    li.timestamp += 1000;
+  });
+
+  liquid_staking_contract_client.stake(&leaving_staker, &1000);
+  liquid_staking_contract_client.unstake(&leaving_staker, &1000);
+
+  assert_eq!(base_token.balance(&leaving_staker), 900);
+  assert_eq!(base_token.balance(&treasury), 0);
+  assert_eq!(
+    liquid_staking_contract_client.earned_fees(&early_staker),
+    100
+  );
+
+  // past the window the early staker leaves without a penalty and takes the forfeited share
+  liquid_staking_contract_client.unstake(&early_staker, &1000);
+  liquid_staking_contract_client.claim_rewards(&early_staker);
+
+  assert_eq!(base_token.balance(&early_staker), 1100);
+}
+
+#[test]
+#[should_panic(expected = "HostError: Error(Contract, #11)")]
+fn test_early_unstake_penalty_too_high() {
+  let env = Env::default();
+
+  env.mock_all_auths();
+
+  let owner = Address::generate(&env);
+
+  let base_token = create_token_contract(&env, &owner);
+  let reward_token = create_token_contract(&env, &owner);
+
+  let liquid_staking_contract_client = create_liquid_staking_contract(&env);
+
+  liquid_staking_contract_client.initialize(
+    &base_token.address.clone(),
+    &reward_token.address.clone(),
+    &owner,
+    &install_contract_wasm(&env),
+  );
+
+  liquid_staking_contract_client.set_early_unstake_penalty(&owner, &1001, &1000);
+}
+
+// STAKE LIMITS
+#[test]
+fn test_stake_limits() {
+  let env = Env::default();
+
+  env.mock_all_auths();
+
+  let owner = Address::generate(&env);
+
+  let first_staker = Address::generate(&env);
+  let second_staker = Address::generate(&env);
+
+  let base_token = create_token_contract(&env, &owner);
+  let reward_token = create_token_contract(&env, &owner);
+
+  let liquid_staking_contract_client = create_liquid_staking_contract(&env);
+
+  liquid_staking_contract_client.initialize(
+    &base_token.address.clone(),
+    &reward_token.address.clone(),
+    &owner,
+    &install_contract_wasm(&env),
+  );
+
+  assert_eq!(liquid_staking_contract_client.remaining_capacity(), None);
+
+  liquid_staking_contract_client.set_stake_limits(&owner, &100, &1000, &1500);
+
+  assert_eq!(
+    liquid_staking_contract_client.remaining_capacity(),
+    Some(1500)
+  );
+
+  base_token.mint(&first_staker, &2000);
+  base_token.mint(&second_staker, &2000);
+
+  assert_eq!(
+    liquid_staking_contract_client.try_stake(&first_staker, &99),
+    Err(Ok(errors::Error::BelowMinimum))
+  );
+
+  liquid_staking_contract_client.stake(&first_staker, &600);
+
+  assert_eq!(
+    liquid_staking_contract_client.try_stake(&first_staker, &401),
+    Err(Ok(errors::Error::ExceedsUserCap))
+  );
+
+  liquid_staking_contract_client.stake(&first_staker, &400);
+
+  assert_eq!(
+    liquid_staking_contract_client.remaining_capacity(),
+    Some(500)
+  );
+
+  assert_eq!(
+    liquid_staking_contract_client.try_stake(&second_staker, &501),
+    Err(Ok(errors::Error::ExceedsPoolCap))
+  );
+
+  liquid_staking_contract_client.stake(&second_staker, &500);
+
+  assert_eq!(liquid_staking_contract_client.remaining_capacity(), Some(0));
+
+  // unstaking frees capacity again
+  liquid_staking_contract_client.unstake(&first_staker, &200);
+
+  assert_eq!(
+    liquid_staking_contract_client.remaining_capacity(),
+    Some(200)
+  );
+}
+
+#[test]
+#[should_panic(expected = "HostError: Error(Contract, #5)")]
+fn test_stake_zero_amount() {
+  let env = Env::default();
+
+  env.mock_all_auths();
+
+  let owner = Address::generate(&env);
+
+  let staker = Address::generate(&env);
+
+  let base_token = create_token_contract(&env, &owner);
+  let reward_token = create_token_contract(&env, &owner);
+
+  let liquid_staking_contract_client = create_liquid_staking_contract(&env);
+
+  liquid_staking_contract_client.initialize(
+    &base_token.address.clone(),
+    &reward_token.address.clone(),
+    &owner,
+    &install_contract_wasm(&env),
+  );
+
+  liquid_staking_contract_client.stake(&staker, &0);
+}
+
+#[test]
+#[should_panic(expected = "HostError: Error(Contract, #3)")]
+fn test_stake_limits_not_owner() {
+  let env = Env::default();
+
+  env.mock_all_auths();
+
+  let owner = Address::generate(&env);
+
+  let base_token = create_token_contract(&env, &owner);
+  let reward_token = create_token_contract(&env, &owner);
+
+  let liquid_staking_contract_client = create_liquid_staking_contract(&env);
+
+  liquid_staking_contract_client.initialize(
+    &base_token.address.clone(),
+    &reward_token.address.clone(),
+    &owner,
+    &install_contract_wasm(&env),
+  );
+
+  let fake_owner = Address::generate(&env);
+
+  liquid_staking_contract_client.set_stake_limits(&fake_owner, &0, &0, &1000);
+}
+
+// ALLOWLIST
+#[test]
+fn test_allowlist_gates_stake_and_share_transfers() {
+  let env = Env::default();
+
+  env.mock_all_auths();
+
+  let owner = Address::generate(&env);
+
+  let compliance = Address::generate(&env);
+  let approved_staker = Address::generate(&env);
+  let approved_receiver = Address::generate(&env);
+  let outsider = Address::generate(&env);
+
+  let base_token = create_token_contract(&env, &owner);
+  let reward_token = create_token_contract(&env, &owner);
+
+  let liquid_staking_contract_client = create_liquid_staking_contract(&env);
+
+  liquid_staking_contract_client.initialize(
+    &base_token.address.clone(),
+    &reward_token.address.clone(),
+    &owner,
+    &install_contract_wasm(&env),
+  );
+
+  let state = liquid_staking_contract_client.get_staking_state();
+  let share_token = token::Client::new(&env, &state.share_token);
+
+  liquid_staking_contract_client.set_compliance(&owner, &compliance);
+  liquid_staking_contract_client.set_allowlist_enabled(&owner, &true);
+  liquid_staking_contract_client.add_to_allowlist_batch(
+    &compliance,
+    &soroban_sdk::vec![&env, approved_staker.clone(), approved_receiver.clone()],
+  );
+
+  assert!(liquid_staking_contract_client.is_allowlisted(&approved_staker));
+  assert!(!liquid_staking_contract_client.is_allowlisted(&outsider));
+  assert!(share_token.allowlist_enabled());
+  assert!(share_token.allowlisted(&approved_receiver));
+
+  base_token.mint(&approved_staker, &1000);
+  base_token.mint(&outsider, &1000);
+
+  assert_eq!(
+    liquid_staking_contract_client.try_stake(&outsider, &1000),
+    Err(Ok(errors::Error::NotAllowlisted))
+  );
+
+  liquid_staking_contract_client.stake(&approved_staker, &1000);
+
+  share_token.transfer(&approved_staker, &approved_receiver, &100);
+
+  assert!(share_token
+    .try_transfer(&approved_staker, &outsider, &100)
+    .is_err());
+
+  // removal never traps funds, the staker can still leave the pool
+  liquid_staking_contract_client.remove_from_allowlist(&owner, &approved_staker);
+
+  assert!(!share_token.allowlisted(&approved_staker));
+  assert!(!liquid_staking_contract_client.is_allowlisted(&approved_staker));
+
+  liquid_staking_contract_client.unstake(&approved_staker, &900);
+
+  assert_eq!(base_token.balance(&approved_staker), 900);
+
+  assert_eq!(
+    liquid_staking_contract_client.try_stake(&approved_staker, &900),
+    Err(Ok(errors::Error::NotAllowlisted))
+  );
+}
+
+#[test]
+#[should_panic(expected = "HostError: Error(Contract, #16)")]
+fn test_allowlist_unauthorized() {
+  let env = Env::default();
+
+  env.mock_all_auths();
+
+  let owner = Address::generate(&env);
+
+  let stranger = Address::generate(&env);
+
+  let base_token = create_token_contract(&env, &owner);
+  let reward_token = create_token_contract(&env, &owner);
+
+  let liquid_staking_contract_client = create_liquid_staking_contract(&env);
+
+  liquid_staking_contract_client.initialize(
+    &base_token.address.clone(),
+    &reward_token.address.clone(),
+    &owner,
+    &install_contract_wasm(&env),
+  );
+
+  liquid_staking_contract_client.add_to_allowlist(&stranger, &stranger);
+}
+
+// FREEZE
+#[test]
+fn test_freeze_shares() {
+  let env = Env::default();
+
+  env.mock_all_auths();
+
+  let owner = Address::generate(&env);
+
+  let staker = Address::generate(&env);
+  let receiver = Address::generate(&env);
+
+  let base_token = create_token_contract(&env, &owner);
+  let reward_token = create_token_contract(&env, &owner);
+
+  let liquid_staking_contract_client = create_liquid_staking_contract(&env);
+
+  liquid_staking_contract_client.initialize(
+    &base_token.address.clone(),
+    &reward_token.address.clone(),
+    &owner,
+    &install_contract_wasm(&env),
+  );
+
+  let state = liquid_staking_contract_client.get_staking_state();
+  let share_token = token::Client::new(&env, &state.share_token);
+
+  base_token.mint(&staker, &1000);
+  liquid_staking_contract_client.stake(&staker, &1000);
+
+  liquid_staking_contract_client.freeze_shares(&owner, &staker);
+
+  assert!(share_token.is_frozen(&staker));
+  assert!(share_token.try_transfer(&staker, &receiver, &100).is_err());
+  assert!(liquid_staking_contract_client
+    .try_unstake(&staker, &100)
+    .is_err());
+
+  liquid_staking_contract_client.unfreeze_shares(&owner, &staker);
+
+  assert!(!share_token.is_frozen(&staker));
+
+  liquid_staking_contract_client.unstake(&staker, &100);
+
+  assert_eq!(base_token.balance(&staker), 100);
+}
+
+#[test]
+fn test_freeze_shares_not_owner() {
+  let env = Env::default();
+
+  env.mock_all_auths();
+
+  let owner = Address::generate(&env);
+
+  let not_owner = Address::generate(&env);
+  let staker = Address::generate(&env);
+
+  let base_token = create_token_contract(&env, &owner);
+  let reward_token = create_token_contract(&env, &owner);
+
+  let liquid_staking_contract_client = create_liquid_staking_contract(&env);
+
+  liquid_staking_contract_client.initialize(
+    &base_token.address.clone(),
+    &reward_token.address.clone(),
+    &owner,
+    &install_contract_wasm(&env),
+  );
+
+  assert_eq!(
+    liquid_staking_contract_client.try_freeze_shares(&not_owner, &staker),
+    Err(Ok(errors::Error::NotOwner))
+  );
+}
+
+// PAUSE
+#[test]
+fn test_pause_stops_user_flows() {
+  let env = Env::default();
+
+  env.mock_all_auths();
+
+  let owner = Address::generate(&env);
+
+  let staker = Address::generate(&env);
+
+  let base_token = create_token_contract(&env, &owner);
+  let reward_token = create_token_contract(&env, &owner);
+
+  let liquid_staking_contract_client = create_liquid_staking_contract(&env);
+
+  liquid_staking_contract_client.initialize(
+    &base_token.address.clone(),
+    &reward_token.address.clone(),
+    &owner,
+    &install_contract_wasm(&env),
+  );
+
+  reward_token.mint(&owner, &1_000_000);
+  liquid_staking_contract_client.add_reward_funds(&owner, &1_000_000);
+
+  base_token.mint(&staker, &2000);
+  liquid_staking_contract_client.stake(&staker, &1000);
+
+  env.ledger().with_mut(|li| {
+    li.timestamp += 100;
+  });
+
+  liquid_staking_contract_client.set_paused(&owner, &true);
+
+  assert!(liquid_staking_contract_client.get_staking_state().paused);
+  assert_eq!(
+    liquid_staking_contract_client.try_stake(&staker, &1000),
+    Err(Ok(errors::Error::Paused))
+  );
+  assert_eq!(
+    liquid_staking_contract_client.try_unstake(&staker, &1000),
+    Err(Ok(errors::Error::Paused))
+  );
+  assert_eq!(
+    liquid_staking_contract_client.try_claim_rewards(&staker),
+    Err(Ok(errors::Error::Paused))
+  );
+  assert_eq!(
+    liquid_staking_contract_client.try_release_vested(&staker),
+    Err(Ok(errors::Error::Paused))
+  );
+  assert_eq!(
+    liquid_staking_contract_client.try_exit_vesting(&staker),
+    Err(Ok(errors::Error::Paused))
+  );
+
+  liquid_staking_contract_client.set_paused(&owner, &false);
+
+  liquid_staking_contract_client.claim_rewards(&staker);
+  liquid_staking_contract_client.unstake(&staker, &1000);
+
+  assert_eq!(reward_token.balance(&staker), 10_000);
+  assert_eq!(base_token.balance(&staker), 2000);
+}
+
+#[test]
+fn test_set_paused_not_owner() {
+  let env = Env::default();
+
+  env.mock_all_auths();
+
+  let owner = Address::generate(&env);
+
+  let not_owner = Address::generate(&env);
+
+  let base_token = create_token_contract(&env, &owner);
+  let reward_token = create_token_contract(&env, &owner);
+
+  let liquid_staking_contract_client = create_liquid_staking_contract(&env);
+
+  liquid_staking_contract_client.initialize(
+    &base_token.address.clone(),
+    &reward_token.address.clone(),
+    &owner,
+    &install_contract_wasm(&env),
+  );
+
+  assert_eq!(
+    liquid_staking_contract_client.try_set_paused(&not_owner, &true),
+    Err(Ok(errors::Error::NotOwner))
+  );
+}
+
+// CLAWBACK
+#[test]
+fn test_clawback_keeps_records_in_sync() {
+  let env = Env::default();
+
+  env.mock_all_auths();
+
+  let owner = Address::generate(&env);
+
+  let staker = Address::generate(&env);
+  let other_staker = Address::generate(&env);
+  let custody = Address::generate(&env);
+
+  let base_token = create_token_contract(&env, &owner);
+  let reward_token = create_token_contract(&env, &owner);
+
+  let liquid_staking_contract_client = create_liquid_staking_contract(&env);
+
+  liquid_staking_contract_client.initialize(
+    &base_token.address.clone(),
+    &reward_token.address.clone(),
+    &owner,
+    &install_contract_wasm(&env),
+  );
+
+  reward_token.mint(&owner, &100000);
+
+  liquid_staking_contract_client.add_reward_funds(&owner, &100000);
+
+  base_token.mint(&staker, &1000);
+  base_token.mint(&other_staker, &1000);
+
+  liquid_staking_contract_client.stake(&staker, &1000);
+  liquid_staking_contract_client.stake(&other_staker, &1000);
+
+  env.ledger().with_mut(|li| {
+    li.timestamp += 10;
+  });
+
+  liquid_staking_contract_client.clawback(&owner, &staker, &600, &custody);
+
+  let state = liquid_staking_contract_client.get_staking_state();
+  let share_token = token::Client::new(&env, &state.share_token);
+
+  assert_eq!(
+    liquid_staking_contract_client
+      .get_user_position(&staker)
+      .balance,
+    400
+  );
+  assert_eq!(share_token.balance(&staker), 400);
+  assert_eq!(
+    liquid_staking_contract_client
+      .get_global_state()
+      .token_supply,
+    1400
+  );
+  assert_eq!(base_token.balance(&custody), 600);
+
+  // rewards accrued before the clawback are kept
+  assert_eq!(liquid_staking_contract_client.earned(&staker), 500);
+
+  liquid_staking_contract_client.unstake(&staker, &400);
+  liquid_staking_contract_client.unstake(&other_staker, &1000);
+
+  assert_eq!(
+    base_token.balance(&liquid_staking_contract_client.address),
+    0
+  );
+}
+
+#[test]
+#[should_panic(expected = "HostError: Error(Contract, #3)")]
+fn test_clawback_not_owner() {
+  let env = Env::default();
+
+  env.mock_all_auths();
+
+  let owner = Address::generate(&env);
+
+  let staker = Address::generate(&env);
+
+  let base_token = create_token_contract(&env, &owner);
+  let reward_token = create_token_contract(&env, &owner);
+
+  let liquid_staking_contract_client = create_liquid_staking_contract(&env);
+
+  liquid_staking_contract_client.initialize(
+    &base_token.address.clone(),
+    &reward_token.address.clone(),
+    &owner,
+    &install_contract_wasm(&env),
+  );
+
+  base_token.mint(&staker, &1000);
+
+  liquid_staking_contract_client.stake(&staker, &1000);
+
+  liquid_staking_contract_client.clawback(&staker, &staker, &1000, &staker);
+}
+
+#[test]
+fn test_clawback_refuses_transferred_shares() {
+  let env = Env::default();
+
+  env.mock_all_auths();
+
+  let owner = Address::generate(&env);
+
+  let staker = Address::generate(&env);
+  let holder = Address::generate(&env);
+
+  let base_token = create_token_contract(&env, &owner);
+  let reward_token = create_token_contract(&env, &owner);
+
+  let liquid_staking_contract_client = create_liquid_staking_contract(&env);
+
+  liquid_staking_contract_client.initialize(
+    &base_token.address.clone(),
+    &reward_token.address.clone(),
+    &owner,
+    &install_contract_wasm(&env),
+  );
+
+  let state = liquid_staking_contract_client.get_staking_state();
+  let share_token = token::Client::new(&env, &state.share_token);
+
+  base_token.mint(&staker, &1000);
+  liquid_staking_contract_client.stake(&staker, &1000);
+
+  share_token.transfer(&staker, &holder, &400);
+
+  // the holder has stXLM but no staked balance in the pool
+  assert_eq!(
+    liquid_staking_contract_client.try_clawback(&owner, &holder, &400, &owner),
+    Err(Ok(errors::Error::NotEnoughFunds))
+  );
+
+  // freezing is what is left for the holder
+  liquid_staking_contract_client.freeze_shares(&owner, &holder);
+
+  assert!(share_token.is_frozen(&holder));
+}
+
+// REWARD VESTING
+#[test]
+fn test_reward_vesting_release() {
+  let env = Env::default();
+
+  env.mock_all_auths();
+
+  let owner = Address::generate(&env);
+
+  let staker = Address::generate(&env);
+
+  let base_token = create_token_contract(&env, &owner);
+  let reward_token = create_token_contract(&env, &owner);
+
+  let liquid_staking_contract_client = create_liquid_staking_contract(&env);
+
+  liquid_staking_contract_client.initialize(
+    &base_token.address.clone(),
+    &reward_token.address.clone(),
+    &owner,
+    &install_contract_wasm(&env),
+  );
+
+  liquid_staking_contract_client.set_reward_vesting(&owner, &true, &100, &1000, &5000);
+
+  reward_token.mint(&owner, &1_000_000);
+  liquid_staking_contract_client.add_reward_funds(&owner, &1_000_000);
+
+  base_token.mint(&staker, &1000);
+  liquid_staking_contract_client.stake(&staker, &1000);
+
+  env.ledger().with_mut(|li| {
+    li.timestamp += 100;
+  });
+
+  // nothing is paid on claim, everything goes into the schedule
+  liquid_staking_contract_client.claim_rewards(&staker);
+
+  assert_eq!(reward_token.balance(&staker), 0);
+  assert_eq!(
+    liquid_staking_contract_client.unvested_rewards(&staker),
+    10_000
+  );
+  assert_eq!(
+    liquid_staking_contract_client.releasable_rewards(&staker),
+    0
+  );
+
+  // still before the cliff
+  env.ledger().with_mut(|li| {
+    li.timestamp += 50;
+  });
+
+  assert_eq!(
+    liquid_staking_contract_client.try_release_vested(&staker),
+    Err(Ok(errors::Error::ThereIsNoRewardToClaim))
+  );
+
+  env.ledger().with_mut(|li| {
+    li.timestamp += 450;
+  });
+
+  assert_eq!(liquid_staking_contract_client.vested_rewards(&staker), 5000);
+  assert_eq!(liquid_staking_contract_client.release_vested(&staker), 5000);
+  assert_eq!(reward_token.balance(&staker), 5000);
+  assert_eq!(
+    liquid_staking_contract_client.releasable_rewards(&staker),
+    0
+  );
+  assert_eq!(liquid_staking_contract_client.vested_rewards(&staker), 5000);
+
+  env.ledger().with_mut(|li| {
+    li.timestamp += 1000;
+  });
+
+  assert_eq!(liquid_staking_contract_client.release_vested(&staker), 5000);
+  assert_eq!(reward_token.balance(&staker), 10_000);
+
+  // fully released schedules are removed
+  assert!(liquid_staking_contract_client
+    .get_vesting_schedules(&staker)
+    .is_empty());
+}
+
+#[test]
+fn test_reward_vesting_claim_keeps_earlier_timeline() {
+  let env = Env::default();
+
+  env.mock_all_auths();
+
+  let owner = Address::generate(&env);
+
+  let staker = Address::generate(&env);
+
+  let base_token = create_token_contract(&env, &owner);
+  let reward_token = create_token_contract(&env, &owner);
+
+  let liquid_staking_contract_client = create_liquid_staking_contract(&env);
+
+  liquid_staking_contract_client.initialize(
+    &base_token.address.clone(),
+    &reward_token.address.clone(),
+    &owner,
+    &install_contract_wasm(&env),
+  );
+
+  liquid_staking_contract_client.set_reward_vesting(&owner, &true, &0, &1000, &0);
+
+  reward_token.mint(&owner, &1_000_000);
+  liquid_staking_contract_client.add_reward_funds(&owner, &1_000_000);
+
+  base_token.mint(&staker, &1000);
+  liquid_staking_contract_client.stake(&staker, &1000);
+
+  env.ledger().with_mut(|li| {
+    li.timestamp += 100;
+  });
+
+  liquid_staking_contract_client.claim_rewards(&staker);
+
+  env.ledger().with_mut(|li| {
+    li.timestamp += 500;
+  });
+
+  // the unlocked half of the first schedule is paid, the new rewards get a schedule of their own
+  liquid_staking_contract_client.claim_rewards(&staker);
+
+  assert_eq!(reward_token.balance(&staker), 5000);
+
+  let schedules = liquid_staking_contract_client.get_vesting_schedules(&staker);
+
+  assert_eq!(schedules.len(), 2);
+  assert_eq!(schedules.get_unchecked(0).total, 10_000);
+  assert_eq!(schedules.get_unchecked(0).released, 5000);
+  assert_eq!(schedules.get_unchecked(0).start, 100);
+  assert_eq!(schedules.get_unchecked(1).total, 50_000);
+  assert_eq!(schedules.get_unchecked(1).start, 600);
+
+  // committed rewards still cover every locked reward
+  let global_state = liquid_staking_contract_client.get_global_state();
+  assert_eq!(global_state.committed_rewards, 55_000);
+
+  // the first rewards are fully unlocked when their own schedule ends
+  env.ledger().with_mut(|li| {
+    li.timestamp = 1100;
+  });
+
+  assert_eq!(
+    liquid_staking_contract_client.release_vested(&staker),
+    5000 + 25_000
+  );
+
+  let schedules = liquid_staking_contract_client.get_vesting_schedules(&staker);
+
+  assert_eq!(schedules.len(), 1);
+  assert_eq!(schedules.get_unchecked(0).total, 50_000);
+  assert_eq!(schedules.get_unchecked(0).released, 25_000);
+}
+
+#[test]
+fn test_reward_vesting_early_exit() {
+  let env = Env::default();
+
+  env.mock_all_auths();
+
+  let owner = Address::generate(&env);
+
+  let staker = Address::generate(&env);
+
+  let base_token = create_token_contract(&env, &owner);
+  let reward_token = create_token_contract(&env, &owner);
+
+  let liquid_staking_contract_client = create_liquid_staking_contract(&env);
+
+  liquid_staking_contract_client.initialize(
+    &base_token.address.clone(),
+    &reward_token.address.clone(),
+    &owner,
+    &install_contract_wasm(&env),
+  );
+
+  liquid_staking_contract_client.set_reward_vesting(&owner, &true, &0, &1000, &5000);
+
+  reward_token.mint(&owner, &1_000_000);
+  liquid_staking_contract_client.add_reward_funds(&owner, &1_000_000);
+
+  base_token.mint(&staker, &1000);
+  liquid_staking_contract_client.stake(&staker, &1000);
+
+  env.ledger().with_mut(|li| {
+    li.timestamp += 100;
+  });
+
+  // leave the pool so nothing else is emitted while the schedule runs
+  liquid_staking_contract_client.unstake(&staker, &1000);
+  liquid_staking_contract_client.claim_rewards(&staker);
+
+  env.ledger().with_mut(|li| {
+    li.timestamp += 250;
+  });
+
+  assert_eq!(liquid_staking_contract_client.release_vested(&staker), 2500);
+
+  let reserve_before = liquid_staking_contract_client
+    .get_global_state()
+    .reward_reserve;
+
+  // half of the 7500 still locked is forfeited
+  assert_eq!(liquid_staking_contract_client.exit_vesting(&staker), 3750);
+  assert_eq!(reward_token.balance(&staker), 6250);
+
+  let global_state = liquid_staking_contract_client.get_global_state();
+  assert_eq!(global_state.reward_reserve, reserve_before + 3750);
+  assert_eq!(global_state.committed_rewards, 0);
+
+  assert!(liquid_staking_contract_client
+    .get_vesting_schedules(&staker)
+    .is_empty());
+  assert_eq!(
+    liquid_staking_contract_client.try_exit_vesting(&staker),
+    Err(Ok(errors::Error::ThereIsNoRewardToClaim))
+  );
+}
+
+#[test]
+fn test_reward_vesting_exit_uses_schedule_terms() {
+  let env = Env::default();
+
+  env.mock_all_auths();
+
+  let owner = Address::generate(&env);
+
+  let staker = Address::generate(&env);
+
+  let base_token = create_token_contract(&env, &owner);
+  let reward_token = create_token_contract(&env, &owner);
+
+  let liquid_staking_contract_client = create_liquid_staking_contract(&env);
+
+  liquid_staking_contract_client.initialize(
+    &base_token.address.clone(),
+    &reward_token.address.clone(),
+    &owner,
+    &install_contract_wasm(&env),
+  );
+
+  liquid_staking_contract_client.set_reward_vesting(&owner, &true, &0, &1000, &1000);
+
+  reward_token.mint(&owner, &1_000_000);
+  liquid_staking_contract_client.add_reward_funds(&owner, &1_000_000);
+
+  base_token.mint(&staker, &1000);
+  liquid_staking_contract_client.stake(&staker, &1000);
+
+  env.ledger().with_mut(|li| {
+    li.timestamp += 100;
+  });
+
+  liquid_staking_contract_client.unstake(&staker, &1000);
+  liquid_staking_contract_client.claim_rewards(&staker);
+
+  let schedule = liquid_staking_contract_client
+    .get_vesting_schedules(&staker)
+    .get_unchecked(0);
+  assert_eq!(schedule.early_exit_penalty_bps, 1000);
+
+  // the owner raises the penalty and stretches the schedule after the claim
+  liquid_staking_contract_client.set_reward_vesting(&owner, &true, &500, &10_000, &5000);
+
+  env.ledger().with_mut(|li| {
+    li.timestamp += 250;
+  });
+
+  // the running schedule still vests over 1000 seconds and forfeits 10% of the 7500 locked
+  assert_eq!(liquid_staking_contract_client.exit_vesting(&staker), 9250);
+  assert_eq!(reward_token.balance(&staker), 9250);
+}
+
+#[test]
+fn test_reward_vesting_zero_duration_pays_at_once() {
+  let env = Env::default();
+
+  env.mock_all_auths();
+
+  let owner = Address::generate(&env);
+
+  let staker = Address::generate(&env);
+
+  let base_token = create_token_contract(&env, &owner);
+  let reward_token = create_token_contract(&env, &owner);
+
+  let liquid_staking_contract_client = create_liquid_staking_contract(&env);
+
+  liquid_staking_contract_client.initialize(
+    &base_token.address.clone(),
+    &reward_token.address.clone(),
+    &owner,
+    &install_contract_wasm(&env),
+  );
+
+  liquid_staking_contract_client.set_reward_vesting(&owner, &true, &0, &0, &0);
+
+  reward_token.mint(&owner, &1_000_000);
+  liquid_staking_contract_client.add_reward_funds(&owner, &1_000_000);
+
+  base_token.mint(&staker, &1000);
+  liquid_staking_contract_client.stake(&staker, &1000);
+
+  env.ledger().with_mut(|li| {
+    li.timestamp += 100;
+  });
+
+  liquid_staking_contract_client.claim_rewards(&staker);
+
+  assert_eq!(reward_token.balance(&staker), 10_000);
+  assert!(liquid_staking_contract_client
+    .get_vesting_schedules(&staker)
+    .is_empty());
+}
+
+#[test]
+fn test_reward_vesting_invalid_config() {
+  let env = Env::default();
+
+  env.mock_all_auths();
+
+  let owner = Address::generate(&env);
+
+  let not_owner = Address::generate(&env);
+
+  let base_token = create_token_contract(&env, &owner);
+  let reward_token = create_token_contract(&env, &owner);
+
+  let liquid_staking_contract_client = create_liquid_staking_contract(&env);
+
+  liquid_staking_contract_client.initialize(
+    &base_token.address.clone(),
+    &reward_token.address.clone(),
+    &owner,
+    &install_contract_wasm(&env),
+  );
+
+  assert_eq!(
+    liquid_staking_contract_client.try_set_reward_vesting(&owner, &true, &1001, &1000, &0),
+    Err(Ok(errors::Error::InvalidSchedule))
+  );
+
+  assert_eq!(
+    liquid_staking_contract_client.try_set_reward_vesting(&owner, &true, &0, &1000, &5001),
+    Err(Ok(errors::Error::FeeTooHigh))
+  );
+
+  assert_eq!(
+    liquid_staking_contract_client.try_set_reward_vesting(&not_owner, &true, &0, &1000, &0),
+    Err(Ok(errors::Error::NotOwner))
+  );
+
+  assert!(!liquid_staking_contract_client.get_vesting_config().enabled);
+}
+
+// GOVERNANCE VOTES
+#[test]
+fn test_stake_and_unstake_checkpoint_votes() {
+  let env = Env::default();
+
+  env.mock_all_auths();
+
+  let owner = Address::generate(&env);
+
+  let staker = Address::generate(&env);
+  let delegatee = Address::generate(&env);
+
+  let base_token = create_token_contract(&env, &owner);
+  let reward_token = create_token_contract(&env, &owner);
+
+  let liquid_staking_contract_client = create_liquid_staking_contract(&env);
+
+  liquid_staking_contract_client.initialize(
+    &base_token.address.clone(),
+    &reward_token.address.clone(),
+    &owner,
+    &install_contract_wasm(&env),
+  );
+
+  let share_token = token::Client::new(
+    &env,
+    &liquid_staking_contract_client
+      .get_staking_state()
+      .share_token,
+  );
+
+  base_token.mint(&staker, &1000);
+
+  env.ledger().with_mut(|li| {
+    li.timestamp = 100;
+  });
+
+  liquid_staking_contract_client.stake(&staker, &1000);
+
+  env.ledger().with_mut(|li| {
+    li.timestamp = 200;
+  });
+
+  share_token.delegate(&staker, &delegatee);
+  liquid_staking_contract_client.unstake(&staker, &400);
+
+  env.ledger().with_mut(|li| {
+    li.timestamp = 300;
+  });
+
+  assert_eq!(share_token.get_votes(&staker), 0);
+  assert_eq!(share_token.get_votes(&delegatee), 600);
+  assert_eq!(share_token.get_past_votes(&staker, &150), 1000);
+  assert_eq!(share_token.get_past_votes(&delegatee, &150), 0);
+  assert_eq!(share_token.get_past_total_supply(&150), 1000);
+  assert_eq!(share_token.get_past_total_supply(&250), 600);
+}
+
+// STRATEGIES
+#[contracttype]
+enum MockStrategyKey {
+  Token,
+  Pool,
+}
+
+// keeps whatever it gets, yield is simulated by minting base tokens straight to it and a loss
+// by burning them with `lose`
+#[contract]
+pub struct MockStrategy;
+
+#[contractimpl]
+impl MockStrategy {
+  pub fn initialize(env: Env, token: Address, pool: Address) {
+    env
+      .storage()
+      .instance()
+      .set(&MockStrategyKey::Token, &token);
+    env.storage().instance().set(&MockStrategyKey::Pool, &pool);
+  }
+
+  pub fn deposit(env: Env, _amount: i128) {
+    let pool: Address = env
+      .storage()
+      .instance()
+      .get(&MockStrategyKey::Pool)
+      .unwrap();
+
+    pool.require_auth();
+  }
+
+  pub fn withdraw(env: Env, amount: i128) {
+    let pool: Address = env
+      .storage()
+      .instance()
+      .get(&MockStrategyKey::Pool)
+      .unwrap();
+
+    pool.require_auth();
+
+    let token: Address = env
+      .storage()
+      .instance()
+      .get(&MockStrategyKey::Token)
+      .unwrap();
+
+    soroban_sdk::token::Client::new(&env, &token).transfer(
+      &env.current_contract_address(),
+      &pool,
+      &amount,
+    );
+  }
+
+  pub fn total_assets(env: Env) -> i128 {
+    let token: Address = env
+      .storage()
+      .instance()
+      .get(&MockStrategyKey::Token)
+      .unwrap();
+
+    soroban_sdk::token::Client::new(&env, &token).balance(&env.current_contract_address())
+  }
+
+  pub fn lose(env: Env, amount: i128) {
+    let token: Address = env
+      .storage()
+      .instance()
+      .get(&MockStrategyKey::Token)
+      .unwrap();
+
+    soroban_sdk::token::Client::new(&env, &token).burn(&env.current_contract_address(), &amount);
+  }
+}
+
+fn create_mock_strategy(e: &Env, token: &Address, pool: &Address) -> Address {
+  let strategy = e.register_contract(None, MockStrategy {});
+
+  MockStrategyClient::new(e, &strategy).initialize(token, pool);
+
+  strategy
+}
+
+#[test]
+fn test_strategy_rebalance_keeps_unstakes_liquid() {
+  let env = Env::default();
+
+  env.mock_all_auths();
+
+  let owner = Address::generate(&env);
+
+  let staker = Address::generate(&env);
+
+  let base_token = create_token_contract(&env, &owner);
+  let reward_token = create_token_contract(&env, &owner);
+
+  let liquid_staking_contract_client = create_liquid_staking_contract(&env);
+
+  liquid_staking_contract_client.initialize(
+    &base_token.address.clone(),
+    &reward_token.address.clone(),
+    &owner,
+    &install_contract_wasm(&env),
+  );
+
+  let strategy = create_mock_strategy(
+    &env,
+    &base_token.address,
+    &liquid_staking_contract_client.address,
+  );
+
+  liquid_staking_contract_client.set_strategy(&owner, &Some(strategy.clone()), &8000);
+
+  base_token.mint(&staker, &1000);
+  liquid_staking_contract_client.stake(&staker, &1000);
+
+  assert_eq!(liquid_staking_contract_client.rebalance(), 800);
+  assert_eq!(base_token.balance(&strategy), 800);
+  assert_eq!(
+    base_token.balance(&liquid_staking_contract_client.address),
+    200
+  );
+
+  // the buffer is not enough, the missing part comes back from the strategy
+  liquid_staking_contract_client.unstake(&staker, &500);
+
+  assert_eq!(base_token.balance(&staker), 500);
+  assert_eq!(
+    liquid_staking_contract_client.get_strategy_state().deployed,
+    500
+  );
+
+  // back to 80% of what is still staked
+  assert_eq!(liquid_staking_contract_client.rebalance(), 400);
+  assert_eq!(
+    base_token.balance(&liquid_staking_contract_client.address),
+    100
+  );
+
+  liquid_staking_contract_client.unstake(&staker, &500);
+
+  assert_eq!(base_token.balance(&staker), 1000);
+  assert_eq!(
+    liquid_staking_contract_client.get_strategy_state().deployed,
+    0
+  );
+}
+
+#[test]
+fn test_strategy_harvest_pays_stakers() {
+  let env = Env::default();
+
+  env.mock_all_auths();
+
+  let owner = Address::generate(&env);
+
+  let staker_1 = Address::generate(&env);
+  let staker_2 = Address::generate(&env);
+
+  let base_token = create_token_contract(&env, &owner);
+  let reward_token = create_token_contract(&env, &owner);
+
+  let liquid_staking_contract_client = create_liquid_staking_contract(&env);
+
+  liquid_staking_contract_client.initialize(
+    &base_token.address.clone(),
+    &reward_token.address.clone(),
+    &owner,
+    &install_contract_wasm(&env),
+  );
+
+  let strategy = create_mock_strategy(
+    &env,
+    &base_token.address,
+    &liquid_staking_contract_client.address,
+  );
+
+  liquid_staking_contract_client.set_strategy(&owner, &Some(strategy.clone()), &5000);
+
+  base_token.mint(&staker_1, &1000);
+  base_token.mint(&staker_2, &1000);
+  liquid_staking_contract_client.stake(&staker_1, &1000);
+  liquid_staking_contract_client.stake(&staker_2, &1000);
+
+  liquid_staking_contract_client.rebalance();
+
+  // nothing to realize yet
+  assert_eq!(liquid_staking_contract_client.harvest(), 0);
+
+  base_token.mint(&strategy, &200);
+
+  assert_eq!(liquid_staking_contract_client.harvest(), 200);
+  assert_eq!(base_token.balance(&strategy), 1000);
+  assert_eq!(liquid_staking_contract_client.earned_fees(&staker_1), 100);
+  assert_eq!(liquid_staking_contract_client.earned_fees(&staker_2), 100);
+
+  liquid_staking_contract_client.claim_rewards(&staker_1);
+
+  assert_eq!(base_token.balance(&staker_1), 100);
+
+  // deployed principal stays protected from recover_token
+  assert_eq!(
+    liquid_staking_contract_client.try_recover_token(&owner, &base_token.address, &1, &owner),
+    Err(Ok(errors::Error::ExceedsRecoverableBalance))
+  );
+}
+
+#[test]
+fn test_switching_strategy_pulls_funds_back() {
+  let env = Env::default();
+
+  env.mock_all_auths();
+
+  let owner = Address::generate(&env);
+
+  let staker = Address::generate(&env);
+
+  let base_token = create_token_contract(&env, &owner);
+  let reward_token = create_token_contract(&env, &owner);
+
+  let liquid_staking_contract_client = create_liquid_staking_contract(&env);
+
+  liquid_staking_contract_client.initialize(
+    &base_token.address.clone(),
+    &reward_token.address.clone(),
+    &owner,
+    &install_contract_wasm(&env),
+  );
+
+  let strategy = create_mock_strategy(
+    &env,
+    &base_token.address,
+    &liquid_staking_contract_client.address,
+  );
+
+  liquid_staking_contract_client.set_strategy(&owner, &Some(strategy.clone()), &9000);
+
+  base_token.mint(&staker, &1000);
+  liquid_staking_contract_client.stake(&staker, &1000);
+  liquid_staking_contract_client.rebalance();
+
+  base_token.mint(&strategy, &50);
+
+  // gains are harvested before the funds come back
+  liquid_staking_contract_client.set_strategy(&owner, &None, &0);
+
+  assert_eq!(base_token.balance(&strategy), 0);
+  assert_eq!(
+    liquid_staking_contract_client.get_strategy_state().deployed,
+    0
+  );
+  assert_eq!(liquid_staking_contract_client.earned_fees(&staker), 50);
+  assert_eq!(
+    base_token.balance(&liquid_staking_contract_client.address),
+    1050
+  );
+
+  assert_eq!(
+    liquid_staking_contract_client.try_rebalance(),
+    Err(Ok(errors::Error::StrategyNotSet))
+  );
+}
+
+#[test]
+fn test_strategy_loss_is_shared_by_stakers() {
+  let env = Env::default();
+
+  env.mock_all_auths();
+
+  let owner = Address::generate(&env);
+
+  let staker_1 = Address::generate(&env);
+  let staker_2 = Address::generate(&env);
+
+  let base_token = create_token_contract(&env, &owner);
+  let reward_token = create_token_contract(&env, &owner);
+
+  let liquid_staking_contract_client = create_liquid_staking_contract(&env);
+
+  liquid_staking_contract_client.initialize(
+    &base_token.address.clone(),
+    &reward_token.address.clone(),
+    &owner,
+    &install_contract_wasm(&env),
+  );
+
+  let share_token = token::Client::new(
+    &env,
+    &liquid_staking_contract_client
+      .get_staking_state()
+      .share_token,
+  );
+
+  let strategy = create_mock_strategy(
+    &env,
+    &base_token.address,
+    &liquid_staking_contract_client.address,
+  );
+
+  liquid_staking_contract_client.set_strategy(&owner, &Some(strategy.clone()), &5000);
+
+  base_token.mint(&staker_1, &600);
+  base_token.mint(&staker_2, &400);
+  liquid_staking_contract_client.stake(&staker_1, &600);
+  liquid_staking_contract_client.stake(&staker_2, &400);
+
+  assert_eq!(liquid_staking_contract_client.rebalance(), 500);
+
+  MockStrategyClient::new(&env, &strategy).lose(&100);
+
+  // the strategy can't return the deployed principal anymore, there is nothing to harvest
+  assert_eq!(liquid_staking_contract_client.harvest(), 0);
+
+  assert_eq!(
+    liquid_staking_contract_client.try_realize_loss(&staker_1),
+    Err(Ok(errors::Error::NotOwner))
+  );
+  assert_eq!(liquid_staking_contract_client.realize_loss(&owner), 100);
+
+  let strategy_state = liquid_staking_contract_client.get_strategy_state();
+  assert_eq!(strategy_state.deployed, 400);
+  assert_eq!(strategy_state.loss, 100);
+
+  assert_eq!(
+    liquid_staking_contract_client
+      .position_summary(&staker_2)
+      .withdrawable,
+    360
+  );
+
+  // each staker loses 10% of its balance, the stXLM backed by it is burned
+  assert_eq!(
+    liquid_staking_contract_client.try_unstake(&staker_1, &600),
+    Err(Ok(errors::Error::NotEnoughFunds))
+  );
+  liquid_staking_contract_client.unstake(&staker_1, &540);
+
+  assert_eq!(base_token.balance(&staker_1), 540);
+  assert_eq!(share_token.balance(&staker_1), 0);
+  assert_eq!(liquid_staking_contract_client.get_strategy_state().loss, 40);
+
+  liquid_staking_contract_client.set_strategy(&owner, &None, &0);
+
+  assert_eq!(base_token.balance(&strategy), 0);
+
+  liquid_staking_contract_client.unstake(&staker_2, &360);
+
+  assert_eq!(base_token.balance(&staker_2), 360);
+  assert_eq!(share_token.balance(&staker_2), 0);
+  assert_eq!(
+    liquid_staking_contract_client
+      .get_global_state()
+      .token_supply,
+    0
+  );
+  assert_eq!(liquid_staking_contract_client.get_strategy_state().loss, 0);
+  assert_eq!(
+    base_token.balance(&liquid_staking_contract_client.address),
+    0
+  );
+}
+
+#[test]
+fn test_switching_away_from_losing_strategy() {
+  let env = Env::default();
+
+  env.mock_all_auths();
+
+  let owner = Address::generate(&env);
+
+  let staker = Address::generate(&env);
+
+  let base_token = create_token_contract(&env, &owner);
+  let reward_token = create_token_contract(&env, &owner);
+
+  let liquid_staking_contract_client = create_liquid_staking_contract(&env);
+
+  liquid_staking_contract_client.initialize(
+    &base_token.address.clone(),
+    &reward_token.address.clone(),
+    &owner,
+    &install_contract_wasm(&env),
+  );
+
+  let strategy = create_mock_strategy(
+    &env,
+    &base_token.address,
+    &liquid_staking_contract_client.address,
+  );
+
+  liquid_staking_contract_client.set_strategy(&owner, &Some(strategy.clone()), &9000);
+
+  base_token.mint(&staker, &1000);
+  liquid_staking_contract_client.stake(&staker, &1000);
+  liquid_staking_contract_client.rebalance();
+
+  MockStrategyClient::new(&env, &strategy).lose(&300);
+
+  // the loss is written off and whatever is left comes back
+  liquid_staking_contract_client.set_strategy(&owner, &None, &0);
+
+  assert_eq!(base_token.balance(&strategy), 0);
+  assert_eq!(
+    liquid_staking_contract_client.get_strategy_state().deployed,
+    0
+  );
+
+  liquid_staking_contract_client.unstake(&staker, &700);
+
+  assert_eq!(base_token.balance(&staker), 700);
+  assert_eq!(
+    liquid_staking_contract_client
+      .get_user_position(&staker)
+      .balance,
+    0
+  );
+}
+
+#[test]
+fn test_set_strategy_invalid() {
+  let env = Env::default();
+
+  env.mock_all_auths();
+
+  let owner = Address::generate(&env);
+
+  let not_owner = Address::generate(&env);
+
+  let base_token = create_token_contract(&env, &owner);
+  let reward_token = create_token_contract(&env, &owner);
+
+  let liquid_staking_contract_client = create_liquid_staking_contract(&env);
+
+  liquid_staking_contract_client.initialize(
+    &base_token.address.clone(),
+    &reward_token.address.clone(),
+    &owner,
+    &install_contract_wasm(&env),
+  );
+
+  let strategy = create_mock_strategy(
+    &env,
+    &base_token.address,
+    &liquid_staking_contract_client.address,
+  );
+
+  assert_eq!(
+    liquid_staking_contract_client.try_set_strategy(&owner, &Some(strategy.clone()), &9001),
+    Err(Ok(errors::Error::DeployRatioTooHigh))
+  );
+
+  assert_eq!(
+    liquid_staking_contract_client.try_set_strategy(&not_owner, &Some(strategy), &5000),
+    Err(Ok(errors::Error::NotOwner))
+  );
+}
+
+// CHECKPOINTS
+// stakes odd balances under a reward fee so neither the fee nor the reward per token divide evenly,
+// the third staker joins later. Checkpoints every few seconds when `keeper` is set
+fn run_checkpoint_pool(keeper: bool) -> ([i128; 3], storage::StakingGlobals) {
+  let env = Env::default();
+
+  env.mock_all_auths();
+
+  let owner = Address::generate(&env);
+
+  let stakers = [
+    Address::generate(&env),
+    Address::generate(&env),
+    Address::generate(&env),
+  ];
+
+  let base_token = create_token_contract(&env, &owner);
+  let reward_token = create_token_contract(&env, &owner);
+
+  let liquid_staking_contract_client = create_liquid_staking_contract(&env);
+
+  liquid_staking_contract_client.initialize(
+    &base_token.address.clone(),
+    &reward_token.address.clone(),
+    &owner,
+    &install_contract_wasm(&env),
+  );
+
+  liquid_staking_contract_client.set_reward_fee(&owner, &750);
+
+  reward_token.mint(&owner, &100000);
+  liquid_staking_contract_client.add_reward_funds(&owner, &100000);
+
+  for (staker, amount) in stakers.iter().zip([333, 1001, 7]) {
+    base_token.mint(staker, &amount);
+  }
+
+  liquid_staking_contract_client.stake(&stakers[0], &333);
+  liquid_staking_contract_client.stake(&stakers[1], &1001);
+
+  for step in 1..=120_u64 {
+    env.ledger().with_mut(|li| {
+      li.timestamp += 3;
+    });
+
+    if step == 50 {
+      liquid_staking_contract_client.stake(&stakers[2], &7);
+    }
+
+    if keeper {
+      assert_eq!(
+        liquid_staking_contract_client.checkpoint(&Address::generate(&env)),
+        0
+      );
+      assert_eq!(
+        liquid_staking_contract_client
+          .get_global_state()
+          .last_updated_time,
+        env.ledger().timestamp()
+      );
+    }
+  }
+
+  let earned = stakers
+    .clone()
+    .map(|staker| liquid_staking_contract_client.earned(&staker));
+
+  // settled by a claim, so both runs compare their stored globals at the same point
+  liquid_staking_contract_client.claim_rewards(&stakers[0]);
+
+  (earned, liquid_staking_contract_client.get_global_state())
+}
+
+#[test]
+fn test_checkpoint_does_not_change_earned() {
+  let (checkpointed_earned, checkpointed) = run_checkpoint_pool(true);
+  let (earned, globals) = run_checkpoint_pool(false);
+
+  assert_eq!(checkpointed_earned, earned);
+  assert_eq!(
+    (
+      checkpointed.reward_per_token_stored,
+      checkpointed.reward_reserve,
+      checkpointed.committed_rewards,
+      checkpointed.protocol_fees,
+    ),
+    (
+      globals.reward_per_token_stored,
+      globals.reward_reserve,
+      globals.committed_rewards,
+      globals.protocol_fees,
+    )
+  );
+}
+
+#[test]
+fn test_checkpoint_pays_keeper_bounty() {
+  let env = Env::default();
+
+  env.mock_all_auths();
+
+  let owner = Address::generate(&env);
+
+  let keeper = Address::generate(&env);
+
+  let staker = Address::generate(&env);
+
+  let base_token = create_token_contract(&env, &owner);
+  let reward_token = create_token_contract(&env, &owner);
+
+  let liquid_staking_contract_client = create_liquid_staking_contract(&env);
+
+  liquid_staking_contract_client.initialize(
+    &base_token.address.clone(),
+    &reward_token.address.clone(),
+    &owner,
+    &install_contract_wasm(&env),
+  );
+
+  let strategy = create_mock_strategy(
+    &env,
+    &base_token.address,
+    &liquid_staking_contract_client.address,
+  );
+
+  liquid_staking_contract_client.set_strategy(&owner, &Some(strategy.clone()), &5000);
+  liquid_staking_contract_client.set_keeper_bounty(&owner, &500);
+
+  reward_token.mint(&owner, &100000);
+  liquid_staking_contract_client.add_reward_funds(&owner, &100000);
+
+  base_token.mint(&staker, &1000);
+  liquid_staking_contract_client.stake(&staker, &1000);
+  liquid_staking_contract_client.rebalance();
+
+  base_token.mint(&strategy, &400);
+
+  env.ledger().with_mut(|li| {
+    li.timestamp += 10;
+  });
+
+  let earned = liquid_staking_contract_client.earned(&staker);
+
+  assert_eq!(liquid_staking_contract_client.checkpoint(&keeper), 20);
+  assert_eq!(base_token.balance(&keeper), 20);
+  assert_eq!(liquid_staking_contract_client.earned(&staker), earned);
+  assert_eq!(liquid_staking_contract_client.earned_fees(&staker), 380);
+  assert_eq!(
+    liquid_staking_contract_client.get_strategy_state().deployed,
+    500
+  );
+
+  // nothing left to harvest, nothing to pay
+  assert_eq!(liquid_staking_contract_client.checkpoint(&keeper), 0);
+  assert_eq!(base_token.balance(&keeper), 20);
+
+  // a plain harvest does not pay any bounty
+  base_token.mint(&strategy, &100);
+
+  assert_eq!(liquid_staking_contract_client.harvest(), 100);
+  assert_eq!(liquid_staking_contract_client.earned_fees(&staker), 480);
+}
+
+#[test]
+fn test_set_keeper_bounty_invalid() {
+  let env = Env::default();
+
+  env.mock_all_auths();
+
+  let owner = Address::generate(&env);
+
+  let not_owner = Address::generate(&env);
+
+  let base_token = create_token_contract(&env, &owner);
+  let reward_token = create_token_contract(&env, &owner);
+
+  let liquid_staking_contract_client = create_liquid_staking_contract(&env);
+
+  assert_eq!(
+    liquid_staking_contract_client.try_checkpoint(&owner),
+    Err(Ok(errors::Error::NotInitialized))
+  );
+
+  liquid_staking_contract_client.initialize(
+    &base_token.address.clone(),
+    &reward_token.address.clone(),
+    &owner,
+    &install_contract_wasm(&env),
+  );
+
+  assert_eq!(
+    liquid_staking_contract_client.try_set_keeper_bounty(&owner, &1001),
+    Err(Ok(errors::Error::FeeTooHigh))
+  );
+
+  assert_eq!(
+    liquid_staking_contract_client.try_set_keeper_bounty(&not_owner, &100),
+    Err(Ok(errors::Error::NotOwner))
+  );
+}
+
+// SIMULATION
+// random action sequences checked against the pool invariants after every step, a failing run
+// prints its seed and can be replayed alone with STAKING_SIM_SEED=<seed> cargo test simulation
+const SIM_SEEDS: u64 = 8;
+const SIM_STEPS: u32 = 50;
+const SIM_USERS: usize = 4;
+
+// splitmix64, good enough to drive the actions and keeps the tests free of extra dependencies
+struct SimRng(u64);
+
+impl SimRng {
+  fn next(&mut self) -> u64 {
+    self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+
+    let mut z = self.0;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+
+    z ^ (z >> 31)
+  }
+
+  fn below(&mut self, bound: u64) -> u64 {
+    self.next() % bound
+  }
+}
+
+#[derive(Debug)]
+enum SimAction {
+  Stake(usize, i128),
+  Unstake(usize, i128),
+  Claim(usize),
+  Fund(i128),
+  Advance(u64),
+}
+
+// what the harness expects the pool to look like, kept without reading the contract
+#[derive(Default)]
+struct SimModel {
+  balances: [i128; SIM_USERS],
+  funded: i128,
+  reserve: i128,
+  emitted: i128,
+  claimed: i128,
+}
+
+impl SimModel {
+  fn supply(&self) -> i128 {
+    self.balances.iter().sum()
+  }
+}
+
+fn next_sim_action(rng: &mut SimRng, balances: &[i128; SIM_USERS]) -> SimAction {
+  let user = rng.below(SIM_USERS as u64) as usize;
+
+  match rng.below(10) {
+    0..=2 => SimAction::Stake(user, 1 + rng.below(5_000) as i128),
+    // sometimes more than the balance, the pool has to refuse it
+    3..=4 => SimAction::Unstake(user, 1 + rng.below(balances[user] as u64 + 100) as i128),
+    5..=6 => SimAction::Claim(user),
+    7 => SimAction::Fund(1 + rng.below(200_000) as i128),
+    _ => SimAction::Advance(1 + rng.below(600)),
+  }
+}
+
+fn run_simulation(seed: u64) {
+  let env = Env::default();
+
+  env.mock_all_auths();
+  env.budget().reset_unlimited();
+
+  let owner = Address::generate(&env);
+
+  let users: std::vec::Vec<Address> = (0..SIM_USERS).map(|_| Address::generate(&env)).collect();
+
+  let base_token = create_token_contract(&env, &owner);
+  let reward_token = create_token_contract(&env, &owner);
+
+  let liquid_staking_contract_client = create_liquid_staking_contract(&env);
+
+  liquid_staking_contract_client.initialize(
+    &base_token.address.clone(),
+    &reward_token.address.clone(),
+    &owner,
+    &install_contract_wasm(&env),
+  );
+
+  let state = liquid_staking_contract_client.get_staking_state();
+  let share_token = token::Client::new(&env, &state.share_token);
+
+  for user in users.iter() {
+    base_token.mint(user, &1_000_000_000);
+  }
+
+  let mut rng = SimRng(seed);
+  let mut model = SimModel::default();
+  let mut trace = std::vec::Vec::new();
+
+  for step in 0..SIM_STEPS {
+    let action = next_sim_action(&mut rng, &model.balances);
+
+    trace.push(std::format!("{step}: {action:?}"));
+
+    let fail = |reason: &str| -> ! {
+      panic!(
+        "simulation seed {seed} failed at step {step}: {reason}\n{}",
+        trace.join("\n")
+      )
+    };
+
+    match action {
+      SimAction::Stake(user, amount) => {
+        if liquid_staking_contract_client
+          .try_stake(&users[user], &amount)
+          .is_err()
+        {
+          fail("stake was refused");
+        }
+
+        model.balances[user] += amount;
+      }
+      SimAction::Unstake(user, amount) => {
+        let result = liquid_staking_contract_client.try_unstake(&users[user], &amount);
+
+        if amount > model.balances[user] {
+          if result != Err(Ok(errors::Error::NotEnoughFunds)) {
+            fail("unstake above the balance was not refused");
+          }
+        } else if result.is_err() {
+          fail("unstake was refused");
+        } else {
+          model.balances[user] -= amount;
+        }
+      }
+      SimAction::Claim(user) => {
+        let before = reward_token.balance(&users[user]);
+
+        let result = liquid_staking_contract_client.try_claim_rewards(&users[user]);
+
+        let paid = reward_token.balance(&users[user]) - before;
+
+        match result {
+          Ok(_) => model.claimed += paid,
+          Err(Ok(errors::Error::ThereIsNoRewardToClaim)) if paid == 0 => {}
+          _ => fail("claim was refused"),
+        }
+      }
+      SimAction::Fund(amount) => {
+        reward_token.mint(&owner, &amount);
+
+        if liquid_staking_contract_client
+          .try_add_reward_funds(&owner, &amount)
+          .is_err()
+        {
+          fail("funding was refused");
+        }
+
+        model.funded += amount;
+        model.reserve += amount;
+      }
+      SimAction::Advance(seconds) => {
+        // nothing is emitted while the pool is empty
+        if model.supply() > 0 {
+          let emission = (seconds as i128 * constants::REWARD_RATE).min(model.reserve);
+
+          model.reserve -= emission;
+          model.emitted += emission;
+        }
+
+        env.ledger().with_mut(|li| {
+          li.timestamp += seconds;
+        });
+      }
+    }
+
+    let global_state = liquid_staking_contract_client.get_global_state();
+
+    let mut recorded = 0;
+    let mut outstanding = 0;
+
+    for (i, user) in users.iter().enumerate() {
+      let balance = liquid_staking_contract_client
+        .get_user_position(user)
+        .balance;
+
+      if balance != model.balances[i] {
+        fail("user balance differs from the model");
+      }
+
+      recorded += balance;
+      outstanding += liquid_staking_contract_client.earned(user);
+    }
+
+    if recorded != global_state.token_supply {
+      fail("user balances do not add up to the token supply");
+    }
+
+    if share_token.total_supply() != global_state.token_supply {
+      fail("stXLM supply differs from the token supply");
+    }
+
+    if base_token.balance(&liquid_staking_contract_client.address) < global_state.token_supply {
+      fail("token supply is not backed by base tokens");
+    }
+
+    if model.claimed + outstanding > model.emitted {
+      fail("claimed and outstanding rewards exceed the emitted rewards");
+    }
+
+    if model.emitted > model.funded {
+      fail("emitted more rewards than funded");
+    }
+  }
+}
+
+#[test]
+fn test_simulation_invariants() {
+  match std::env::var("STAKING_SIM_SEED") {
+    Ok(seed) => run_simulation(seed.parse().expect("STAKING_SIM_SEED must be a u64")),
+    Err(_) => {
+      for seed in 0..SIM_SEEDS {
+        run_simulation(seed);
+      }
+    }
+  }
+}
+
+// REWARD CALCULATOR
+// the same random actions replayed through the contract and through reward_calculator, every
+// intermediate value has to match exactly
+fn run_differential(seed: u64) {
+  let env = Env::default();
+
+  env.mock_all_auths();
+  env.budget().reset_unlimited();
+
+  let owner = Address::generate(&env);
+
+  let users: std::vec::Vec<Address> = (0..SIM_USERS).map(|_| Address::generate(&env)).collect();
+
+  let base_token = create_token_contract(&env, &owner);
+  let reward_token = create_token_contract(&env, &owner);
+
+  let liquid_staking_contract_client = create_liquid_staking_contract(&env);
+
+  liquid_staking_contract_client.initialize(
+    &base_token.address.clone(),
+    &reward_token.address.clone(),
+    &owner,
+    &install_contract_wasm(&env),
+  );
+
+  for user in users.iter() {
+    base_token.mint(user, &1_000_000_000);
+  }
+
+  let mut rng = SimRng(seed);
+  let mut pool = reward_calculator::RewardPool::new(constants::REWARD_RATE, 0);
+  let mut accounts = [reward_calculator::Account::default(); SIM_USERS];
+
+  for step in 0..SIM_STEPS {
+    let now = env.ledger().timestamp();
+
+    let balances = accounts.map(|account| account.balance);
+
+    // the reward fee changes now and then, the library has to follow
+    if rng.below(8) == 0 {
+      let fee_bps = rng.below(constants::MAX_REWARD_FEE_BPS as u64 + 1) as u32;
+
+      liquid_staking_contract_client.set_reward_fee(&owner, &fee_bps);
+      pool.set_reward_fee(fee_bps, now);
+    }
+
+    let action = next_sim_action(&mut rng, &balances);
+
+    // refused calls leave the contract untouched, so they are not replayed
+    match action {
+      SimAction::Stake(user, amount) => {
+        liquid_staking_contract_client.stake(&users[user], &amount);
+        accounts[user].stake(&mut pool, amount, now);
+      }
+      SimAction::Unstake(user, amount) => {
+        if liquid_staking_contract_client
+          .try_unstake(&users[user], &amount)
+          .is_ok()
+        {
+          accounts[user].unstake(&mut pool, amount, now);
+        }
+      }
+      SimAction::Claim(user) => {
+        let before = reward_token.balance(&users[user]);
+
+        if liquid_staking_contract_client
+          .try_claim_rewards(&users[user])
+          .is_ok()
+        {
+          let paid = accounts[user].claim(&mut pool, now);
+
+          assert_eq!(
+            reward_token.balance(&users[user]) - before,
+            paid,
+            "seed {seed} step {step}: claimed amount"
+          );
+        }
+      }
+      SimAction::Fund(amount) => {
+        reward_token.mint(&owner, &amount);
+        liquid_staking_contract_client.add_reward_funds(&owner, &amount);
+        pool.fund(amount, now);
+      }
+      SimAction::Advance(seconds) => {
+        env.ledger().with_mut(|li| {
+          li.timestamp += seconds;
+        });
+      }
+    }
+
+    let now = env.ledger().timestamp();
+
+    let global_state = liquid_staking_contract_client.get_global_state();
+
+    assert_eq!(
+      (
+        global_state.token_supply,
+        global_state.reward_per_token_stored,
+        global_state.last_updated_time,
+        global_state.reward_reserve,
+        global_state.committed_rewards,
+        global_state.protocol_fees,
+        global_state.fee_remainder,
+        global_state.reward_remainder,
+      ),
+      (
+        pool.token_supply,
+        pool.reward_per_token_stored,
+        pool.last_updated_time,
+        pool.reward_reserve,
+        pool.committed_rewards,
+        pool.protocol_fees,
+        pool.fee_remainder,
+        pool.reward_remainder,
+      ),
+      "seed {seed} step {step}: globals after {action:?}"
+    );
+
+    assert_eq!(
+      liquid_staking_contract_client.accrued_protocol_fees(),
+      pool.accrued(now).protocol_fees,
+      "seed {seed} step {step}: accrued protocol fees"
+    );
+
+    for (user, account) in users.iter().zip(accounts.iter()) {
+      assert_eq!(
+        liquid_staking_contract_client.earned(user),
+        account.earned(&pool, now),
+        "seed {seed} step {step}: earned after {action:?}"
+      );
+
+      // emptied records are removed by the contract, only live ones can be compared
+      if account.balance > 0 {
+        let record = liquid_staking_contract_client.get_user_position(user);
+
+        assert_eq!(
+          (
+            record.balance,
+            record.rewards_per_token_paid,
+            record.rewards_to_claim
+          ),
+          (
+            account.balance,
+            account.reward_per_token_paid,
+            account.rewards_to_claim
+          ),
+          "seed {seed} step {step}: record after {action:?}"
+        );
+      }
+    }
+  }
+}
+
+#[test]
+fn test_reward_calculator_matches_contract() {
+  for seed in 0..SIM_SEEDS {
+    run_differential(seed);
+  }
+}
@@ -0,0 +1,252 @@
+use crate::{errors::Error, merkle, storage};
+use soroban_sdk::{contract, contractimpl, symbol_short, token, Address, BytesN, Env, Vec};
+use storage::DistributorRegistry;
+
+#[contract]
+pub struct MerkleDistributor;
+
+#[contractimpl]
+impl MerkleDistributor {
+  pub fn initialize(env: Env, owner: Address) -> Result<storage::DistributorState, Error> {
+    owner.require_auth();
+
+    let mut state = env
+      .storage()
+      .instance()
+      .get(&storage::DISTRIBUTOR_STATE)
+      .unwrap_or(storage::StorageClient::get_default_state(env.clone()));
+
+    if state.initialized {
+      return Err(Error::AlreadyInitialized);
+    }
+
+    state.owner = owner;
+    state.initialized = true;
+
+    env
+      .storage()
+      .instance()
+      .set(&storage::DISTRIBUTOR_STATE, &state);
+
+    Ok(state)
+  }
+
+  pub fn set_owner(env: Env, new_owner: Address, current_owner: Address) -> Result<(), Error> {
+    current_owner.require_auth();
+
+    let mut state = Self::get_distributor_state(env.clone())?;
+
+    if state.owner != current_owner {
+      return Err(Error::NotOwner);
+    }
+
+    state.owner = new_owner;
+
+    env
+      .storage()
+      .instance()
+      .set(&storage::DISTRIBUTOR_STATE, &state);
+
+    Ok(())
+  }
+
+  // creates a new epoch paying `token` to the leaves of `merkle_root`, funded by the owner
+  pub fn create_epoch(
+    env: Env,
+    owner: Address,
+    token: Address,
+    merkle_root: BytesN<32>,
+    amount: i128,
+  ) -> Result<u32, Error> {
+    owner.require_auth();
+
+    let mut state = Self::get_distributor_state(env.clone())?;
+
+    if state.owner != owner {
+      return Err(Error::NotOwner);
+    }
+
+    if amount < 0 {
+      return Err(Error::InvalidAmount);
+    }
+
+    if amount > 0 {
+      token::Client::new(&env, &token).transfer(&owner, &env.current_contract_address(), &amount);
+    }
+
+    let epoch_id = state.epoch_count;
+    let epoch = storage::Epoch {
+      token,
+      merkle_root,
+      funded: amount,
+      claimed: 0,
+    };
+
+    Self::write_epoch(&env, epoch_id, &epoch);
+
+    state.epoch_count += 1;
+
+    env
+      .storage()
+      .instance()
+      .set(&storage::DISTRIBUTOR_STATE, &state);
+
+    Ok(epoch_id)
+  }
+
+  // tops up an existing epoch, in case it was created without enough funds
+  pub fn fund_epoch(
+    env: Env,
+    owner: Address,
+    epoch_id: u32,
+    amount: i128,
+  ) -> Result<storage::Epoch, Error> {
+    owner.require_auth();
+
+    let state = Self::get_distributor_state(env.clone())?;
+
+    if state.owner != owner {
+      return Err(Error::NotOwner);
+    }
+
+    if amount <= 0 {
+      return Err(Error::InvalidAmount);
+    }
+
+    let mut epoch = Self::get_epoch(env.clone(), epoch_id)?;
+
+    token::Client::new(&env, &epoch.token).transfer(
+      &owner,
+      &env.current_contract_address(),
+      &amount,
+    );
+
+    epoch.funded += amount;
+
+    Self::write_epoch(&env, epoch_id, &epoch);
+
+    Ok(epoch)
+  }
+
+  // anyone can submit the claim, the tokens always go to `account`
+  pub fn claim(
+    env: Env,
+    account: Address,
+    epoch_id: u32,
+    index: u32,
+    amount: i128,
+    proof: Vec<BytesN<32>>,
+  ) -> Result<(), Error> {
+    let mut epoch = Self::get_epoch(env.clone(), epoch_id)?;
+
+    if amount <= 0 {
+      return Err(Error::InvalidAmount);
+    }
+
+    if Self::is_claimed(env.clone(), epoch_id, index) {
+      return Err(Error::AlreadyClaimed);
+    }
+
+    let leaf = merkle::leaf_hash(&env, index, &account, amount);
+
+    if !merkle::verify(&env, &proof, &epoch.merkle_root, leaf) {
+      return Err(Error::InvalidProof);
+    }
+
+    if epoch.funded - epoch.claimed < amount {
+      return Err(Error::InsufficientEpochFunds);
+    }
+
+    Self::set_claimed(&env, epoch_id, index);
+
+    epoch.claimed += amount;
+
+    Self::write_epoch(&env, epoch_id, &epoch);
+
+    token::Client::new(&env, &epoch.token).transfer(
+      &env.current_contract_address(),
+      &account,
+      &amount,
+    );
+
+    env.events().publish(
+      (symbol_short!("claimed"), epoch_id, account),
+      (index, amount),
+    );
+
+    Ok(())
+  }
+
+  pub fn is_claimed(env: Env, epoch_id: u32, index: u32) -> bool {
+    let key = DistributorRegistry::ClaimedWord(epoch_id, index / 128);
+    let word: u128 = match env.storage().persistent().get(&key) {
+      Some(word) => {
+        Self::extend_persistent(&env, &key);
+        word
+      }
+      None => 0,
+    };
+
+    word & (1u128 << (index % 128)) != 0
+  }
+
+  pub fn get_epoch(env: Env, epoch_id: u32) -> Result<storage::Epoch, Error> {
+    let key = DistributorRegistry::Epoch(epoch_id);
+    let epoch: storage::Epoch = env
+      .storage()
+      .persistent()
+      .get(&key)
+      .ok_or(Error::EpochNotFound)?;
+
+    Self::extend_persistent(&env, &key);
+
+    Ok(epoch)
+  }
+
+  pub fn get_distributor_state(env: Env) -> Result<storage::DistributorState, Error> {
+    let state: storage::DistributorState = env
+      .storage()
+      .instance()
+      .get(&storage::DISTRIBUTOR_STATE)
+      .unwrap_or(storage::StorageClient::get_default_state(env.clone()));
+
+    if !state.initialized {
+      return Err(Error::NotInitialized);
+    }
+
+    Ok(state)
+  }
+
+  fn set_claimed(env: &Env, epoch_id: u32, index: u32) {
+    let key = DistributorRegistry::ClaimedWord(epoch_id, index / 128);
+    let word: u128 = env.storage().persistent().get(&key).unwrap_or(0);
+
+    env
+      .storage()
+      .persistent()
+      .set(&key, &(word | (1u128 << (index % 128))));
+
+    Self::extend_persistent(env, &key);
+  }
+
+  fn write_epoch(env: &Env, epoch_id: u32, epoch: &storage::Epoch) {
+    let key = DistributorRegistry::Epoch(epoch_id);
+
+    env.storage().persistent().set(&key, epoch);
+
+    Self::extend_persistent(env, &key);
+  }
+
+  fn extend_persistent(env: &Env, key: &DistributorRegistry) {
+    env.storage().persistent().extend_ttl(
+      key,
+      storage::EPOCH_LIFETIME_THRESHOLD,
+      storage::EPOCH_BUMP_AMOUNT,
+    );
+
+    env.storage().instance().extend_ttl(
+      storage::INSTANCE_LIFETIME_THRESHOLD,
+      storage::INSTANCE_BUMP_AMOUNT,
+    );
+  }
+}
@@ -0,0 +1,15 @@
+use soroban_sdk::contracterror;
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum Error {
+  AlreadyInitialized = 1,
+  NotInitialized = 2,
+  NotOwner = 3,
+  InvalidAmount = 4,
+  EpochNotFound = 5,
+  AlreadyClaimed = 6,
+  InvalidProof = 7,
+  InsufficientEpochFunds = 8,
+}
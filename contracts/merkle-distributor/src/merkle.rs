@@ -0,0 +1,38 @@
+use soroban_sdk::{xdr::ToXdr, Address, Bytes, BytesN, Env, Vec};
+
+// a leaf preimage is 64 bytes like a node's, the prefixes keep one from passing for the other
+const LEAF_PREFIX: u8 = 0x00;
+const NODE_PREFIX: u8 = 0x01;
+
+// leaf = sha256(0x00 || index || account xdr || amount), all big endian
+pub fn leaf_hash(env: &Env, index: u32, account: &Address, amount: i128) -> BytesN<32> {
+  let mut data = Bytes::new(env);
+  data.push_back(LEAF_PREFIX);
+  data.extend_from_array(&index.to_be_bytes());
+  data.append(&account.clone().to_xdr(env));
+  data.extend_from_array(&amount.to_be_bytes());
+  env.crypto().sha256(&data).into()
+}
+
+// node = sha256(0x01 || low || high), pairs are sorted before hashing, so proofs don't need to
+// carry the side of each sibling
+pub fn node_hash(env: &Env, a: &BytesN<32>, b: &BytesN<32>) -> BytesN<32> {
+  let mut data = Bytes::new(env);
+  data.push_back(NODE_PREFIX);
+  if a.to_array() <= b.to_array() {
+    data.append(&a.clone().into());
+    data.append(&b.clone().into());
+  } else {
+    data.append(&b.clone().into());
+    data.append(&a.clone().into());
+  }
+  env.crypto().sha256(&data).into()
+}
+
+pub fn verify(env: &Env, proof: &Vec<BytesN<32>>, root: &BytesN<32>, leaf: BytesN<32>) -> bool {
+  let mut computed = leaf;
+  for sibling in proof.iter() {
+    computed = node_hash(env, &computed, &sibling);
+  }
+  computed == *root
+}
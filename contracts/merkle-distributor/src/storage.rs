@@ -0,0 +1,48 @@
+#![allow(unused)]
+use soroban_sdk::{contracttype, symbol_short, Address, BytesN, Env, Symbol};
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DistributorState {
+  pub owner: Address,   // s_owner - creates and funds the epochs
+  pub epoch_count: u32, // s_epochCount - number of epochs created so far, also the next epoch id
+  pub initialized: bool,
+}
+
+pub const DISTRIBUTOR_STATE: Symbol = symbol_short!("state");
+
+pub(crate) const DAY_IN_LEDGERS: u32 = 17280;
+pub(crate) const INSTANCE_BUMP_AMOUNT: u32 = 7 * DAY_IN_LEDGERS;
+pub(crate) const INSTANCE_LIFETIME_THRESHOLD: u32 = INSTANCE_BUMP_AMOUNT - DAY_IN_LEDGERS;
+
+// epochs and claimed words must outlive the claim window, otherwise an archived
+// word would read back as unclaimed
+pub(crate) const EPOCH_BUMP_AMOUNT: u32 = 30 * DAY_IN_LEDGERS;
+pub(crate) const EPOCH_LIFETIME_THRESHOLD: u32 = EPOCH_BUMP_AMOUNT - DAY_IN_LEDGERS;
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Epoch {
+  pub token: Address,          // s_token - SEP-41 token paid out by the epoch
+  pub merkle_root: BytesN<32>, // s_merkleRoot - root of the (index, account, amount) leaves
+  pub funded: i128,            // s_funded - total amount deposited by the owner
+  pub claimed: i128,           // s_claimed - total amount paid out so far
+}
+
+#[contracttype]
+pub enum DistributorRegistry {
+  Epoch(u32),
+  ClaimedWord(u32, u32), // (epoch, index / 128) - bitmap of claimed leaves
+}
+
+pub struct StorageClient;
+
+impl StorageClient {
+  pub fn get_default_state(env: Env) -> DistributorState {
+    DistributorState {
+      owner: env.current_contract_address().clone(),
+      epoch_count: 0,
+      initialized: false,
+    }
+  }
+}
@@ -0,0 +1,484 @@
+#![cfg(test)]
+extern crate std;
+
+use super::*;
+use soroban_sdk::testutils::Address as _;
+use soroban_sdk::xdr::{FromXdr, ToXdr};
+use soroban_sdk::{token, vec, Address, Bytes, BytesN, Env, Vec};
+
+use crate::errors::Error;
+
+fn create_token_contract<'a>(e: &Env, admin: &Address) -> token::StellarAssetClient<'a> {
+  let asset_contract_registration = e.register_stellar_asset_contract_v2(admin.clone());
+
+  token::StellarAssetClient::new(e, &asset_contract_registration.address())
+}
+
+fn create_distributor_contract<'a>(e: &Env) -> contract::MerkleDistributorClient<'a> {
+  contract::MerkleDistributorClient::new(
+    e,
+    &e.register_contract(None, contract::MerkleDistributor {}),
+  )
+}
+
+// builds every level of the tree, an odd node at the end of a level is carried up as is
+fn build_tree(e: &Env, leaves: &[(Address, i128)]) -> std::vec::Vec<std::vec::Vec<BytesN<32>>> {
+  let mut levels = std::vec![leaves
+    .iter()
+    .enumerate()
+    .map(|(index, (account, amount))| merkle::leaf_hash(e, index as u32, account, *amount))
+    .collect::<std::vec::Vec<_>>()];
+
+  while levels.last().unwrap().len() > 1 {
+    let level = levels.last().unwrap();
+    let next = level
+      .chunks(2)
+      .map(|pair| match pair {
+        [a, b] => merkle::node_hash(e, a, b),
+        [a] => a.clone(),
+        _ => unreachable!(),
+      })
+      .collect();
+    levels.push(next);
+  }
+
+  levels
+}
+
+fn root(tree: &[std::vec::Vec<BytesN<32>>]) -> BytesN<32> {
+  tree.last().unwrap()[0].clone()
+}
+
+fn proof(e: &Env, tree: &[std::vec::Vec<BytesN<32>>], index: usize) -> Vec<BytesN<32>> {
+  let mut proof = Vec::new(e);
+  let mut index = index;
+  for level in &tree[..tree.len() - 1] {
+    let sibling = index ^ 1;
+    if sibling < level.len() {
+      proof.push_back(level[sibling].clone());
+    }
+    index /= 2;
+  }
+  proof
+}
+
+fn create_leaves(e: &Env, count: usize) -> std::vec::Vec<(Address, i128)> {
+  (0..count)
+    .map(|i| (Address::generate(e), 100 * (i as i128 + 1)))
+    .collect()
+}
+
+// INITIALIZE
+#[test]
+fn test_contract_initialize() {
+  let env = Env::default();
+  env.mock_all_auths();
+
+  let owner = Address::generate(&env);
+  let distributor = create_distributor_contract(&env);
+
+  let state = distributor.initialize(&owner);
+  assert_eq!(state.owner, owner);
+  assert_eq!(state.epoch_count, 0);
+
+  assert_eq!(
+    distributor.try_initialize(&owner),
+    Err(Ok(Error::AlreadyInitialized))
+  );
+}
+
+#[test]
+fn test_not_initialized() {
+  let env = Env::default();
+  env.mock_all_auths();
+
+  let distributor = create_distributor_contract(&env);
+
+  assert_eq!(
+    distributor.try_get_distributor_state(),
+    Err(Ok(Error::NotInitialized))
+  );
+}
+
+// EPOCHS
+#[test]
+fn test_create_and_fund_epoch() {
+  let env = Env::default();
+  env.mock_all_auths();
+
+  let owner = Address::generate(&env);
+  let distributor = create_distributor_contract(&env);
+  let token = create_token_contract(&env, &owner);
+  let token_client = token::Client::new(&env, &token.address);
+  token.mint(&owner, &1_000);
+
+  distributor.initialize(&owner);
+
+  let tree = build_tree(&env, &create_leaves(&env, 3));
+  let epoch_id = distributor.create_epoch(&owner, &token.address, &root(&tree), &400);
+  assert_eq!(epoch_id, 0);
+
+  let epoch = distributor.fund_epoch(&owner, &epoch_id, &200);
+  assert_eq!(epoch.funded, 600);
+  assert_eq!(epoch.claimed, 0);
+  assert_eq!(token_client.balance(&distributor.address), 600);
+  assert_eq!(distributor.get_distributor_state().epoch_count, 1);
+
+  assert_eq!(distributor.try_get_epoch(&1), Err(Ok(Error::EpochNotFound)));
+  assert_eq!(
+    distributor.try_fund_epoch(&owner, &epoch_id, &0),
+    Err(Ok(Error::InvalidAmount))
+  );
+}
+
+#[test]
+fn test_only_owner_creates_epochs() {
+  let env = Env::default();
+  env.mock_all_auths();
+
+  let owner = Address::generate(&env);
+  let attacker = Address::generate(&env);
+  let distributor = create_distributor_contract(&env);
+  let token = create_token_contract(&env, &owner);
+  token.mint(&attacker, &1_000);
+
+  distributor.initialize(&owner);
+
+  let tree = build_tree(&env, &create_leaves(&env, 2));
+  assert_eq!(
+    distributor.try_create_epoch(&attacker, &token.address, &root(&tree), &100),
+    Err(Ok(Error::NotOwner))
+  );
+
+  distributor.set_owner(&attacker, &owner);
+  assert_eq!(distributor.get_distributor_state().owner, attacker);
+}
+
+// CLAIM
+#[test]
+fn test_claim_every_leaf() {
+  let env = Env::default();
+  env.mock_all_auths();
+
+  let owner = Address::generate(&env);
+  let distributor = create_distributor_contract(&env);
+  let token = create_token_contract(&env, &owner);
+  let token_client = token::Client::new(&env, &token.address);
+  token.mint(&owner, &10_000);
+
+  distributor.initialize(&owner);
+
+  // odd leaf count, so one node gets carried up a level
+  let leaves = create_leaves(&env, 5);
+  let tree = build_tree(&env, &leaves);
+  let total: i128 = leaves.iter().map(|(_, amount)| amount).sum();
+  let epoch_id = distributor.create_epoch(&owner, &token.address, &root(&tree), &total);
+
+  for (index, (account, amount)) in leaves.iter().enumerate() {
+    let index = index as u32;
+    assert!(!distributor.is_claimed(&epoch_id, &index));
+
+    distributor.claim(
+      account,
+      &epoch_id,
+      &index,
+      amount,
+      &proof(&env, &tree, index as usize),
+    );
+
+    assert!(distributor.is_claimed(&epoch_id, &index));
+    assert_eq!(token_client.balance(account), *amount);
+  }
+
+  let epoch = distributor.get_epoch(&epoch_id);
+  assert_eq!(epoch.claimed, total);
+  assert_eq!(token_client.balance(&distributor.address), 0);
+}
+
+#[test]
+fn test_claim_twice() {
+  let env = Env::default();
+  env.mock_all_auths();
+
+  let owner = Address::generate(&env);
+  let distributor = create_distributor_contract(&env);
+  let token = create_token_contract(&env, &owner);
+  token.mint(&owner, &10_000);
+
+  distributor.initialize(&owner);
+
+  let leaves = create_leaves(&env, 4);
+  let tree = build_tree(&env, &leaves);
+  let epoch_id = distributor.create_epoch(&owner, &token.address, &root(&tree), &10_000);
+
+  let (account, amount) = &leaves[1];
+  distributor.claim(account, &epoch_id, &1, amount, &proof(&env, &tree, 1));
+
+  assert_eq!(
+    distributor.try_claim(account, &epoch_id, &1, amount, &proof(&env, &tree, 1)),
+    Err(Ok(Error::AlreadyClaimed))
+  );
+}
+
+#[test]
+fn test_forged_proofs() {
+  let env = Env::default();
+  env.mock_all_auths();
+
+  let owner = Address::generate(&env);
+  let attacker = Address::generate(&env);
+  let distributor = create_distributor_contract(&env);
+  let token = create_token_contract(&env, &owner);
+  let token_client = token::Client::new(&env, &token.address);
+  token.mint(&owner, &10_000);
+
+  distributor.initialize(&owner);
+
+  let leaves = create_leaves(&env, 4);
+  let tree = build_tree(&env, &leaves);
+  let epoch_id = distributor.create_epoch(&owner, &token.address, &root(&tree), &10_000);
+
+  let (account, amount) = &leaves[2];
+  let valid_proof = proof(&env, &tree, 2);
+
+  // inflated amount
+  assert_eq!(
+    distributor.try_claim(account, &epoch_id, &2, &(amount + 1), &valid_proof),
+    Err(Ok(Error::InvalidProof))
+  );
+
+  // someone else's leaf
+  assert_eq!(
+    distributor.try_claim(&attacker, &epoch_id, &2, amount, &valid_proof),
+    Err(Ok(Error::InvalidProof))
+  );
+
+  // wrong index
+  assert_eq!(
+    distributor.try_claim(account, &epoch_id, &3, amount, &valid_proof),
+    Err(Ok(Error::InvalidProof))
+  );
+
+  // proof of another leaf
+  assert_eq!(
+    distributor.try_claim(account, &epoch_id, &2, amount, &proof(&env, &tree, 0)),
+    Err(Ok(Error::InvalidProof))
+  );
+
+  // tampered sibling
+  let mut tampered = valid_proof.clone();
+  tampered.set(0, BytesN::from_array(&env, &[7; 32]));
+  assert_eq!(
+    distributor.try_claim(account, &epoch_id, &2, amount, &tampered),
+    Err(Ok(Error::InvalidProof))
+  );
+
+  // empty proof
+  assert_eq!(
+    distributor.try_claim(account, &epoch_id, &2, amount, &vec![&env]),
+    Err(Ok(Error::InvalidProof))
+  );
+
+  assert!(!distributor.is_claimed(&epoch_id, &2));
+  assert_eq!(token_client.balance(account), 0);
+  assert_eq!(token_client.balance(&attacker), 0);
+}
+
+#[test]
+fn test_leaf_and_node_hashes_are_separated() {
+  let env = Env::default();
+
+  // a G... account address, its xdr is 44 bytes where a contract address takes 40
+  let mut account_xdr = Bytes::new(&env);
+  account_xdr.extend_from_array(&[0, 0, 0, 18, 0, 0, 0, 0, 0, 0, 0, 0]);
+  account_xdr.extend_from_array(&[0xff; 32]);
+  let account = Address::from_xdr(&env, &account_xdr).unwrap();
+  let amount = 1_000_i128;
+
+  // the account leaf preimage has the length of two child hashes
+  let mut preimage = Bytes::new(&env);
+  preimage.extend_from_array(&0_u32.to_be_bytes());
+  preimage.append(&account.clone().to_xdr(&env));
+  preimage.extend_from_array(&amount.to_be_bytes());
+  assert_eq!(preimage.len(), 64);
+
+  let left: BytesN<32> = preimage.slice(0..32).try_into().unwrap();
+  let right: BytesN<32> = preimage.slice(32..64).try_into().unwrap();
+
+  // already in sorted order, so without the prefixes both hashes would be sha256(preimage)
+  assert!(left.to_array() <= right.to_array());
+
+  assert_ne!(
+    merkle::leaf_hash(&env, 0, &account, amount),
+    merkle::node_hash(&env, &left, &right)
+  );
+}
+
+#[test]
+fn test_claim_underfunded_epoch() {
+  let env = Env::default();
+  env.mock_all_auths();
+
+  let owner = Address::generate(&env);
+  let distributor = create_distributor_contract(&env);
+  let token = create_token_contract(&env, &owner);
+  let token_client = token::Client::new(&env, &token.address);
+  token.mint(&owner, &10_000);
+
+  distributor.initialize(&owner);
+
+  let leaves = create_leaves(&env, 2);
+  let tree = build_tree(&env, &leaves);
+  let epoch_id = distributor.create_epoch(&owner, &token.address, &root(&tree), &0);
+
+  let (account, amount) = &leaves[1];
+  assert_eq!(
+    distributor.try_claim(account, &epoch_id, &1, amount, &proof(&env, &tree, 1)),
+    Err(Ok(Error::InsufficientEpochFunds))
+  );
+
+  distributor.fund_epoch(&owner, &epoch_id, amount);
+  distributor.claim(account, &epoch_id, &1, amount, &proof(&env, &tree, 1));
+  assert_eq!(token_client.balance(account), *amount);
+}
+
+#[test]
+fn test_epochs_are_independent() {
+  let env = Env::default();
+  env.mock_all_auths();
+
+  let owner = Address::generate(&env);
+  let distributor = create_distributor_contract(&env);
+  let token_a = create_token_contract(&env, &owner);
+  let token_b = create_token_contract(&env, &owner);
+  token_a.mint(&owner, &10_000);
+  token_b.mint(&owner, &10_000);
+
+  distributor.initialize(&owner);
+
+  let leaves = create_leaves(&env, 3);
+  let tree = build_tree(&env, &leaves);
+
+  // same tree, funded with two different tokens
+  let epoch_a = distributor.create_epoch(&owner, &token_a.address, &root(&tree), &10_000);
+  let epoch_b = distributor.create_epoch(&owner, &token_b.address, &root(&tree), &10_000);
+
+  let (account, amount) = &leaves[0];
+  distributor.claim(account, &epoch_a, &0, amount, &proof(&env, &tree, 0));
+  assert!(!distributor.is_claimed(&epoch_b, &0));
+
+  distributor.claim(account, &epoch_b, &0, amount, &proof(&env, &tree, 0));
+  assert_eq!(
+    token::Client::new(&env, &token_a.address).balance(account),
+    *amount
+  );
+  assert_eq!(
+    token::Client::new(&env, &token_b.address).balance(account),
+    *amount
+  );
+}
+
+#[test]
+fn test_claimed_bitmap_words() {
+  let env = Env::default();
+  env.mock_all_auths();
+  env.budget().reset_unlimited();
+
+  let owner = Address::generate(&env);
+  let distributor = create_distributor_contract(&env);
+  let token = create_token_contract(&env, &owner);
+  token.mint(&owner, &1_000_000);
+
+  distributor.initialize(&owner);
+
+  // enough leaves to spill into a second bitmap word
+  let leaves = create_leaves(&env, 130);
+  let tree = build_tree(&env, &leaves);
+  let epoch_id = distributor.create_epoch(&owner, &token.address, &root(&tree), &1_000_000);
+
+  for index in [127usize, 128, 129] {
+    let (account, amount) = &leaves[index];
+    distributor.claim(
+      account,
+      &epoch_id,
+      &(index as u32),
+      amount,
+      &proof(&env, &tree, index),
+    );
+  }
+
+  assert!(!distributor.is_claimed(&epoch_id, &0));
+  assert!(!distributor.is_claimed(&epoch_id, &126));
+  assert!(distributor.is_claimed(&epoch_id, &127));
+  assert!(distributor.is_claimed(&epoch_id, &128));
+  assert!(distributor.is_claimed(&epoch_id, &129));
+}
+
+#[test]
+fn test_claim_extends_ttl() {
+  use soroban_sdk::testutils::storage::Persistent as _;
+  use soroban_sdk::testutils::{Ledger, LedgerInfo};
+
+  let env = Env::default();
+  env.mock_all_auths();
+  env.budget().reset_unlimited();
+  env.ledger().set(LedgerInfo {
+    timestamp: 0,
+    protocol_version: 21,
+    sequence_number: 10,
+    network_id: Default::default(),
+    base_reserve: 10,
+    min_temp_entry_ttl: 16,
+    min_persistent_entry_ttl: 25 * storage::DAY_IN_LEDGERS,
+    max_entry_ttl: 100 * storage::DAY_IN_LEDGERS,
+  });
+
+  let owner = Address::generate(&env);
+  let distributor = create_distributor_contract(&env);
+  let token = create_token_contract(&env, &owner);
+  token.mint(&owner, &1_000);
+
+  distributor.initialize(&owner);
+
+  let leaves = create_leaves(&env, 2);
+  let tree = build_tree(&env, &leaves);
+  let epoch_id = distributor.create_epoch(&owner, &token.address, &root(&tree), &1_000);
+
+  // close to the end of the default lifetime, without the bump the word would be archived
+  env
+    .ledger()
+    .with_mut(|li| li.sequence_number += 20 * storage::DAY_IN_LEDGERS);
+
+  let (account, amount) = &leaves[0];
+  distributor.claim(account, &epoch_id, &0, amount, &proof(&env, &tree, 0));
+
+  env.as_contract(&distributor.address, || {
+    let epoch_ttl = env
+      .storage()
+      .persistent()
+      .get_ttl(&storage::DistributorRegistry::Epoch(epoch_id));
+    let word_ttl = env
+      .storage()
+      .persistent()
+      .get_ttl(&storage::DistributorRegistry::ClaimedWord(epoch_id, 0));
+
+    assert_eq!(epoch_ttl, storage::EPOCH_BUMP_AMOUNT);
+    assert_eq!(word_ttl, storage::EPOCH_BUMP_AMOUNT);
+  });
+
+  // reading the claimed word keeps it alive as well
+  env
+    .ledger()
+    .with_mut(|li| li.sequence_number += 2 * storage::DAY_IN_LEDGERS);
+  assert!(distributor.is_claimed(&epoch_id, &0));
+
+  env.as_contract(&distributor.address, || {
+    assert_eq!(
+      env
+        .storage()
+        .persistent()
+        .get_ttl(&storage::DistributorRegistry::ClaimedWord(epoch_id, 0)),
+      storage::EPOCH_BUMP_AMOUNT
+    );
+  });
+}
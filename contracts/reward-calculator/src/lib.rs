@@ -0,0 +1,68 @@
+#![no_std]
+
+mod pool;
+mod test;
+
+pub use pool::{Account, RewardPool};
+
+// fixed point precision used for reward per token (7 decimals, same as stXLM)
+pub const DECIMALS: i128 = 10_000_000;
+
+pub const BPS_DENOMINATOR: i128 = 10_000;
+
+// rewards emitted between `last_updated_time` and `now`, capped at the funded reserve, nothing is
+// emitted while the pool is empty
+pub fn pending_emission(
+  token_supply: i128,
+  reward_reserve: i128,
+  last_updated_time: u64,
+  now: u64,
+  rate: i128,
+) -> i128 {
+  if token_supply == 0 {
+    return 0;
+  }
+
+  let emission = (now as i128 - last_updated_time as i128) * rate;
+
+  emission.min(reward_reserve).max(0)
+}
+
+// part of the emission kept by the protocol, rounded down. What the rounding drops is returned as
+// the new remainder and added back on the next call, so settling an emission in one go or in
+// several steps ends up at the same total fee
+pub fn protocol_fee(emission: i128, reward_fee_bps: u32, fee_remainder: i128) -> (i128, i128) {
+  let scaled = emission * reward_fee_bps as i128 + fee_remainder;
+
+  (scaled / BPS_DENOMINATOR, scaled % BPS_DENOMINATOR)
+}
+
+// same carry as protocol_fee, the remainder is the part of the scaled emission the division could
+// not hand out yet. It is only handed out along with the next emission, so an update that emits
+// nothing changes nothing, even if the supply changed since
+pub fn reward_per_token(
+  reward_per_token_stored: i128,
+  token_supply: i128,
+  net_emission: i128,
+  reward_remainder: i128,
+) -> (i128, i128) {
+  if token_supply == 0 || net_emission == 0 {
+    return (reward_per_token_stored, reward_remainder);
+  }
+
+  let scaled = net_emission * DECIMALS + reward_remainder;
+
+  (
+    reward_per_token_stored + scaled / token_supply,
+    scaled % token_supply,
+  )
+}
+
+pub fn earned(
+  balance: i128,
+  reward_per_token: i128,
+  reward_per_token_paid: i128,
+  rewards_to_claim: i128,
+) -> i128 {
+  (balance * (reward_per_token - reward_per_token_paid)) / DECIMALS + rewards_to_claim
+}
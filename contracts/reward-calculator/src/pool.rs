@@ -0,0 +1,136 @@
+use crate::{earned, pending_emission, protocol_fee, reward_per_token};
+
+// mirror of the staking pool's reward accounting, every method settles the pending emission the
+// same way the contract does before it changes anything
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct RewardPool {
+  pub rate: i128,          // rewards emitted per second while the pool is not empty
+  pub reward_fee_bps: u32, // fee on emitted rewards, in basis points
+  pub token_supply: i128,  // base tokens staked in the pool
+  pub reward_per_token_stored: i128, // reward per token at the last update
+  pub last_updated_time: u64, // last time the reward per token was updated
+  pub reward_reserve: i128, // funded rewards that have not been emitted yet
+  pub committed_rewards: i128, // emitted rewards that have not been paid out yet
+  pub protocol_fees: i128, // emitted rewards kept as protocol fees
+  pub fee_remainder: i128, // protocol fee lost to rounding, carried to the next update
+  pub reward_remainder: i128, // scaled rewards the reward per token could not hand out yet
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Account {
+  pub balance: i128,               // base tokens staked by the account
+  pub reward_per_token_paid: i128, // reward per token already accounted for the account
+  pub rewards_to_claim: i128,      // rewards accounted but not claimed yet
+}
+
+impl RewardPool {
+  pub fn new(rate: i128, reward_fee_bps: u32) -> Self {
+    RewardPool {
+      rate,
+      reward_fee_bps,
+      ..Default::default()
+    }
+  }
+
+  pub fn pending_emission(&self, now: u64) -> i128 {
+    pending_emission(
+      self.token_supply,
+      self.reward_reserve,
+      self.last_updated_time,
+      now,
+      self.rate,
+    )
+  }
+
+  pub fn reward_per_token(&self, now: u64) -> i128 {
+    self.accrued(now).reward_per_token_stored
+  }
+
+  pub fn accrue(&mut self, now: u64) {
+    *self = self.accrued(now);
+  }
+
+  // the pool with the pending emission settled
+  pub fn accrued(&self, now: u64) -> Self {
+    let emission = self.pending_emission(now);
+    let (fee, fee_remainder) = protocol_fee(emission, self.reward_fee_bps, self.fee_remainder);
+    let (reward_per_token_stored, reward_remainder) = reward_per_token(
+      self.reward_per_token_stored,
+      self.token_supply,
+      emission - fee,
+      self.reward_remainder,
+    );
+
+    RewardPool {
+      reward_per_token_stored,
+      reward_remainder,
+      fee_remainder,
+      reward_reserve: self.reward_reserve - emission,
+      committed_rewards: self.committed_rewards + emission - fee,
+      protocol_fees: self.protocol_fees + fee,
+      last_updated_time: now,
+      ..*self
+    }
+  }
+
+  pub fn fund(&mut self, amount: i128, now: u64) {
+    self.accrue(now);
+
+    self.reward_reserve += amount;
+  }
+
+  pub fn set_reward_fee(&mut self, reward_fee_bps: u32, now: u64) {
+    self.accrue(now);
+
+    self.reward_fee_bps = reward_fee_bps;
+  }
+
+  pub fn collect_fees(&mut self, now: u64) -> i128 {
+    self.accrue(now);
+
+    core::mem::take(&mut self.protocol_fees)
+  }
+}
+
+impl Account {
+  pub fn earned(&self, pool: &RewardPool, now: u64) -> i128 {
+    earned(
+      self.balance,
+      pool.reward_per_token(now),
+      self.reward_per_token_paid,
+      self.rewards_to_claim,
+    )
+  }
+
+  pub fn update(&mut self, pool: &mut RewardPool, now: u64) {
+    pool.accrue(now);
+
+    self.rewards_to_claim = self.earned(pool, now);
+    self.reward_per_token_paid = pool.reward_per_token_stored;
+  }
+
+  pub fn stake(&mut self, pool: &mut RewardPool, amount: i128, now: u64) {
+    self.update(pool, now);
+
+    self.balance += amount;
+    pool.token_supply += amount;
+  }
+
+  pub fn unstake(&mut self, pool: &mut RewardPool, amount: i128, now: u64) {
+    self.update(pool, now);
+
+    self.balance -= amount;
+    pool.token_supply -= amount;
+  }
+
+  // returns the rewards paid out
+  pub fn claim(&mut self, pool: &mut RewardPool, now: u64) -> i128 {
+    self.update(pool, now);
+
+    let rewards = core::mem::take(&mut self.rewards_to_claim);
+
+    pool.committed_rewards -= rewards;
+
+    rewards
+  }
+}
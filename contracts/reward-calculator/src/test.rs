@@ -0,0 +1,132 @@
+#![cfg(test)]
+extern crate std;
+
+use super::*;
+
+#[test]
+fn test_pending_emission() {
+  assert_eq!(pending_emission(1000, 10_000, 100, 150, 100), 5_000);
+
+  // capped at the reserve
+  assert_eq!(pending_emission(1000, 3_000, 100, 150, 100), 3_000);
+
+  // nothing while the pool is empty
+  assert_eq!(pending_emission(0, 10_000, 100, 150, 100), 0);
+
+  // never negative
+  assert_eq!(pending_emission(1000, 10_000, 150, 100, 100), 0);
+}
+
+#[test]
+fn test_reward_per_token_and_earned() {
+  assert_eq!(protocol_fee(1_000, 750, 0), (75, 0));
+
+  // what rounding drops comes back on the next call
+  assert_eq!(protocol_fee(1, 750, 0), (0, 750));
+  assert_eq!(protocol_fee(13, 750, 750), (1, 500));
+
+  let (reward_per_token, remainder) = reward_per_token(0, 3, 100, 0);
+
+  assert_eq!(reward_per_token, 333_333_333);
+  assert_eq!(remainder, 1);
+  assert_eq!(earned(1, reward_per_token, 0, 0), 33);
+  assert_eq!(earned(2, reward_per_token, 0, 5), 71);
+
+  assert_eq!(
+    super::reward_per_token(reward_per_token, 3, 100, remainder),
+    (666_666_666, 2)
+  );
+
+  // an empty pool keeps the stored value and the remainder
+  assert_eq!(super::reward_per_token(42, 0, 100, 7), (42, 7));
+}
+
+#[test]
+fn test_pool_splits_rewards_by_balance() {
+  let mut pool = RewardPool::new(100, 0);
+
+  let mut alice = Account::default();
+  let mut bob = Account::default();
+
+  pool.fund(1_000_000, 0);
+
+  alice.stake(&mut pool, 1000, 0);
+  bob.stake(&mut pool, 3000, 10);
+
+  // alice alone for 10 seconds, then a quarter of the next 10
+  assert_eq!(alice.earned(&pool, 20), 1_250);
+  assert_eq!(bob.earned(&pool, 20), 750);
+
+  assert_eq!(alice.claim(&mut pool, 20), 1_250);
+  assert_eq!(alice.earned(&pool, 20), 0);
+  assert_eq!(pool.committed_rewards, 750);
+  assert_eq!(pool.reward_reserve, 1_000_000 - 2_000);
+}
+
+#[test]
+fn test_pool_funding_periods() {
+  let mut pool = RewardPool::new(100, 0);
+
+  let mut alice = Account::default();
+
+  alice.stake(&mut pool, 1000, 0);
+
+  // unfunded, nothing is emitted
+  assert_eq!(alice.earned(&pool, 10), 0);
+
+  pool.fund(5_000, 10);
+
+  // 100 per second until the reserve runs out at 60
+  assert_eq!(alice.earned(&pool, 25), 1_500);
+  assert_eq!(alice.earned(&pool, 100), 5_000);
+
+  pool.accrue(100);
+
+  assert_eq!(pool.reward_reserve, 0);
+
+  // a new period starts when the pool is funded again
+  pool.fund(1_000, 200);
+
+  assert_eq!(alice.earned(&pool, 201), 5_100);
+}
+
+#[test]
+fn test_pool_reward_fee() {
+  let mut pool = RewardPool::new(100, 1_000);
+
+  let mut alice = Account::default();
+
+  pool.fund(100_000, 0);
+  alice.stake(&mut pool, 1000, 0);
+
+  assert_eq!(alice.earned(&pool, 10), 900);
+
+  pool.set_reward_fee(0, 10);
+
+  assert_eq!(alice.earned(&pool, 20), 1_900);
+  assert_eq!(pool.collect_fees(20), 100);
+  assert_eq!(pool.protocol_fees, 0);
+}
+
+#[test]
+fn test_accrual_does_not_depend_on_update_frequency() {
+  let mut checkpointed = RewardPool::new(7, 750);
+  let mut alice = Account::default();
+  let mut bob = Account::default();
+
+  checkpointed.fund(1_000_000, 0);
+  alice.stake(&mut checkpointed, 333, 0);
+  bob.stake(&mut checkpointed, 1001, 0);
+
+  let mut lazy = checkpointed;
+
+  for now in 1..=1000 {
+    checkpointed.accrue(now);
+  }
+
+  lazy.accrue(1000);
+
+  assert_eq!(checkpointed, lazy);
+  assert_eq!(alice.earned(&checkpointed, 1000), alice.earned(&lazy, 1000));
+  assert_eq!(bob.earned(&checkpointed, 1000), bob.earned(&lazy, 1000));
+}
@@ -0,0 +1,100 @@
+use crate::{
+  errors::{flatten, ClientError},
+  pool::StakingPool,
+};
+use soroban_sdk::Address;
+
+// collects owner settings and applies them with one call per setter, settings left alone keep
+// their current value in the pool
+pub struct ConfigBuilder<'p, 'a> {
+  pool: &'p StakingPool<'a>,
+  owner: Address,
+  treasury: Option<Address>,
+  reward_fee_bps: Option<u32>,
+  stake_fees: Option<(u32, u32, bool)>,
+  early_unstake_penalty: Option<(u32, u64)>,
+  stake_limits: Option<(i128, i128, i128)>,
+  allowlist_enabled: Option<bool>,
+}
+
+impl<'p, 'a> ConfigBuilder<'p, 'a> {
+  pub fn new(pool: &'p StakingPool<'a>, owner: &Address) -> Self {
+    ConfigBuilder {
+      pool,
+      owner: owner.clone(),
+      treasury: None,
+      reward_fee_bps: None,
+      stake_fees: None,
+      early_unstake_penalty: None,
+      stake_limits: None,
+      allowlist_enabled: None,
+    }
+  }
+
+  pub fn treasury(mut self, treasury: &Address) -> Self {
+    self.treasury = Some(treasury.clone());
+    self
+  }
+
+  pub fn reward_fee(mut self, fee_bps: u32) -> Self {
+    self.reward_fee_bps = Some(fee_bps);
+    self
+  }
+
+  pub fn stake_fees(
+    mut self,
+    stake_fee_bps: u32,
+    unstake_fee_bps: u32,
+    redistribute: bool,
+  ) -> Self {
+    self.stake_fees = Some((stake_fee_bps, unstake_fee_bps, redistribute));
+    self
+  }
+
+  pub fn early_unstake_penalty(mut self, penalty_bps: u32, window: u64) -> Self {
+    self.early_unstake_penalty = Some((penalty_bps, window));
+    self
+  }
+
+  pub fn stake_limits(mut self, min_stake: i128, max_stake_per_user: i128, pool_cap: i128) -> Self {
+    self.stake_limits = Some((min_stake, max_stake_per_user, pool_cap));
+    self
+  }
+
+  pub fn allowlist(mut self, enabled: bool) -> Self {
+    self.allowlist_enabled = Some(enabled);
+    self
+  }
+
+  // stops at the first setter the pool refuses, the ones before it stay applied
+  pub fn apply(self) -> Result<(), ClientError> {
+    let client = self.pool.client();
+    let owner = &self.owner;
+
+    if let Some(treasury) = &self.treasury {
+      flatten(client.try_set_treasury(owner, treasury))?;
+    }
+
+    if let Some(fee_bps) = self.reward_fee_bps {
+      flatten(client.try_set_reward_fee(owner, &fee_bps))?;
+    }
+
+    if let Some((stake_fee_bps, unstake_fee_bps, redistribute)) = self.stake_fees {
+      flatten(client.try_set_stake_fees(owner, &stake_fee_bps, &unstake_fee_bps, &redistribute))?;
+    }
+
+    if let Some((penalty_bps, window)) = self.early_unstake_penalty {
+      flatten(client.try_set_early_unstake_penalty(owner, &penalty_bps, &window))?;
+    }
+
+    if let Some((min_stake, max_stake_per_user, pool_cap)) = self.stake_limits {
+      flatten(client.try_set_stake_limits(owner, &min_stake, &max_stake_per_user, &pool_cap))?;
+    }
+
+    if let Some(enabled) = self.allowlist_enabled {
+      flatten(client.try_set_allowlist_enabled(owner, &enabled))?;
+    }
+
+    Ok(())
+  }
+}
@@ -0,0 +1,48 @@
+use crate::staking::Error;
+use soroban_sdk::{xdr::ScErrorType, InvokeError};
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ClientError {
+  Contract(Error),  // the pool refused the call with one of its errors
+  UnknownCode(u32), // the pool returned an error code this client does not know about
+  Abort,            // the call panicked, ran out of budget or failed in the host
+  Conversion,       // the result could not be converted into the expected type
+}
+
+pub fn decode_error(code: u32) -> Option<Error> {
+  Error::try_from(soroban_sdk::Error::from_contract_error(code)).ok()
+}
+
+impl From<soroban_sdk::Error> for ClientError {
+  fn from(error: soroban_sdk::Error) -> Self {
+    if !error.is_type(ScErrorType::Contract) {
+      return ClientError::Abort;
+    }
+
+    match decode_error(error.get_code()) {
+      Some(error) => ClientError::Contract(error),
+      None => ClientError::UnknownCode(error.get_code()),
+    }
+  }
+}
+
+impl From<InvokeError> for ClientError {
+  fn from(error: InvokeError) -> Self {
+    match error {
+      InvokeError::Contract(code) => soroban_sdk::Error::from_contract_error(code).into(),
+      InvokeError::Abort => ClientError::Abort,
+    }
+  }
+}
+
+// flattens what the generated `try_*` functions return
+pub(crate) fn flatten<T, C, E: Into<ClientError>>(
+  result: Result<Result<T, C>, Result<E, InvokeError>>,
+) -> Result<T, ClientError> {
+  match result {
+    Ok(Ok(value)) => Ok(value),
+    Ok(Err(_)) => Err(ClientError::Conversion),
+    Err(Ok(error)) => Err(error.into()),
+    Err(Err(error)) => Err(error.into()),
+  }
+}
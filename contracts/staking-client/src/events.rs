@@ -0,0 +1,73 @@
+use soroban_sdk::{Address, Env, Symbol, TryFromVal, Val, Vec};
+
+// the pool does not publish events of its own, its stakes, unstakes and clawbacks show up as
+// stXLM mints, burns and clawbacks, decode the events published by the share token
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ShareEvent {
+  Staked {
+    user: Address,
+    shares: i128,
+  },
+  Unstaked {
+    user: Address,
+    shares: i128,
+  },
+  ClawedBack {
+    user: Address,
+    shares: i128,
+  },
+  Transferred {
+    from: Address,
+    to: Address,
+    shares: i128,
+  },
+  Delegated {
+    delegator: Address,
+    previous: Address,
+    delegatee: Address,
+  },
+}
+
+// returns None for events that are not share token movements
+pub fn decode_share_event(env: &Env, topics: &Vec<Val>, data: &Val) -> Option<ShareEvent> {
+  let name = Symbol::try_from_val(env, &topics.get(0)?).ok()?;
+
+  let address = |index: u32| Address::try_from_val(env, &topics.get(index)?).ok();
+
+  if name == Symbol::new(env, "delegate") {
+    let (previous, delegatee) = <(Address, Address)>::try_from_val(env, data).ok()?;
+
+    return Some(ShareEvent::Delegated {
+      delegator: address(1)?,
+      previous,
+      delegatee,
+    });
+  }
+
+  let shares = i128::try_from_val(env, data).ok()?;
+
+  if name == Symbol::new(env, "mint") {
+    Some(ShareEvent::Staked {
+      user: address(2)?,
+      shares,
+    })
+  } else if name == Symbol::new(env, "burn") {
+    Some(ShareEvent::Unstaked {
+      user: address(1)?,
+      shares,
+    })
+  } else if name == Symbol::new(env, "clawback") {
+    Some(ShareEvent::ClawedBack {
+      user: address(2)?,
+      shares,
+    })
+  } else if name == Symbol::new(env, "transfer") {
+    Some(ShareEvent::Transferred {
+      from: address(1)?,
+      to: address(2)?,
+      shares,
+    })
+  } else {
+    None
+  }
+}
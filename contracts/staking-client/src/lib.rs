@@ -0,0 +1,14 @@
+#![no_std]
+
+mod builder;
+mod errors;
+mod events;
+mod pool;
+pub mod staking;
+mod test;
+
+pub use builder::ConfigBuilder;
+pub use errors::{decode_error, ClientError};
+pub use events::{decode_share_event, ShareEvent};
+pub use pool::{Claimed, StakingPool};
+pub use staking::Error;
@@ -0,0 +1,108 @@
+use crate::{
+  builder::ConfigBuilder,
+  errors::{flatten, ClientError},
+  staking,
+};
+use soroban_sdk::{token, Address, Env};
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Claimed {
+  pub rewards: i128, // reward tokens paid, vested ones included
+  pub fees: i128,    // redistributed fees paid, in base tokens
+}
+
+// typed wrapper around the generated client, works from contracts and from native tests alike
+pub struct StakingPool<'a> {
+  env: Env,
+  client: staking::Client<'a>,
+}
+
+impl<'a> StakingPool<'a> {
+  pub fn new(env: &Env, address: &Address) -> Self {
+    StakingPool {
+      env: env.clone(),
+      client: staking::Client::new(env, address),
+    }
+  }
+
+  pub fn address(&self) -> &Address {
+    &self.client.address
+  }
+
+  // the generated client, for anything the wrapper does not cover
+  pub fn client(&self) -> &staking::Client<'a> {
+    &self.client
+  }
+
+  pub fn state(&self) -> Result<staking::StakingContractState, ClientError> {
+    flatten(self.client.try_get_staking_state())
+  }
+
+  pub fn position(&self, user: &Address) -> Result<staking::PositionSummary, ClientError> {
+    flatten(self.client.try_position_summary(user))
+  }
+
+  // stakes and returns the user's record right after it
+  pub fn stake(&self, user: &Address, amount: i128) -> Result<staking::UserRecord, ClientError> {
+    flatten(self.client.try_stake(user, &amount))?;
+
+    Ok(self.client.get_user_position(user))
+  }
+
+  pub fn unstake(&self, user: &Address, amount: i128) -> Result<staking::UserRecord, ClientError> {
+    flatten(self.client.try_unstake(user, &amount))?;
+
+    Ok(self.client.get_user_position(user))
+  }
+
+  // unstakes the whole balance and returns the base tokens received
+  pub fn unstake_all(&self, user: &Address) -> Result<i128, ClientError> {
+    let state = self.state()?;
+
+    let balance = self.client.get_user_position(user).balance;
+
+    if balance == 0 {
+      return Ok(0);
+    }
+
+    let base_token = token::Client::new(&self.env, &state.base_token);
+
+    let before = base_token.balance(user);
+
+    flatten(self.client.try_unstake(user, &balance))?;
+
+    Ok(base_token.balance(user) - before)
+  }
+
+  // claims rewards and fees, then releases whatever already vested, doing nothing is not an error
+  pub fn claim_all(&self, user: &Address) -> Result<Claimed, ClientError> {
+    let state = self.state()?;
+
+    let reward_token = token::Client::new(&self.env, &state.reward_token);
+
+    let before = reward_token.balance(user);
+
+    let fees = self.client.earned_fees(user);
+
+    if fees > 0 || self.client.earned(user) > 0 {
+      flatten(self.client.try_claim_rewards(user))?;
+    }
+
+    if self.client.releasable_rewards(user) > 0 {
+      flatten(self.client.try_release_vested(user))?;
+    }
+
+    let mut rewards = reward_token.balance(user) - before;
+
+    // both come out of the same balance when the pool rewards in its base token
+    if state.reward_token == state.base_token {
+      rewards -= fees;
+    }
+
+    Ok(Claimed { rewards, fees })
+  }
+
+  pub fn configure(&self, owner: &Address) -> ConfigBuilder<'_, 'a> {
+    ConfigBuilder::new(self, owner)
+  }
+}
@@ -0,0 +1,338 @@
+#![cfg(test)]
+extern crate std;
+
+use super::*;
+use soroban_sdk::testutils::{Address as _, Events, Ledger};
+use soroban_sdk::xdr::{ScErrorCode, ScErrorType};
+use soroban_sdk::{contract, contractimpl, token, Address, Env};
+
+mod share_token {
+  soroban_sdk::contractimport!(
+    file = "../liquid-staking-contract/token/soroban_token_contract.wasm"
+  );
+}
+
+struct Setup<'a> {
+  env: Env,
+  owner: Address,
+  alice: Address,
+  base_token: token::StellarAssetClient<'a>,
+  reward_token: token::StellarAssetClient<'a>,
+  pool: StakingPool<'a>,
+}
+
+fn create_token_contract<'a>(e: &Env, admin: &Address) -> token::StellarAssetClient<'a> {
+  let asset_contract_registration = e.register_stellar_asset_contract_v2(admin.clone());
+
+  token::StellarAssetClient::new(e, &asset_contract_registration.address())
+}
+
+fn setup<'a>() -> Setup<'a> {
+  let env = Env::default();
+  env.mock_all_auths_allowing_non_root_auth();
+  env.budget().reset_unlimited();
+
+  let owner = Address::generate(&env);
+  let alice = Address::generate(&env);
+
+  let base_token = create_token_contract(&env, &owner);
+  let reward_token = create_token_contract(&env, &owner);
+
+  let pool = StakingPool::new(&env, &env.register_contract_wasm(None, staking::WASM));
+  pool.client().initialize(
+    &base_token.address,
+    &reward_token.address,
+    &owner,
+    &env.deployer().upload_contract_wasm(share_token::WASM),
+  );
+
+  base_token.mint(&alice, &10_000);
+
+  Setup {
+    env,
+    owner,
+    alice,
+    base_token,
+    reward_token,
+    pool,
+  }
+}
+
+// stakes through the client from inside another contract
+#[contract]
+pub struct StakeRouter;
+
+#[contractimpl]
+impl StakeRouter {
+  pub fn stake_for(env: Env, pool: Address, user: Address, amount: i128) -> i128 {
+    match StakingPool::new(&env, &pool).stake(&user, amount) {
+      Ok(record) => record.balance,
+      Err(ClientError::Contract(error)) => -(error as i128),
+      Err(_) => i128::MIN,
+    }
+  }
+}
+
+#[test]
+fn test_decode_error() {
+  assert_eq!(decode_error(3), Some(Error::NotOwner));
+  assert_eq!(decode_error(19), Some(Error::DeployRatioTooHigh));
+  assert_eq!(decode_error(0), None);
+  assert_eq!(decode_error(999), None);
+
+  assert_eq!(
+    ClientError::from(soroban_sdk::Error::from_contract_error(6)),
+    ClientError::Contract(Error::NotEnoughFunds)
+  );
+  assert_eq!(
+    ClientError::from(soroban_sdk::InvokeError::Contract(17)),
+    ClientError::Contract(Error::InvalidSchedule)
+  );
+  assert_eq!(
+    ClientError::from(soroban_sdk::InvokeError::Abort),
+    ClientError::Abort
+  );
+  assert_eq!(
+    ClientError::from(soroban_sdk::Error::from_contract_error(999)),
+    ClientError::UnknownCode(999)
+  );
+  assert_eq!(
+    ClientError::from(soroban_sdk::Error::from_type_and_code(
+      ScErrorType::Context,
+      ScErrorCode::InvalidAction
+    )),
+    ClientError::Abort
+  );
+}
+
+#[test]
+fn test_stake_and_unstake_return_position() {
+  let setup = setup();
+
+  let record = setup.pool.stake(&setup.alice, 1000).unwrap();
+
+  assert_eq!(record.balance, 1000);
+  assert_eq!(record.address, setup.alice);
+
+  let record = setup.pool.unstake(&setup.alice, 400).unwrap();
+
+  assert_eq!(record.balance, 600);
+
+  assert_eq!(
+    setup.pool.unstake(&setup.alice, 601),
+    Err(ClientError::Contract(Error::NotEnoughFunds))
+  );
+
+  assert_eq!(setup.pool.unstake_all(&setup.alice), Ok(600));
+  assert_eq!(
+    token::Client::new(&setup.env, &setup.base_token.address).balance(&setup.alice),
+    10_000
+  );
+  assert_eq!(setup.pool.unstake_all(&setup.alice), Ok(0));
+}
+
+#[test]
+fn test_config_builder() {
+  let setup = setup();
+
+  let treasury = Address::generate(&setup.env);
+
+  setup
+    .pool
+    .configure(&setup.owner)
+    .treasury(&treasury)
+    .reward_fee(500)
+    .stake_fees(100, 50, true)
+    .early_unstake_penalty(200, 3600)
+    .stake_limits(10, 5000, 0)
+    .apply()
+    .unwrap();
+
+  let state = setup.pool.state().unwrap();
+
+  assert_eq!(state.treasury, treasury);
+  assert_eq!(state.reward_fee_bps, 500);
+  assert_eq!(
+    (
+      state.stake_fee_bps,
+      state.unstake_fee_bps,
+      state.redistribute_fees
+    ),
+    (100, 50, true)
+  );
+  assert_eq!(
+    (state.early_unstake_penalty_bps, state.early_unstake_window),
+    (200, 3600)
+  );
+  assert_eq!(
+    (state.min_stake, state.max_stake_per_user, state.pool_cap),
+    (10, 5000, 0)
+  );
+  assert!(!state.allowlist_enabled);
+
+  assert_eq!(
+    setup
+      .pool
+      .configure(&setup.owner)
+      .reward_fee(10_000)
+      .apply(),
+    Err(ClientError::Contract(Error::FeeTooHigh))
+  );
+
+  let stranger = Address::generate(&setup.env);
+
+  assert_eq!(
+    setup.pool.configure(&stranger).allowlist(true).apply(),
+    Err(ClientError::Contract(Error::NotOwner))
+  );
+
+  assert_eq!(
+    setup.pool.stake(&setup.alice, 5),
+    Err(ClientError::Contract(Error::BelowMinimum))
+  );
+}
+
+#[test]
+fn test_claim_all() {
+  let setup = setup();
+
+  let bob = Address::generate(&setup.env);
+
+  setup.base_token.mint(&bob, &10_000);
+
+  setup
+    .pool
+    .configure(&setup.owner)
+    .stake_fees(100, 0, true)
+    .apply()
+    .unwrap();
+
+  setup.reward_token.mint(&setup.owner, &1_000_000);
+  setup
+    .pool
+    .client()
+    .add_reward_funds(&setup.owner, &1_000_000);
+
+  // nothing to claim is not an error
+  assert_eq!(setup.pool.claim_all(&setup.alice), Ok(Claimed::default()));
+
+  setup.pool.stake(&setup.alice, 1000).unwrap();
+
+  // bob's stake fee goes to alice, rounded down over her 990 staked
+  setup.pool.stake(&bob, 1000).unwrap();
+
+  setup.env.ledger().with_mut(|li| {
+    li.timestamp += 10;
+  });
+
+  let earned = setup.pool.client().earned(&setup.alice);
+
+  assert_eq!(
+    setup.pool.claim_all(&setup.alice),
+    Ok(Claimed {
+      rewards: earned,
+      fees: 9
+    })
+  );
+  assert_eq!(
+    token::Client::new(&setup.env, &setup.reward_token.address).balance(&setup.alice),
+    earned
+  );
+
+  // with vesting, what unlocked since the last claim is released as well
+  setup
+    .pool
+    .client()
+    .set_reward_vesting(&setup.owner, &true, &0, &100, &0);
+
+  setup.env.ledger().with_mut(|li| {
+    li.timestamp += 10;
+  });
+
+  setup.pool.claim_all(&bob).unwrap();
+
+  setup.env.ledger().with_mut(|li| {
+    li.timestamp += 100;
+  });
+
+  let releasable = setup.pool.client().releasable_rewards(&bob);
+
+  assert!(releasable > 0);
+
+  // the first schedule is fully vested and paid, the new rewards start their own schedule
+  let claimed = setup.pool.claim_all(&bob).unwrap();
+
+  assert!(claimed.rewards >= releasable);
+  assert_eq!(setup.pool.client().releasable_rewards(&bob), 0);
+}
+
+#[test]
+fn test_decode_share_events() {
+  let setup = setup();
+
+  let bob = Address::generate(&setup.env);
+
+  setup.pool.stake(&setup.alice, 1000).unwrap();
+
+  let share_token_address = setup.pool.state().unwrap().share_token;
+  let share_token = share_token::Client::new(&setup.env, &share_token_address);
+
+  share_token.transfer(&setup.alice, &bob, &300);
+  share_token.delegate(&setup.alice, &bob);
+
+  setup.pool.unstake(&setup.alice, 200).unwrap();
+
+  let events: std::vec::Vec<ShareEvent> = setup
+    .env
+    .events()
+    .all()
+    .iter()
+    .filter(|(contract, _, _)| *contract == share_token_address)
+    .filter_map(|(_, topics, data)| decode_share_event(&setup.env, &topics, &data))
+    .collect();
+
+  assert_eq!(
+    events,
+    std::vec![
+      ShareEvent::Staked {
+        user: setup.alice.clone(),
+        shares: 1000
+      },
+      ShareEvent::Transferred {
+        from: setup.alice.clone(),
+        to: bob.clone(),
+        shares: 300
+      },
+      ShareEvent::Delegated {
+        delegator: setup.alice.clone(),
+        previous: setup.alice.clone(),
+        delegatee: bob.clone()
+      },
+      ShareEvent::Unstaked {
+        user: setup.alice.clone(),
+        shares: 200
+      },
+    ]
+  );
+}
+
+#[test]
+fn test_client_from_contract() {
+  let setup = setup();
+
+  let router = StakeRouterClient::new(
+    &setup.env,
+    &setup.env.register_contract(None, StakeRouter {}),
+  );
+
+  assert_eq!(
+    router.stake_for(&setup.pool.address().clone(), &setup.alice, &700),
+    700
+  );
+
+  // contract errors come back typed
+  assert_eq!(
+    router.stake_for(&setup.pool.address().clone(), &setup.alice, &-1),
+    -(Error::InvalidAmount as i128)
+  );
+}
@@ -0,0 +1,267 @@
+use crate::{errors::Error, storage};
+use soroban_sdk::{
+  contract, contractimpl, symbol_short, Address, Env, Symbol, TryFromVal, Val, Vec,
+};
+use storage::TimelockRegistry;
+
+#[contract]
+pub struct Timelock;
+
+#[contractimpl]
+impl Timelock {
+  pub fn initialize(
+    env: Env,
+    admin: Address,
+    min_delay: u64,
+    immediate_functions: Vec<(Address, Symbol)>,
+  ) -> Result<storage::TimelockState, Error> {
+    admin.require_auth();
+
+    let mut state = env
+      .storage()
+      .instance()
+      .get(&storage::TIMELOCK_STATE)
+      .unwrap_or(storage::StorageClient::get_default_state(env.clone()));
+
+    if state.initialized {
+      return Err(Error::AlreadyInitialized);
+    }
+
+    state.admin = admin;
+    state.min_delay = min_delay;
+    state.immediate_functions = immediate_functions;
+    state.initialized = true;
+
+    env
+      .storage()
+      .instance()
+      .set(&storage::TIMELOCK_STATE, &state);
+
+    Self::extend_instance(&env);
+
+    Ok(state)
+  }
+
+  // queues a call that can run between `eta` and `eta + GRACE_PERIOD`
+  pub fn queue(env: Env, admin: Address, call: storage::Call, eta: u64) -> Result<u32, Error> {
+    admin.require_auth();
+
+    let state = Self::get_timelock_state(env.clone())?;
+
+    if state.admin != admin {
+      return Err(Error::NotAdmin);
+    }
+
+    if eta < env.ledger().timestamp() + state.min_delay {
+      return Err(Error::EtaTooSoon);
+    }
+
+    if call.contract == env.current_contract_address() && !Self::is_self_call(&env, &call) {
+      return Err(Error::InvalidCall);
+    }
+
+    let id: u32 = env
+      .storage()
+      .instance()
+      .get(&TimelockRegistry::OperationCount)
+      .unwrap_or(0);
+
+    let operation = storage::Operation {
+      id,
+      call: call.clone(),
+      eta,
+      executed: false,
+      canceled: false,
+    };
+
+    Self::write_operation(env.clone(), &operation);
+
+    env
+      .storage()
+      .instance()
+      .set(&TimelockRegistry::OperationCount, &(id + 1));
+
+    env.events().publish(
+      (symbol_short!("queued"), id),
+      (call.contract, call.function, call.args, eta),
+    );
+
+    Ok(id)
+  }
+
+  // anyone can run a queued operation once its eta is reached
+  pub fn execute(env: Env, id: u32) -> Result<(), Error> {
+    Self::get_timelock_state(env.clone())?;
+
+    let mut operation = Self::get_operation(env.clone(), id)?;
+
+    if operation.executed || operation.canceled {
+      return Err(Error::AlreadyDone);
+    }
+
+    let now = env.ledger().timestamp();
+
+    if now < operation.eta {
+      return Err(Error::NotReady);
+    }
+
+    if now > operation.eta + storage::GRACE_PERIOD {
+      return Err(Error::Expired);
+    }
+
+    // marked before the call so the call cannot execute the operation again
+    operation.executed = true;
+
+    Self::write_operation(env.clone(), &operation);
+
+    Self::invoke(env.clone(), &operation.call)?;
+
+    env.events().publish((symbol_short!("executed"), id), ());
+
+    Ok(())
+  }
+
+  pub fn cancel(env: Env, admin: Address, id: u32) -> Result<(), Error> {
+    admin.require_auth();
+
+    let state = Self::get_timelock_state(env.clone())?;
+
+    if state.admin != admin {
+      return Err(Error::NotAdmin);
+    }
+
+    let mut operation = Self::get_operation(env.clone(), id)?;
+
+    if operation.executed || operation.canceled {
+      return Err(Error::AlreadyDone);
+    }
+
+    operation.canceled = true;
+
+    Self::write_operation(env.clone(), &operation);
+
+    env.events().publish((symbol_short!("canceled"), id), ());
+
+    Ok(())
+  }
+
+  // emergency levers such as pausing are not worth anything behind a delay. Only the listed
+  // functions of the listed contracts qualify, the same name on another contract still waits
+  pub fn execute_immediate(env: Env, admin: Address, call: storage::Call) -> Result<(), Error> {
+    admin.require_auth();
+
+    let state = Self::get_timelock_state(env.clone())?;
+
+    if state.admin != admin {
+      return Err(Error::NotAdmin);
+    }
+
+    let target = (call.contract.clone(), call.function.clone());
+
+    if call.contract == env.current_contract_address()
+      || !state.immediate_functions.contains(&target)
+    {
+      return Err(Error::NotImmediate);
+    }
+
+    env.invoke_contract::<Val>(&call.contract, &call.function, call.args.clone());
+
+    Self::extend_instance(&env);
+
+    env.events().publish(
+      (symbol_short!("immediate"), call.contract, call.function),
+      call.args,
+    );
+
+    Ok(())
+  }
+
+  pub fn get_operation(env: Env, id: u32) -> Result<storage::Operation, Error> {
+    let key = TimelockRegistry::Operation(id);
+    let operation: storage::Operation = env
+      .storage()
+      .persistent()
+      .get(&key)
+      .ok_or(Error::OperationNotFound)?;
+
+    Self::extend_persistent(&env, &key);
+
+    Ok(operation)
+  }
+
+  pub fn get_timelock_state(env: Env) -> Result<storage::TimelockState, Error> {
+    let state: storage::TimelockState = env
+      .storage()
+      .instance()
+      .get(&storage::TIMELOCK_STATE)
+      .unwrap_or(storage::StorageClient::get_default_state(env.clone()));
+
+    if !state.initialized {
+      return Err(Error::NotInitialized);
+    }
+
+    Ok(state)
+  }
+
+  fn write_operation(env: Env, operation: &storage::Operation) {
+    let key = TimelockRegistry::Operation(operation.id);
+
+    env.storage().persistent().set(&key, operation);
+
+    Self::extend_persistent(&env, &key);
+  }
+
+  fn extend_persistent(env: &Env, key: &TimelockRegistry) {
+    env.storage().persistent().extend_ttl(
+      key,
+      storage::OPERATION_LIFETIME_THRESHOLD,
+      storage::OPERATION_BUMP_AMOUNT,
+    );
+
+    Self::extend_instance(env);
+  }
+
+  fn extend_instance(env: &Env) {
+    env.storage().instance().extend_ttl(
+      storage::INSTANCE_LIFETIME_THRESHOLD,
+      storage::INSTANCE_BUMP_AMOUNT,
+    );
+  }
+
+  // the timelock's own settings are changed through the queue as well
+  fn is_self_call(env: &Env, call: &storage::Call) -> bool {
+    call.function == Symbol::new(env, "set_admin")
+      || call.function == Symbol::new(env, "set_min_delay")
+      || call.function == Symbol::new(env, "set_immediate_functions")
+  }
+
+  fn invoke(env: Env, call: &storage::Call) -> Result<(), Error> {
+    if call.contract != env.current_contract_address() {
+      env.invoke_contract::<Val>(&call.contract, &call.function, call.args.clone());
+      return Ok(());
+    }
+
+    // soroban does not let a contract call itself, so these are applied in place
+    let mut state = Self::get_timelock_state(env.clone())?;
+
+    let arg = call.args.get(0).ok_or(Error::InvalidCall)?;
+
+    if call.function == Symbol::new(&env, "set_admin") {
+      state.admin = Address::try_from_val(&env, &arg).map_err(|_| Error::InvalidCall)?;
+    } else if call.function == Symbol::new(&env, "set_min_delay") {
+      state.min_delay = u64::try_from_val(&env, &arg).map_err(|_| Error::InvalidCall)?;
+    } else if call.function == Symbol::new(&env, "set_immediate_functions") {
+      state.immediate_functions =
+        Vec::<(Address, Symbol)>::try_from_val(&env, &arg).map_err(|_| Error::InvalidCall)?;
+    } else {
+      return Err(Error::InvalidCall);
+    }
+
+    env
+      .storage()
+      .instance()
+      .set(&storage::TIMELOCK_STATE, &state);
+
+    Ok(())
+  }
+}
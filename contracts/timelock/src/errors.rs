@@ -0,0 +1,17 @@
+use soroban_sdk::contracterror;
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum Error {
+  AlreadyInitialized = 1,
+  NotInitialized = 2,
+  NotAdmin = 3,
+  EtaTooSoon = 4,
+  OperationNotFound = 5,
+  NotReady = 6,
+  Expired = 7,
+  AlreadyDone = 8,
+  NotImmediate = 9,
+  InvalidCall = 10,
+}
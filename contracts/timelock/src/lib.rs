@@ -0,0 +1,6 @@
+#![no_std]
+
+mod contract;
+mod errors;
+mod storage;
+mod test;
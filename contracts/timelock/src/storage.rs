@@ -0,0 +1,62 @@
+#![allow(unused)]
+use soroban_sdk::{contracttype, symbol_short, Address, Env, Symbol, Val, Vec};
+
+// queued operations that are not executed within this window after their eta can't run anymore
+pub const GRACE_PERIOD: u64 = 14 * 24 * 60 * 60;
+
+pub(crate) const DAY_IN_LEDGERS: u32 = 17280;
+pub(crate) const INSTANCE_BUMP_AMOUNT: u32 = 7 * DAY_IN_LEDGERS;
+pub(crate) const INSTANCE_LIFETIME_THRESHOLD: u32 = INSTANCE_BUMP_AMOUNT - DAY_IN_LEDGERS;
+
+// queued operations have to outlive their delay and the grace period, and are bumped every time
+// they are touched
+pub(crate) const OPERATION_BUMP_AMOUNT: u32 = 30 * DAY_IN_LEDGERS;
+pub(crate) const OPERATION_LIFETIME_THRESHOLD: u32 = OPERATION_BUMP_AMOUNT - DAY_IN_LEDGERS;
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TimelockState {
+  pub admin: Address, // s_admin - queues, cancels and runs immediate operations
+  pub min_delay: u64, // s_minDelay - shortest time between queue and eta
+  pub immediate_functions: Vec<(Address, Symbol)>, // s_immediateFunctions - calls without delay
+  pub initialized: bool,
+}
+
+pub const TIMELOCK_STATE: Symbol = symbol_short!("state");
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Call {
+  pub contract: Address, // contract invoked by the timelock
+  pub function: Symbol,  // function name
+  pub args: Vec<Val>,    // function arguments
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Operation {
+  pub id: u32,
+  pub call: Call, // call made on execute
+  pub eta: u64,   // timestamp the operation can be executed at
+  pub executed: bool,
+  pub canceled: bool,
+}
+
+#[contracttype]
+pub enum TimelockRegistry {
+  OperationCount,
+  Operation(u32),
+}
+
+pub struct StorageClient;
+
+impl StorageClient {
+  pub fn get_default_state(env: Env) -> TimelockState {
+    TimelockState {
+      admin: env.current_contract_address().clone(),
+      min_delay: 0,
+      immediate_functions: Vec::new(&env),
+      initialized: false,
+    }
+  }
+}
@@ -0,0 +1,531 @@
+#![cfg(test)]
+extern crate std;
+
+use super::*;
+use soroban_sdk::testutils::{Address as _, Events, Ledger, MockAuth, MockAuthInvoke};
+use soroban_sdk::{symbol_short, token, vec, Address, Env, IntoVal, Symbol, Val, Vec};
+
+use crate::errors::Error;
+use crate::storage::{Call, GRACE_PERIOD};
+
+mod share_token {
+  soroban_sdk::contractimport!(
+    file = "../liquid-staking-contract/token/soroban_token_contract.wasm"
+  );
+}
+
+mod staking {
+  soroban_sdk::contractimport!(
+    file = "../liquid-staking-contract/wasm/liquid_staking_contract.wasm"
+  );
+}
+
+const MIN_DELAY: u64 = 2 * 24 * 60 * 60;
+
+struct Setup<'a> {
+  env: Env,
+  admin: Address,
+  staking_pool: staking::Client<'a>,
+  timelock: contract::TimelockClient<'a>,
+}
+
+fn create_token_contract<'a>(e: &Env, admin: &Address) -> token::StellarAssetClient<'a> {
+  let asset_contract_registration = e.register_stellar_asset_contract_v2(admin.clone());
+
+  token::StellarAssetClient::new(e, &asset_contract_registration.address())
+}
+
+// a staking pool owned by a timelock, pausing the pool is the emergency lever
+fn setup<'a>() -> Setup<'a> {
+  let env = Env::default();
+  env.mock_all_auths();
+  env.budget().reset_unlimited();
+
+  let admin = Address::generate(&env);
+
+  let base_token = create_token_contract(&env, &admin);
+  let reward_token = create_token_contract(&env, &admin);
+
+  let staking_pool = staking::Client::new(&env, &env.register_contract_wasm(None, staking::WASM));
+  staking_pool.initialize(
+    &base_token.address,
+    &reward_token.address,
+    &admin,
+    &env.deployer().upload_contract_wasm(share_token::WASM),
+  );
+
+  let timelock =
+    contract::TimelockClient::new(&env, &env.register_contract(None, contract::Timelock {}));
+  timelock.initialize(
+    &admin,
+    &MIN_DELAY,
+    &vec![
+      &env,
+      (
+        staking_pool.address.clone(),
+        Symbol::new(&env, "set_paused"),
+      ),
+    ],
+  );
+
+  // mocked here, test_ownership_handover_to_timelock runs the handover with real signatures
+  staking_pool.set_owner(&timelock.address, &admin);
+  staking_pool.accept_owner(&timelock.address);
+
+  env.ledger().with_mut(|li| {
+    li.timestamp = 1000;
+  });
+
+  Setup {
+    env,
+    admin,
+    staking_pool,
+    timelock,
+  }
+}
+
+fn staking_call(setup: &Setup, function: &str, args: Vec<Val>) -> Call {
+  Call {
+    contract: setup.staking_pool.address.clone(),
+    function: Symbol::new(&setup.env, function),
+    args,
+  }
+}
+
+fn set_reward_fee_call(setup: &Setup, fee_bps: u32) -> Call {
+  staking_call(
+    setup,
+    "set_reward_fee",
+    (setup.timelock.address.clone(), fee_bps).into_val(&setup.env),
+  )
+}
+
+fn jump_to(env: &Env, timestamp: u64) {
+  env.ledger().with_mut(|li| {
+    li.timestamp = timestamp;
+  });
+}
+
+// INITIALIZE
+#[test]
+fn test_initialize_twice() {
+  let setup = setup();
+
+  assert_eq!(
+    setup
+      .timelock
+      .try_initialize(&setup.admin, &0, &Vec::new(&setup.env)),
+    Err(Ok(Error::AlreadyInitialized))
+  );
+}
+
+// QUEUE AND EXECUTE
+#[test]
+fn test_queue_and_execute() {
+  let setup = setup();
+  let timelock = &setup.timelock;
+
+  let eta = 1000 + MIN_DELAY;
+  let id = timelock.queue(&setup.admin, &set_reward_fee_call(&setup, 1000), &eta);
+
+  let event = setup.env.events().all().last().unwrap();
+  assert_eq!(event.0, timelock.address);
+  assert_eq!(event.1, (symbol_short!("queued"), id).into_val(&setup.env));
+
+  jump_to(&setup.env, eta - 1);
+  assert_eq!(timelock.try_execute(&id), Err(Ok(Error::NotReady)));
+
+  jump_to(&setup.env, eta);
+  timelock.execute(&id);
+
+  let event = setup.env.events().all().last().unwrap();
+  assert_eq!(
+    event.1,
+    (symbol_short!("executed"), id).into_val(&setup.env)
+  );
+
+  assert_eq!(setup.staking_pool.get_staking_state().reward_fee_bps, 1000);
+  assert!(timelock.get_operation(&id).executed);
+  assert_eq!(timelock.try_execute(&id), Err(Ok(Error::AlreadyDone)));
+}
+
+#[test]
+fn test_operation_ttl_is_extended() {
+  use soroban_sdk::testutils::storage::{Instance as _, Persistent as _};
+
+  let setup = setup();
+  let timelock = &setup.timelock;
+
+  let id = timelock.queue(
+    &setup.admin,
+    &set_reward_fee_call(&setup, 1000),
+    &(1000 + MIN_DELAY),
+  );
+
+  let ttls = || {
+    setup.env.as_contract(&timelock.address, || {
+      (
+        setup
+          .env
+          .storage()
+          .persistent()
+          .get_ttl(&storage::TimelockRegistry::Operation(id)),
+        setup.env.storage().instance().get_ttl(),
+      )
+    })
+  };
+
+  assert_eq!(
+    ttls(),
+    (
+      storage::OPERATION_BUMP_AMOUNT,
+      storage::INSTANCE_BUMP_AMOUNT
+    )
+  );
+
+  // reading the operation keeps it and the timelock alive while it waits for its eta
+  setup.env.ledger().with_mut(|li| {
+    li.sequence_number += 2 * storage::DAY_IN_LEDGERS;
+  });
+  assert!(!timelock.get_operation(&id).executed);
+  assert_eq!(
+    ttls(),
+    (
+      storage::OPERATION_BUMP_AMOUNT,
+      storage::INSTANCE_BUMP_AMOUNT
+    )
+  );
+}
+
+#[test]
+fn test_eta_too_soon() {
+  let setup = setup();
+
+  assert_eq!(
+    setup.timelock.try_queue(
+      &setup.admin,
+      &set_reward_fee_call(&setup, 1000),
+      &(1000 + MIN_DELAY - 1)
+    ),
+    Err(Ok(Error::EtaTooSoon))
+  );
+}
+
+#[test]
+fn test_only_admin_queues_and_cancels() {
+  let setup = setup();
+  let timelock = &setup.timelock;
+
+  let stranger = Address::generate(&setup.env);
+  let eta = 1000 + MIN_DELAY;
+
+  assert_eq!(
+    timelock.try_queue(&stranger, &set_reward_fee_call(&setup, 1000), &eta),
+    Err(Ok(Error::NotAdmin))
+  );
+
+  let id = timelock.queue(&setup.admin, &set_reward_fee_call(&setup, 1000), &eta);
+
+  assert_eq!(
+    timelock.try_cancel(&stranger, &id),
+    Err(Ok(Error::NotAdmin))
+  );
+}
+
+#[test]
+fn test_cancel() {
+  let setup = setup();
+  let timelock = &setup.timelock;
+
+  let eta = 1000 + MIN_DELAY;
+  let id = timelock.queue(&setup.admin, &set_reward_fee_call(&setup, 1000), &eta);
+
+  timelock.cancel(&setup.admin, &id);
+
+  let event = setup.env.events().all().last().unwrap();
+  assert_eq!(
+    event.1,
+    (symbol_short!("canceled"), id).into_val(&setup.env)
+  );
+
+  jump_to(&setup.env, eta);
+  assert_eq!(timelock.try_execute(&id), Err(Ok(Error::AlreadyDone)));
+  assert_eq!(setup.staking_pool.get_staking_state().reward_fee_bps, 0);
+}
+
+#[test]
+fn test_expired_operation() {
+  let setup = setup();
+  let timelock = &setup.timelock;
+
+  let eta = 1000 + MIN_DELAY;
+  let id = timelock.queue(&setup.admin, &set_reward_fee_call(&setup, 1000), &eta);
+
+  jump_to(&setup.env, eta + GRACE_PERIOD + 1);
+  assert_eq!(timelock.try_execute(&id), Err(Ok(Error::Expired)));
+}
+
+#[test]
+fn test_recover_token_through_timelock() {
+  let setup = setup();
+  let timelock = &setup.timelock;
+
+  let stray_token = create_token_contract(&setup.env, &setup.admin);
+  stray_token.mint(&setup.staking_pool.address, &500);
+
+  let eta = 1000 + MIN_DELAY;
+  let id = timelock.queue(
+    &setup.admin,
+    &staking_call(
+      &setup,
+      "recover_token",
+      (
+        timelock.address.clone(),
+        stray_token.address.clone(),
+        500_i128,
+        setup.admin.clone(),
+      )
+        .into_val(&setup.env),
+    ),
+    &eta,
+  );
+
+  jump_to(&setup.env, eta);
+  timelock.execute(&id);
+
+  assert_eq!(
+    token::Client::new(&setup.env, &stray_token.address).balance(&setup.admin),
+    500
+  );
+}
+
+#[test]
+fn test_set_owner_through_timelock() {
+  let setup = setup();
+  let timelock = &setup.timelock;
+
+  let new_owner = Address::generate(&setup.env);
+
+  let eta = 1000 + MIN_DELAY;
+  let id = timelock.queue(
+    &setup.admin,
+    &staking_call(
+      &setup,
+      "set_owner",
+      (new_owner.clone(), timelock.address.clone()).into_val(&setup.env),
+    ),
+    &eta,
+  );
+
+  jump_to(&setup.env, eta);
+
+  // nobody signs, the timelock authorizes set_owner by being the caller
+  setup.env.set_auths(&[]);
+  timelock.execute(&id);
+
+  assert_eq!(
+    setup.staking_pool.get_staking_state().pending_owner,
+    Some(new_owner.clone())
+  );
+
+  setup.env.mock_all_auths();
+  setup.staking_pool.accept_owner(&new_owner);
+
+  assert_eq!(setup.staking_pool.get_staking_state().owner, new_owner);
+}
+
+#[test]
+fn test_ownership_handover_to_timelock() {
+  let setup = setup();
+  let env = &setup.env;
+  let timelock = &setup.timelock;
+
+  let pool = staking::Client::new(env, &env.register_contract_wasm(None, staking::WASM));
+  pool.initialize(
+    &create_token_contract(env, &setup.admin).address,
+    &create_token_contract(env, &setup.admin).address,
+    &setup.admin,
+    &env.deployer().upload_contract_wasm(share_token::WASM),
+  );
+
+  // only the old owner signs
+  env.mock_auths(&[MockAuth {
+    address: &setup.admin,
+    invoke: &MockAuthInvoke {
+      contract: &pool.address,
+      fn_name: "set_owner",
+      args: (timelock.address.clone(), setup.admin.clone()).into_val(env),
+      sub_invokes: &[],
+    },
+  }]);
+  pool.set_owner(&timelock.address, &setup.admin);
+
+  env.mock_all_auths();
+
+  let eta = 1000 + MIN_DELAY;
+  let id = timelock.queue(
+    &setup.admin,
+    &Call {
+      contract: pool.address.clone(),
+      function: Symbol::new(env, "accept_owner"),
+      args: (timelock.address.clone(),).into_val(env),
+    },
+    &eta,
+  );
+
+  jump_to(env, eta);
+
+  // the timelock accepts from its own call, no signature is mocked
+  env.set_auths(&[]);
+  timelock.execute(&id);
+
+  let state = pool.get_staking_state();
+  assert_eq!(state.owner, timelock.address);
+  assert_eq!(state.pending_owner, None);
+}
+
+#[test]
+fn test_upgrade_through_timelock() {
+  let setup = setup();
+  let timelock = &setup.timelock;
+
+  let wasm_hash = setup.env.deployer().upload_contract_wasm(staking::WASM);
+
+  let eta = 1000 + MIN_DELAY;
+  let id = timelock.queue(
+    &setup.admin,
+    &staking_call(
+      &setup,
+      "upgrade",
+      (timelock.address.clone(), wasm_hash.clone()).into_val(&setup.env),
+    ),
+    &eta,
+  );
+
+  // the owner key alone can't upgrade the pool anymore
+  assert_eq!(
+    setup
+      .staking_pool
+      .try_upgrade(&setup.admin, &wasm_hash)
+      .unwrap_err(),
+    Ok(soroban_sdk::Error::from_contract_error(
+      staking::Error::NotOwner as u32
+    ))
+  );
+
+  jump_to(&setup.env, eta);
+  timelock.execute(&id);
+
+  // the code is replaced, the pool state is kept
+  assert_eq!(
+    setup.staking_pool.get_staking_state().owner,
+    timelock.address
+  );
+}
+
+// IMMEDIATE OPERATIONS
+#[test]
+fn test_execute_immediate() {
+  let setup = setup();
+  let timelock = &setup.timelock;
+
+  let staker = Address::generate(&setup.env);
+  let base_token = token::StellarAssetClient::new(
+    &setup.env,
+    &setup.staking_pool.get_staking_state().base_token,
+  );
+  base_token.mint(&staker, &1000);
+
+  timelock.execute_immediate(
+    &setup.admin,
+    &staking_call(
+      &setup,
+      "set_paused",
+      (timelock.address.clone(), true).into_val(&setup.env),
+    ),
+  );
+
+  assert!(setup.staking_pool.get_staking_state().paused);
+  assert_eq!(
+    setup.staking_pool.try_stake(&staker, &1000),
+    Err(Ok(soroban_sdk::Error::from_contract_error(
+      staking::Error::Paused as u32
+    )))
+  );
+
+  // everything else has to wait
+  assert_eq!(
+    timelock.try_execute_immediate(&setup.admin, &set_reward_fee_call(&setup, 1000)),
+    Err(Ok(Error::NotImmediate))
+  );
+}
+
+#[test]
+fn test_immediate_is_keyed_by_contract() {
+  let setup = setup();
+  let timelock = &setup.timelock;
+
+  // a second pool owned by the timelock, set_paused is only listed for the first one
+  let other_pool = staking::Client::new(
+    &setup.env,
+    &setup.env.register_contract_wasm(None, staking::WASM),
+  );
+  other_pool.initialize(
+    &create_token_contract(&setup.env, &setup.admin).address,
+    &create_token_contract(&setup.env, &setup.admin).address,
+    &setup.admin,
+    &setup.env.deployer().upload_contract_wasm(share_token::WASM),
+  );
+  other_pool.set_owner(&timelock.address, &setup.admin);
+  other_pool.accept_owner(&timelock.address);
+
+  assert_eq!(
+    timelock.try_execute_immediate(
+      &setup.admin,
+      &Call {
+        contract: other_pool.address.clone(),
+        function: Symbol::new(&setup.env, "set_paused"),
+        args: (timelock.address.clone(), true).into_val(&setup.env),
+      },
+    ),
+    Err(Ok(Error::NotImmediate))
+  );
+  assert!(!other_pool.get_staking_state().paused);
+}
+
+// TIMELOCK SETTINGS
+#[test]
+fn test_change_min_delay_through_queue() {
+  let setup = setup();
+  let timelock = &setup.timelock;
+
+  let eta = 1000 + MIN_DELAY;
+  let id = timelock.queue(
+    &setup.admin,
+    &Call {
+      contract: timelock.address.clone(),
+      function: Symbol::new(&setup.env, "set_min_delay"),
+      args: (MIN_DELAY * 2,).into_val(&setup.env),
+    },
+    &eta,
+  );
+
+  jump_to(&setup.env, eta);
+  timelock.execute(&id);
+
+  assert_eq!(timelock.get_timelock_state().min_delay, MIN_DELAY * 2);
+
+  // anything else on the timelock itself is rejected
+  assert_eq!(
+    timelock.try_queue(
+      &setup.admin,
+      &Call {
+        contract: timelock.address.clone(),
+        function: Symbol::new(&setup.env, "cancel"),
+        args: (setup.admin.clone(), 0_u32).into_val(&setup.env),
+      },
+      &(eta + MIN_DELAY * 2),
+    ),
+    Err(Ok(Error::InvalidCall))
+  );
+}
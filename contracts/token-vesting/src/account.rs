@@ -0,0 +1,13 @@
+#![allow(unused)]
+use soroban_sdk::{xdr::ToXdr, Address, BytesN, Env};
+
+soroban_sdk::contractimport!(file = "account/vesting_account.wasm");
+
+// one staking account per beneficiary, the address only depends on the beneficiary
+pub fn create_contract(e: &Env, account_wasm_hash: BytesN<32>, beneficiary: &Address) -> Address {
+  let salt = e.crypto().sha256(&beneficiary.clone().to_xdr(e));
+
+  e.deployer()
+    .with_current_contract(salt)
+    .deploy(account_wasm_hash)
+}
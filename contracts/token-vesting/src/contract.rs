@@ -0,0 +1,458 @@
+use crate::{account, errors::Error, staking, storage};
+use soroban_sdk::{contract, contractimpl, token, Address, BytesN, Env};
+use storage::VestingRegistry;
+
+#[contract]
+pub struct TokenVesting;
+
+#[contractimpl]
+impl TokenVesting {
+  pub fn initialize(
+    env: Env,
+    owner: Address,
+    token: Address,
+    account_wasm_hash: BytesN<32>,
+  ) -> Result<storage::VestingState, Error> {
+    owner.require_auth();
+
+    let mut state = env
+      .storage()
+      .instance()
+      .get(&storage::VESTING_STATE)
+      .unwrap_or(storage::StorageClient::get_default_state(env.clone()));
+
+    if state.initialized {
+      return Err(Error::AlreadyInitialized);
+    }
+
+    state.owner = owner;
+    state.token = token;
+    state.account_wasm_hash = account_wasm_hash;
+    state.initialized = true;
+
+    env
+      .storage()
+      .instance()
+      .set(&storage::VESTING_STATE, &state);
+
+    Ok(state)
+  }
+
+  pub fn set_owner(env: Env, new_owner: Address, current_owner: Address) -> Result<(), Error> {
+    current_owner.require_auth();
+
+    let mut state = Self::get_vesting_state(env.clone())?;
+
+    if state.owner != current_owner {
+      return Err(Error::NotOwner);
+    }
+
+    state.owner = new_owner;
+
+    env
+      .storage()
+      .instance()
+      .set(&storage::VESTING_STATE, &state);
+
+    Ok(())
+  }
+
+  // the pool must stake the vested token, and can only be swapped while nothing is staked
+  pub fn set_staking_contract(
+    env: Env,
+    owner: Address,
+    staking_contract: Address,
+  ) -> Result<(), Error> {
+    owner.require_auth();
+
+    let mut state = Self::get_vesting_state(env.clone())?;
+
+    if state.owner != owner {
+      return Err(Error::NotOwner);
+    }
+
+    if state.total_staked > 0 {
+      return Err(Error::StillStaked);
+    }
+
+    let staking_state = staking::Client::new(&env, &staking_contract).get_staking_state();
+
+    if staking_state.base_token != state.token {
+      return Err(Error::TokenMismatch);
+    }
+
+    state.staking_contract = Some(staking_contract);
+
+    env
+      .storage()
+      .instance()
+      .set(&storage::VESTING_STATE, &state);
+
+    Ok(())
+  }
+
+  // pulls `amount` tokens from the owner and locks them for the beneficiary
+  #[allow(clippy::too_many_arguments)]
+  pub fn create_schedule(
+    env: Env,
+    owner: Address,
+    beneficiary: Address,
+    amount: i128,
+    start: u64,
+    cliff: u64,
+    duration: u64,
+    revocable: bool,
+  ) -> Result<storage::Schedule, Error> {
+    owner.require_auth();
+
+    let state = Self::get_vesting_state(env.clone())?;
+
+    if state.owner != owner {
+      return Err(Error::NotOwner);
+    }
+
+    if amount <= 0 {
+      return Err(Error::InvalidAmount);
+    }
+
+    if cliff > duration {
+      return Err(Error::InvalidSchedule);
+    }
+
+    let key = VestingRegistry::Schedule(beneficiary.clone());
+
+    if env.storage().persistent().has(&key) {
+      return Err(Error::ScheduleExists);
+    }
+
+    token::Client::new(&env, &state.token).transfer(
+      &owner,
+      &env.current_contract_address(),
+      &amount,
+    );
+
+    let schedule = storage::Schedule {
+      beneficiary,
+      total: amount,
+      released: 0,
+      start,
+      cliff,
+      duration,
+      revocable,
+      revoked: false,
+      staked: 0,
+    };
+
+    Self::write_schedule(env.clone(), &schedule);
+
+    Ok(schedule)
+  }
+
+  // pays the vested part that is not staked, unstake first to release the rest
+  pub fn release(env: Env, beneficiary: Address) -> Result<i128, Error> {
+    beneficiary.require_auth();
+
+    let state = Self::get_vesting_state(env.clone())?;
+
+    let mut schedule = Self::get_schedule(env.clone(), beneficiary.clone())?;
+
+    let amount = Self::releasable_amount(env.clone(), beneficiary.clone())?;
+
+    if amount == 0 {
+      return Err(Error::NothingToRelease);
+    }
+
+    token::Client::new(&env, &state.token).transfer(
+      &env.current_contract_address(),
+      &beneficiary,
+      &amount,
+    );
+
+    schedule.released += amount;
+
+    Self::write_schedule(env.clone(), &schedule);
+
+    Ok(amount)
+  }
+
+  // sends the unvested part back to the owner, the vested part stays claimable
+  pub fn revoke(env: Env, owner: Address, beneficiary: Address) -> Result<i128, Error> {
+    owner.require_auth();
+
+    let mut state = Self::get_vesting_state(env.clone())?;
+
+    if state.owner != owner {
+      return Err(Error::NotOwner);
+    }
+
+    let mut schedule = Self::get_schedule(env.clone(), beneficiary.clone())?;
+
+    if !schedule.revocable || schedule.revoked {
+      return Err(Error::NotRevocable);
+    }
+
+    if schedule.staked > 0 {
+      let staked = schedule.staked;
+      Self::unstake_from_pool(env.clone(), &mut state, &mut schedule, staked);
+    }
+
+    let unvested = schedule.total - Self::vested(env.clone(), &schedule);
+
+    schedule.total -= unvested;
+    schedule.revoked = true;
+
+    if unvested > 0 {
+      token::Client::new(&env, &state.token).transfer(
+        &env.current_contract_address(),
+        &owner,
+        &unvested,
+      );
+    }
+
+    env
+      .storage()
+      .instance()
+      .set(&storage::VESTING_STATE, &state);
+
+    Self::write_schedule(env.clone(), &schedule);
+
+    Ok(unvested)
+  }
+
+  // stakes locked tokens in the pool through the beneficiary's own staking account, the account
+  // only sends principal back to this contract so the lock holds
+  pub fn stake(env: Env, beneficiary: Address, amount: i128) -> Result<(), Error> {
+    beneficiary.require_auth();
+
+    let mut state = Self::get_vesting_state(env.clone())?;
+
+    let staking_contract = state
+      .staking_contract
+      .clone()
+      .ok_or(Error::StakingNotConfigured)?;
+
+    let mut schedule = Self::get_schedule(env.clone(), beneficiary.clone())?;
+
+    if amount <= 0 {
+      return Err(Error::InvalidAmount);
+    }
+
+    if amount > schedule.total - schedule.released - schedule.staked {
+      return Err(Error::ExceedsLockedBalance);
+    }
+
+    let account = Self::get_or_create_account(env.clone(), &state, &beneficiary);
+
+    token::Client::new(&env, &state.token).transfer(
+      &env.current_contract_address(),
+      &account,
+      &amount,
+    );
+
+    let staked = account::Client::new(&env, &account).stake(&staking_contract, &amount);
+
+    // a stake fee makes the pool balance smaller than the amount sent, the difference is lost
+    schedule.total -= amount - staked;
+    schedule.staked += staked;
+    state.total_staked += staked;
+
+    env
+      .storage()
+      .instance()
+      .set(&storage::VESTING_STATE, &state);
+
+    Self::write_schedule(env.clone(), &schedule);
+
+    Ok(())
+  }
+
+  pub fn unstake(env: Env, beneficiary: Address, amount: i128) -> Result<(), Error> {
+    beneficiary.require_auth();
+
+    let mut state = Self::get_vesting_state(env.clone())?;
+
+    let mut schedule = Self::get_schedule(env.clone(), beneficiary.clone())?;
+
+    if amount <= 0 || amount > schedule.staked {
+      return Err(Error::InvalidAmount);
+    }
+
+    Self::unstake_from_pool(env.clone(), &mut state, &mut schedule, amount);
+
+    env
+      .storage()
+      .instance()
+      .set(&storage::VESTING_STATE, &state);
+
+    Self::write_schedule(env.clone(), &schedule);
+
+    Ok(())
+  }
+
+  // pool rewards and redistributed fees are not locked, the account sends them straight to the
+  // beneficiary. Returns the reward tokens paid, the fees are paid in the locked token
+  pub fn claim_staking_rewards(env: Env, beneficiary: Address) -> Result<i128, Error> {
+    beneficiary.require_auth();
+
+    let state = Self::get_vesting_state(env.clone())?;
+
+    let staking_contract = state
+      .staking_contract
+      .clone()
+      .ok_or(Error::StakingNotConfigured)?;
+
+    let account =
+      Self::get_account(env.clone(), beneficiary.clone()).ok_or(Error::NothingToRelease)?;
+
+    let claimed = account::Client::new(&env, &account).claim(&staking_contract);
+
+    if claimed.rewards == 0 && claimed.fees == 0 {
+      return Err(Error::NothingToRelease);
+    }
+
+    Ok(claimed.rewards)
+  }
+
+  // vested so far, including what was already released
+  pub fn vested_amount(env: Env, beneficiary: Address) -> Result<i128, Error> {
+    let schedule = Self::get_schedule(env.clone(), beneficiary)?;
+
+    Ok(Self::vested(env.clone(), &schedule))
+  }
+
+  pub fn releasable_amount(env: Env, beneficiary: Address) -> Result<i128, Error> {
+    let schedule = Self::get_schedule(env.clone(), beneficiary)?;
+
+    let unreleased = (Self::vested(env.clone(), &schedule) - schedule.released).max(0);
+
+    let liquid = schedule.total - schedule.released - schedule.staked;
+
+    Ok(unreleased.min(liquid))
+  }
+
+  pub fn get_schedule(env: Env, beneficiary: Address) -> Result<storage::Schedule, Error> {
+    let key = VestingRegistry::Schedule(beneficiary);
+    let schedule: storage::Schedule = env
+      .storage()
+      .persistent()
+      .get(&key)
+      .ok_or(Error::ScheduleNotFound)?;
+
+    Self::extend_persistent(&env, &key);
+
+    Ok(schedule)
+  }
+
+  // staking account of the beneficiary, deployed on its first stake
+  pub fn get_account(env: Env, beneficiary: Address) -> Option<Address> {
+    let key = VestingRegistry::Account(beneficiary);
+    let account: Option<Address> = env.storage().persistent().get(&key);
+
+    if account.is_some() {
+      Self::extend_persistent(&env, &key);
+    }
+
+    account
+  }
+
+  pub fn get_vesting_state(env: Env) -> Result<storage::VestingState, Error> {
+    let state: storage::VestingState = env
+      .storage()
+      .instance()
+      .get(&storage::VESTING_STATE)
+      .unwrap_or(storage::StorageClient::get_default_state(env.clone()));
+
+    if !state.initialized {
+      return Err(Error::NotInitialized);
+    }
+
+    Ok(state)
+  }
+
+  fn vested(env: Env, schedule: &storage::Schedule) -> i128 {
+    if schedule.revoked {
+      return schedule.total;
+    }
+
+    let elapsed = env.ledger().timestamp().saturating_sub(schedule.start);
+
+    if env.ledger().timestamp() < schedule.start || elapsed < schedule.cliff {
+      return 0;
+    }
+
+    if elapsed >= schedule.duration {
+      return schedule.total;
+    }
+
+    schedule.total * elapsed as i128 / schedule.duration as i128
+  }
+
+  fn get_or_create_account(
+    env: Env,
+    state: &storage::VestingState,
+    beneficiary: &Address,
+  ) -> Address {
+    if let Some(account) = Self::get_account(env.clone(), beneficiary.clone()) {
+      return account;
+    }
+
+    let account = account::create_contract(&env, state.account_wasm_hash.clone(), beneficiary);
+
+    account::Client::new(&env, &account).initialize(&env.current_contract_address(), beneficiary);
+
+    let key = VestingRegistry::Account(beneficiary.clone());
+
+    env.storage().persistent().set(&key, &account);
+
+    Self::extend_persistent(&env, &key);
+
+    account
+  }
+
+  // pays out the pool rewards first, so nothing is left behind in the position once it is empty
+  fn unstake_from_pool(
+    env: Env,
+    state: &mut storage::VestingState,
+    schedule: &mut storage::Schedule,
+    amount: i128,
+  ) {
+    let staking_contract = state.staking_contract.clone().unwrap();
+
+    let account = Self::get_account(env.clone(), schedule.beneficiary.clone()).unwrap();
+    let account_client = account::Client::new(&env, &account);
+
+    account_client.claim(&staking_contract);
+
+    let received = account_client.unstake(&staking_contract, &amount);
+
+    // unstake fees and early unstake penalties come out of the locked tokens
+    schedule.total -= amount - received;
+    schedule.staked -= amount;
+    state.total_staked -= amount;
+  }
+
+  fn write_schedule(env: Env, schedule: &storage::Schedule) {
+    let key = VestingRegistry::Schedule(schedule.beneficiary.clone());
+
+    if schedule.released == schedule.total && schedule.staked == 0 {
+      env.storage().persistent().remove(&key);
+    } else {
+      env.storage().persistent().set(&key, schedule);
+
+      Self::extend_persistent(&env, &key);
+    }
+  }
+
+  fn extend_persistent(env: &Env, key: &VestingRegistry) {
+    env.storage().persistent().extend_ttl(
+      key,
+      storage::SCHEDULE_LIFETIME_THRESHOLD,
+      storage::SCHEDULE_BUMP_AMOUNT,
+    );
+
+    env.storage().instance().extend_ttl(
+      storage::INSTANCE_LIFETIME_THRESHOLD,
+      storage::INSTANCE_BUMP_AMOUNT,
+    );
+  }
+}
@@ -0,0 +1,20 @@
+use soroban_sdk::contracterror;
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum Error {
+  AlreadyInitialized = 1,
+  NotInitialized = 2,
+  NotOwner = 3,
+  InvalidAmount = 4,
+  InvalidSchedule = 5,
+  ScheduleExists = 6,
+  ScheduleNotFound = 7,
+  NotRevocable = 8,
+  NothingToRelease = 9,
+  ExceedsLockedBalance = 10,
+  StakingNotConfigured = 11,
+  TokenMismatch = 12,
+  StillStaked = 13,
+}
@@ -0,0 +1,8 @@
+#![no_std]
+
+mod account;
+mod contract;
+mod errors;
+mod staking;
+mod storage;
+mod test;
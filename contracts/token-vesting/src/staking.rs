@@ -0,0 +1,2 @@
+#![allow(unused)]
+soroban_sdk::contractimport!(file = "../liquid-staking-contract/wasm/liquid_staking_contract.wasm");
@@ -0,0 +1,58 @@
+#![allow(unused)]
+use soroban_sdk::{contracttype, symbol_short, Address, BytesN, Env, Symbol};
+
+pub(crate) const DAY_IN_LEDGERS: u32 = 17280;
+pub(crate) const INSTANCE_BUMP_AMOUNT: u32 = 7 * DAY_IN_LEDGERS;
+pub(crate) const INSTANCE_LIFETIME_THRESHOLD: u32 = INSTANCE_BUMP_AMOUNT - DAY_IN_LEDGERS;
+
+// schedules run for months or years, and are bumped every time they are touched
+pub(crate) const SCHEDULE_BUMP_AMOUNT: u32 = 30 * DAY_IN_LEDGERS;
+pub(crate) const SCHEDULE_LIFETIME_THRESHOLD: u32 = SCHEDULE_BUMP_AMOUNT - DAY_IN_LEDGERS;
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct VestingState {
+  pub owner: Address,                    // s_owner - creates and revokes schedules
+  pub token: Address,                    // s_token - SEP-41 token locked by every schedule
+  pub staking_contract: Option<Address>, // s_stakingContract - pool for staking locked tokens
+  pub total_staked: i128, // s_totalStaked - pool balance held by all the staking accounts
+  pub account_wasm_hash: BytesN<32>, // s_accountWasmHash - staking account deployed per beneficiary
+  pub initialized: bool,
+}
+
+pub const VESTING_STATE: Symbol = symbol_short!("state");
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Schedule {
+  pub beneficiary: Address, // s_beneficiary - receives the tokens as they vest
+  pub total: i128,          // s_total - tokens locked, minus any staking fees paid
+  pub released: i128,       // s_released - tokens already paid to the beneficiary
+  pub start: u64,           // s_start - timestamp the vesting starts at
+  pub cliff: u64,           // s_cliff - seconds after start before anything vests
+  pub duration: u64,        // s_duration - seconds after start until everything vests
+  pub revocable: bool,      // s_revocable - owner can take back the unvested part
+  pub revoked: bool,        // s_revoked - revoked schedules are fully vested at their reduced total
+  pub staked: i128,         // s_staked - part of the unreleased tokens sitting in the pool
+}
+
+#[contracttype]
+pub enum VestingRegistry {
+  Schedule(Address),
+  Account(Address), // staking account of the beneficiary, kept after the schedule is done
+}
+
+pub struct StorageClient;
+
+impl StorageClient {
+  pub fn get_default_state(env: Env) -> VestingState {
+    VestingState {
+      owner: env.current_contract_address().clone(),
+      token: env.current_contract_address().clone(),
+      staking_contract: None,
+      total_staked: 0,
+      account_wasm_hash: BytesN::from_array(&env, &[0; 32]),
+      initialized: false,
+    }
+  }
+}
@@ -0,0 +1,587 @@
+#![cfg(test)]
+extern crate std;
+
+use super::*;
+use soroban_sdk::testutils::{Address as _, Ledger};
+use soroban_sdk::{token, Address, BytesN, Env};
+
+use crate::errors::Error;
+
+mod share_token {
+  soroban_sdk::contractimport!(
+    file = "../liquid-staking-contract/token/soroban_token_contract.wasm"
+  );
+}
+
+fn create_token_contract<'a>(e: &Env, admin: &Address) -> token::StellarAssetClient<'a> {
+  let asset_contract_registration = e.register_stellar_asset_contract_v2(admin.clone());
+
+  token::StellarAssetClient::new(e, &asset_contract_registration.address())
+}
+
+fn install_share_token_wasm(e: &Env) -> BytesN<32> {
+  e.deployer().upload_contract_wasm(share_token::WASM)
+}
+
+fn install_account_wasm(e: &Env) -> BytesN<32> {
+  e.deployer().upload_contract_wasm(account::WASM)
+}
+
+fn create_staking_contract<'a>(
+  e: &Env,
+  base_token: &Address,
+  reward_token: &Address,
+  owner: &Address,
+) -> staking::Client<'a> {
+  let staking_pool = staking::Client::new(e, &e.register_contract_wasm(None, staking::WASM));
+
+  staking_pool.initialize(
+    base_token,
+    reward_token,
+    owner,
+    &install_share_token_wasm(e),
+  );
+
+  staking_pool
+}
+
+fn create_vesting_contract<'a>(e: &Env) -> contract::TokenVestingClient<'a> {
+  contract::TokenVestingClient::new(e, &e.register_contract(None, contract::TokenVesting {}))
+}
+
+// INITIALIZE
+#[test]
+fn test_contract_initialize() {
+  let env = Env::default();
+  env.mock_all_auths();
+
+  let owner = Address::generate(&env);
+  let token = create_token_contract(&env, &owner);
+  let vesting = create_vesting_contract(&env);
+
+  let state = vesting.initialize(&owner, &token.address, &install_account_wasm(&env));
+  assert_eq!(state.owner, owner);
+  assert_eq!(state.token, token.address);
+  assert_eq!(state.staking_contract, None);
+
+  assert_eq!(
+    vesting.try_initialize(&owner, &token.address, &install_account_wasm(&env)),
+    Err(Ok(Error::AlreadyInitialized))
+  );
+}
+
+// SCHEDULES
+#[test]
+fn test_linear_release_with_cliff() {
+  let env = Env::default();
+  env.mock_all_auths();
+
+  let owner = Address::generate(&env);
+  let beneficiary = Address::generate(&env);
+  let token = create_token_contract(&env, &owner);
+  let token_client = token::Client::new(&env, &token.address);
+  let vesting = create_vesting_contract(&env);
+  token.mint(&owner, &1000);
+
+  vesting.initialize(&owner, &token.address, &install_account_wasm(&env));
+  vesting.create_schedule(&owner, &beneficiary, &1000, &100, &200, &1000, &false);
+  assert_eq!(token_client.balance(&vesting.address), 1000);
+
+  // before start and before the cliff nothing is releasable
+  assert_eq!(vesting.releasable_amount(&beneficiary), 0);
+  env.ledger().with_mut(|li| {
+    li.timestamp = 299;
+  });
+  assert_eq!(vesting.releasable_amount(&beneficiary), 0);
+  assert_eq!(
+    vesting.try_release(&beneficiary),
+    Err(Ok(Error::NothingToRelease))
+  );
+
+  env.ledger().with_mut(|li| {
+    li.timestamp = 300;
+  });
+  assert_eq!(vesting.vested_amount(&beneficiary), 200);
+  assert_eq!(vesting.release(&beneficiary), 200);
+
+  env.ledger().with_mut(|li| {
+    li.timestamp = 600;
+  });
+  assert_eq!(vesting.vested_amount(&beneficiary), 500);
+  assert_eq!(vesting.release(&beneficiary), 300);
+
+  env.ledger().with_mut(|li| {
+    li.timestamp = 5000;
+  });
+  assert_eq!(vesting.release(&beneficiary), 500);
+  assert_eq!(token_client.balance(&beneficiary), 1000);
+
+  // fully released schedules are removed
+  assert_eq!(
+    vesting.try_get_schedule(&beneficiary),
+    Err(Ok(Error::ScheduleNotFound))
+  );
+}
+
+#[test]
+fn test_many_beneficiaries() {
+  let env = Env::default();
+  env.mock_all_auths();
+
+  let owner = Address::generate(&env);
+  let team = Address::generate(&env);
+  let investor = Address::generate(&env);
+  let token = create_token_contract(&env, &owner);
+  let vesting = create_vesting_contract(&env);
+  token.mint(&owner, &3000);
+
+  vesting.initialize(&owner, &token.address, &install_account_wasm(&env));
+  vesting.create_schedule(&owner, &team, &2000, &0, &0, &2000, &true);
+  vesting.create_schedule(&owner, &investor, &1000, &500, &0, &500, &false);
+
+  assert_eq!(
+    vesting.try_create_schedule(&owner, &team, &1, &0, &0, &1, &true),
+    Err(Ok(Error::ScheduleExists))
+  );
+
+  env.ledger().with_mut(|li| {
+    li.timestamp = 750;
+  });
+
+  assert_eq!(vesting.vested_amount(&team), 750);
+  assert_eq!(vesting.vested_amount(&investor), 500);
+}
+
+#[test]
+fn test_invalid_schedule() {
+  let env = Env::default();
+  env.mock_all_auths();
+
+  let owner = Address::generate(&env);
+  let not_owner = Address::generate(&env);
+  let beneficiary = Address::generate(&env);
+  let token = create_token_contract(&env, &owner);
+  let vesting = create_vesting_contract(&env);
+  token.mint(&owner, &1000);
+
+  vesting.initialize(&owner, &token.address, &install_account_wasm(&env));
+
+  assert_eq!(
+    vesting.try_create_schedule(&owner, &beneficiary, &1000, &0, &1001, &1000, &true),
+    Err(Ok(Error::InvalidSchedule))
+  );
+  assert_eq!(
+    vesting.try_create_schedule(&owner, &beneficiary, &0, &0, &0, &1000, &true),
+    Err(Ok(Error::InvalidAmount))
+  );
+  assert_eq!(
+    vesting.try_create_schedule(&not_owner, &beneficiary, &1000, &0, &0, &1000, &true),
+    Err(Ok(Error::NotOwner))
+  );
+}
+
+#[test]
+fn test_schedule_ttl_is_extended() {
+  use soroban_sdk::testutils::storage::Persistent as _;
+  use soroban_sdk::testutils::LedgerInfo;
+
+  let env = Env::default();
+  env.mock_all_auths();
+  env.ledger().set(LedgerInfo {
+    timestamp: 0,
+    protocol_version: 21,
+    sequence_number: 10,
+    network_id: Default::default(),
+    base_reserve: 10,
+    min_temp_entry_ttl: 16,
+    min_persistent_entry_ttl: 25 * storage::DAY_IN_LEDGERS,
+    max_entry_ttl: 100 * storage::DAY_IN_LEDGERS,
+  });
+
+  let owner = Address::generate(&env);
+  let beneficiary = Address::generate(&env);
+  let token = create_token_contract(&env, &owner);
+  let vesting = create_vesting_contract(&env);
+  token.mint(&owner, &1000);
+
+  vesting.initialize(&owner, &token.address, &install_account_wasm(&env));
+  vesting.create_schedule(&owner, &beneficiary, &1000, &0, &0, &1000, &false);
+
+  let schedule_ttl = || {
+    env.as_contract(&vesting.address, || {
+      env
+        .storage()
+        .persistent()
+        .get_ttl(&storage::VestingRegistry::Schedule(beneficiary.clone()))
+    })
+  };
+
+  assert_eq!(schedule_ttl(), storage::SCHEDULE_BUMP_AMOUNT);
+
+  // a schedule that is only read, never written, must not expire either
+  env.ledger().with_mut(|li| {
+    li.sequence_number += 20 * storage::DAY_IN_LEDGERS;
+    li.timestamp = 100;
+  });
+  assert_eq!(schedule_ttl(), 10 * storage::DAY_IN_LEDGERS);
+
+  assert_eq!(vesting.vested_amount(&beneficiary), 100);
+  assert_eq!(schedule_ttl(), storage::SCHEDULE_BUMP_AMOUNT);
+}
+
+// REVOKE
+#[test]
+fn test_revoke() {
+  let env = Env::default();
+  env.mock_all_auths();
+
+  let owner = Address::generate(&env);
+  let beneficiary = Address::generate(&env);
+  let token = create_token_contract(&env, &owner);
+  let token_client = token::Client::new(&env, &token.address);
+  let vesting = create_vesting_contract(&env);
+  token.mint(&owner, &1000);
+
+  vesting.initialize(&owner, &token.address, &install_account_wasm(&env));
+  vesting.create_schedule(&owner, &beneficiary, &1000, &0, &0, &1000, &true);
+
+  env.ledger().with_mut(|li| {
+    li.timestamp = 400;
+  });
+
+  assert_eq!(vesting.revoke(&owner, &beneficiary), 600);
+  assert_eq!(token_client.balance(&owner), 600);
+
+  // the vested part is still there, and does not grow anymore
+  env.ledger().with_mut(|li| {
+    li.timestamp = 900;
+  });
+  assert_eq!(vesting.vested_amount(&beneficiary), 400);
+  assert_eq!(vesting.release(&beneficiary), 400);
+
+  assert_eq!(token_client.balance(&beneficiary), 400);
+}
+
+#[test]
+fn test_revoke_not_revocable() {
+  let env = Env::default();
+  env.mock_all_auths();
+
+  let owner = Address::generate(&env);
+  let beneficiary = Address::generate(&env);
+  let token = create_token_contract(&env, &owner);
+  let vesting = create_vesting_contract(&env);
+  token.mint(&owner, &1000);
+
+  vesting.initialize(&owner, &token.address, &install_account_wasm(&env));
+  vesting.create_schedule(&owner, &beneficiary, &1000, &0, &0, &1000, &false);
+
+  assert_eq!(
+    vesting.try_revoke(&owner, &beneficiary),
+    Err(Ok(Error::NotRevocable))
+  );
+}
+
+// STAKING
+#[test]
+fn test_stake_locked_tokens() {
+  let env = Env::default();
+  env.mock_all_auths();
+  env.budget().reset_unlimited();
+
+  let owner = Address::generate(&env);
+  let beneficiary = Address::generate(&env);
+  let token = create_token_contract(&env, &owner);
+  let reward_token = create_token_contract(&env, &owner);
+  let token_client = token::Client::new(&env, &token.address);
+  let reward_token_client = token::Client::new(&env, &reward_token.address);
+  let staking_pool = create_staking_contract(&env, &token.address, &reward_token.address, &owner);
+  let vesting = create_vesting_contract(&env);
+  token.mint(&owner, &1000);
+  reward_token.mint(&owner, &1_000_000);
+
+  staking_pool.add_reward_funds(&owner, &1_000_000);
+
+  vesting.initialize(&owner, &token.address, &install_account_wasm(&env));
+  vesting.set_staking_contract(&owner, &staking_pool.address);
+  vesting.create_schedule(&owner, &beneficiary, &1000, &0, &0, &1000, &false);
+
+  vesting.stake(&beneficiary, &800);
+
+  // the position and its shares belong to the beneficiary's staking account, not to the beneficiary
+  let account = vesting.get_account(&beneficiary).unwrap();
+  let share_token = token::Client::new(&env, &staking_pool.get_staking_state().share_token);
+  assert_eq!(share_token.balance(&account), 800);
+  assert_eq!(share_token.balance(&beneficiary), 0);
+  assert_eq!(share_token.balance(&vesting.address), 0);
+  assert_eq!(staking_pool.get_user_position(&account).balance, 800);
+  assert_eq!(vesting.get_schedule(&beneficiary).staked, 800);
+
+  assert_eq!(
+    vesting.try_stake(&beneficiary, &201),
+    Err(Ok(Error::ExceedsLockedBalance))
+  );
+
+  env.ledger().with_mut(|li| {
+    li.timestamp = 500;
+  });
+
+  // half is vested, but only the unstaked part can be released
+  assert_eq!(vesting.vested_amount(&beneficiary), 500);
+  assert_eq!(vesting.release(&beneficiary), 200);
+
+  // pool rewards are not locked
+  assert_eq!(vesting.claim_staking_rewards(&beneficiary), 50_000);
+  assert_eq!(reward_token_client.balance(&beneficiary), 50_000);
+
+  vesting.unstake(&beneficiary, &800);
+  assert_eq!(vesting.release(&beneficiary), 300);
+
+  env.ledger().with_mut(|li| {
+    li.timestamp = 1000;
+  });
+
+  assert_eq!(vesting.release(&beneficiary), 500);
+  assert_eq!(token_client.balance(&beneficiary), 1000);
+}
+
+#[test]
+fn test_staking_rewards_split_between_beneficiaries() {
+  let env = Env::default();
+  env.mock_all_auths();
+  env.budget().reset_unlimited();
+
+  let owner = Address::generate(&env);
+  let alice = Address::generate(&env);
+  let bob = Address::generate(&env);
+  let token = create_token_contract(&env, &owner);
+  let reward_token = create_token_contract(&env, &owner);
+  let reward_token_client = token::Client::new(&env, &reward_token.address);
+  let staking_pool = create_staking_contract(&env, &token.address, &reward_token.address, &owner);
+  let vesting = create_vesting_contract(&env);
+  token.mint(&owner, &4000);
+  reward_token.mint(&owner, &1_000_000);
+
+  staking_pool.add_reward_funds(&owner, &1_000_000);
+
+  vesting.initialize(&owner, &token.address, &install_account_wasm(&env));
+  vesting.set_staking_contract(&owner, &staking_pool.address);
+  vesting.create_schedule(&owner, &alice, &2000, &0, &0, &10_000, &false);
+  vesting.create_schedule(&owner, &bob, &2000, &0, &0, &10_000, &false);
+
+  vesting.stake(&alice, &2000);
+
+  env.ledger().with_mut(|li| {
+    li.timestamp = 100;
+  });
+
+  // alice earned everything so far, then both share equally
+  vesting.stake(&bob, &2000);
+
+  env.ledger().with_mut(|li| {
+    li.timestamp = 200;
+  });
+
+  assert_eq!(vesting.claim_staking_rewards(&alice), 10_000 + 5_000);
+  assert_eq!(vesting.claim_staking_rewards(&bob), 5_000);
+  assert_eq!(reward_token_client.balance(&alice), 15_000);
+  assert_eq!(reward_token_client.balance(&bob), 5_000);
+}
+
+#[test]
+fn test_early_unstake_penalty_is_per_beneficiary() {
+  let env = Env::default();
+  env.mock_all_auths();
+  env.budget().reset_unlimited();
+
+  let owner = Address::generate(&env);
+  let alice = Address::generate(&env);
+  let bob = Address::generate(&env);
+  let token = create_token_contract(&env, &owner);
+  let reward_token = create_token_contract(&env, &owner);
+  let staking_pool = create_staking_contract(&env, &token.address, &reward_token.address, &owner);
+  let vesting = create_vesting_contract(&env);
+  token.mint(&owner, &2000);
+
+  staking_pool.set_early_unstake_penalty(&owner, &1000, &500);
+
+  vesting.initialize(&owner, &token.address, &install_account_wasm(&env));
+  vesting.set_staking_contract(&owner, &staking_pool.address);
+  vesting.create_schedule(&owner, &alice, &1000, &0, &0, &10_000, &false);
+  vesting.create_schedule(&owner, &bob, &1000, &0, &0, &10_000, &false);
+
+  vesting.stake(&alice, &1000);
+
+  env.ledger().with_mut(|li| {
+    li.timestamp = 600;
+  });
+
+  // bob staking late does not restart the penalty window of alice
+  vesting.stake(&bob, &1000);
+  vesting.unstake(&alice, &1000);
+  assert_eq!(vesting.get_schedule(&alice).total, 1000);
+
+  // bob is still inside their own window
+  vesting.unstake(&bob, &1000);
+  assert_eq!(vesting.get_schedule(&bob).total, 900);
+}
+
+#[test]
+fn test_staking_fees_go_to_beneficiary() {
+  let env = Env::default();
+  env.mock_all_auths();
+  env.budget().reset_unlimited();
+
+  let owner = Address::generate(&env);
+  let alice = Address::generate(&env);
+  let bob = Address::generate(&env);
+  let token = create_token_contract(&env, &owner);
+  let reward_token = create_token_contract(&env, &owner);
+  let token_client = token::Client::new(&env, &token.address);
+  let staking_pool = create_staking_contract(&env, &token.address, &reward_token.address, &owner);
+  let vesting = create_vesting_contract(&env);
+  token.mint(&owner, &2000);
+
+  staking_pool.set_stake_fees(&owner, &100, &0, &true);
+
+  vesting.initialize(&owner, &token.address, &install_account_wasm(&env));
+  vesting.set_staking_contract(&owner, &staking_pool.address);
+  vesting.create_schedule(&owner, &alice, &1000, &0, &0, &10_000, &false);
+  vesting.create_schedule(&owner, &bob, &1000, &0, &0, &10_000, &false);
+
+  vesting.stake(&alice, &1000);
+  vesting.stake(&bob, &1000);
+
+  assert_eq!(vesting.get_schedule(&alice).total, 990);
+  assert_eq!(vesting.get_schedule(&bob).total, 990);
+
+  // the fee bob paid is shared over the 990 alice has staked, and only alice can claim it
+  assert_eq!(
+    staking_pool.earned_fees(&vesting.get_account(&alice).unwrap()),
+    9
+  );
+  assert_eq!(vesting.claim_staking_rewards(&alice), 0);
+  assert_eq!(token_client.balance(&alice), 9);
+
+  assert_eq!(
+    vesting.try_claim_staking_rewards(&bob),
+    Err(Ok(Error::NothingToRelease))
+  );
+  assert_eq!(token_client.balance(&bob), 0);
+}
+
+#[test]
+fn test_revoke_staked_schedule() {
+  let env = Env::default();
+  env.mock_all_auths();
+  env.budget().reset_unlimited();
+
+  let owner = Address::generate(&env);
+  let beneficiary = Address::generate(&env);
+  let token = create_token_contract(&env, &owner);
+  let reward_token = create_token_contract(&env, &owner);
+  let token_client = token::Client::new(&env, &token.address);
+  let staking_pool = create_staking_contract(&env, &token.address, &reward_token.address, &owner);
+  let vesting = create_vesting_contract(&env);
+  token.mint(&owner, &1000);
+
+  vesting.initialize(&owner, &token.address, &install_account_wasm(&env));
+  vesting.set_staking_contract(&owner, &staking_pool.address);
+  vesting.create_schedule(&owner, &beneficiary, &1000, &0, &0, &1000, &true);
+
+  vesting.stake(&beneficiary, &1000);
+
+  env.ledger().with_mut(|li| {
+    li.timestamp = 250;
+  });
+
+  // the position is unstaked first so the unvested part can go back to the owner
+  assert_eq!(vesting.revoke(&owner, &beneficiary), 750);
+  assert_eq!(token_client.balance(&owner), 750);
+  let account = vesting.get_account(&beneficiary).unwrap();
+  assert_eq!(staking_pool.get_user_position(&account).balance, 0);
+  assert_eq!(vesting.get_vesting_state().total_staked, 0);
+
+  assert_eq!(vesting.release(&beneficiary), 250);
+}
+
+#[test]
+fn test_unstake_and_revoke_when_reward_token_is_base_token() {
+  let env = Env::default();
+  env.mock_all_auths();
+  env.budget().reset_unlimited();
+
+  let owner = Address::generate(&env);
+  let alice = Address::generate(&env);
+  let bob = Address::generate(&env);
+  let token = create_token_contract(&env, &owner);
+  let token_client = token::Client::new(&env, &token.address);
+  let staking_pool = create_staking_contract(&env, &token.address, &token.address, &owner);
+  let vesting = create_vesting_contract(&env);
+  token.mint(&owner, &1_002_000);
+
+  staking_pool.add_reward_funds(&owner, &1_000_000);
+
+  vesting.initialize(&owner, &token.address, &install_account_wasm(&env));
+  vesting.set_staking_contract(&owner, &staking_pool.address);
+  vesting.create_schedule(&owner, &alice, &1000, &0, &0, &1000, &false);
+  vesting.create_schedule(&owner, &bob, &1000, &0, &0, &1000, &true);
+
+  vesting.stake(&alice, &1000);
+  vesting.stake(&bob, &1000);
+
+  env.ledger().with_mut(|li| {
+    li.timestamp = 100;
+  });
+
+  // pending rewards are paid out on the way, the principal stays locked
+  vesting.unstake(&alice, &1000);
+  assert_eq!(token_client.balance(&alice), 5000);
+  assert_eq!(vesting.get_schedule(&alice).staked, 0);
+  assert_eq!(vesting.release(&alice), 100);
+
+  assert_eq!(vesting.revoke(&owner, &bob), 900);
+  assert_eq!(token_client.balance(&owner), 900);
+  assert_eq!(token_client.balance(&bob), 5000);
+  assert_eq!(vesting.release(&bob), 100);
+  assert_eq!(vesting.get_vesting_state().total_staked, 0);
+}
+
+#[test]
+fn test_set_staking_contract_token_mismatch() {
+  let env = Env::default();
+  env.mock_all_auths();
+
+  let owner = Address::generate(&env);
+  let token = create_token_contract(&env, &owner);
+  let other_token = create_token_contract(&env, &owner);
+  let staking_pool = create_staking_contract(&env, &other_token.address, &token.address, &owner);
+  let vesting = create_vesting_contract(&env);
+
+  vesting.initialize(&owner, &token.address, &install_account_wasm(&env));
+
+  assert_eq!(
+    vesting.try_set_staking_contract(&owner, &staking_pool.address),
+    Err(Ok(Error::TokenMismatch))
+  );
+}
+
+#[test]
+fn test_stake_without_staking_contract() {
+  let env = Env::default();
+  env.mock_all_auths();
+
+  let owner = Address::generate(&env);
+  let beneficiary = Address::generate(&env);
+  let token = create_token_contract(&env, &owner);
+  let vesting = create_vesting_contract(&env);
+  token.mint(&owner, &1000);
+
+  vesting.initialize(&owner, &token.address, &install_account_wasm(&env));
+  vesting.create_schedule(&owner, &beneficiary, &1000, &0, &0, &1000, &false);
+
+  assert_eq!(
+    vesting.try_stake(&beneficiary, &100),
+    Err(Ok(Error::StakingNotConfigured))
+  );
+}
@@ -0,0 +1,42 @@
+use crate::storage_types::{DataKey, BALANCE_BUMP_AMOUNT, BALANCE_LIFETIME_THRESHOLD};
+use soroban_sdk::{Address, Env};
+
+pub fn read_allowlist_enabled(e: &Env) -> bool {
+  let key = DataKey::AllowlistEnabled;
+  e.storage().instance().get(&key).unwrap_or(false)
+}
+
+pub fn write_allowlist_enabled(e: &Env, enabled: bool) {
+  let key = DataKey::AllowlistEnabled;
+  e.storage().instance().set(&key, &enabled);
+}
+
+pub fn is_allowlisted(e: &Env, addr: Address) -> bool {
+  let key = DataKey::Allowlisted(addr);
+  if let Some(allowed) = e.storage().persistent().get::<DataKey, bool>(&key) {
+    e.storage()
+      .persistent()
+      .extend_ttl(&key, BALANCE_LIFETIME_THRESHOLD, BALANCE_BUMP_AMOUNT);
+    allowed
+  } else {
+    false
+  }
+}
+
+pub fn write_allowlisted(e: &Env, addr: Address, allowed: bool) {
+  let key = DataKey::Allowlisted(addr);
+  if allowed {
+    e.storage().persistent().set(&key, &true);
+    e.storage()
+      .persistent()
+      .extend_ttl(&key, BALANCE_LIFETIME_THRESHOLD, BALANCE_BUMP_AMOUNT);
+  } else {
+    e.storage().persistent().remove(&key);
+  }
+}
+
+pub fn check_recipient_allowed(e: &Env, to: &Address) {
+  if read_allowlist_enabled(e) && !is_allowlisted(e, to.clone()) {
+    panic!("recipient is not allowlisted");
+  }
+}
@@ -1,4 +1,5 @@
 use crate::storage_types::{DataKey, BALANCE_BUMP_AMOUNT, BALANCE_LIFETIME_THRESHOLD};
+use crate::votes::{move_votes, read_delegate};
 use soroban_sdk::{Address, Env};
 
 pub fn read_balance(e: &Env, addr: Address) -> i128 {
@@ -23,6 +24,7 @@ fn write_balance(e: &Env, addr: Address, amount: i128) {
 
 pub fn receive_balance(e: &Env, addr: Address, amount: i128) {
   let balance = read_balance(e, addr.clone());
+  move_votes(e, None, Some(read_delegate(e, addr.clone())), amount);
   write_balance(e, addr, balance + amount);
 }
 
@@ -31,5 +33,6 @@ pub fn spend_balance(e: &Env, addr: Address, amount: i128) {
   if balance < amount {
     panic!("insufficient balance");
   }
+  move_votes(e, Some(read_delegate(e, addr.clone())), None, amount);
   write_balance(e, addr, balance - amount);
 }
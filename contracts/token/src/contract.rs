@@ -2,11 +2,24 @@
 //! interface.
 use crate::admin::{has_administrator, read_administrator, write_administrator};
 use crate::allowance::{read_allowance, spend_allowance, write_allowance};
+use crate::allowlist::{
+  check_recipient_allowed, is_allowlisted, read_allowlist_enabled, write_allowlist_enabled,
+  write_allowlisted,
+};
 use crate::balance::{read_balance, receive_balance, spend_balance};
+use crate::freeze::{check_not_frozen, is_frozen, write_frozen};
 use crate::metadata::{read_decimal, read_name, read_symbol, write_metadata};
+use crate::minter::{read_minter_quota, spend_minter_quota, write_minter_quota};
 use crate::storage_types::{INSTANCE_BUMP_AMOUNT, INSTANCE_LIFETIME_THRESHOLD};
+use crate::supply::{
+  decrease_total_supply, increase_total_supply, read_max_supply, read_total_supply,
+  write_max_supply,
+};
+use crate::votes::{
+  move_votes, read_delegate, read_past_total_supply, read_past_votes, read_votes, write_delegate,
+};
 use soroban_sdk::token::{self, Interface as _};
-use soroban_sdk::{contract, contractimpl, Address, Env, String};
+use soroban_sdk::{contract, contractimpl, symbol_short, Address, Env, String, Symbol, Vec};
 use soroban_token_sdk::metadata::TokenMetadata;
 use soroban_token_sdk::TokenUtils;
 
@@ -21,7 +34,14 @@ pub struct Token;
 
 #[contractimpl]
 impl Token {
-  pub fn initialize(e: Env, admin: Address, decimal: u32, name: String, symbol: String) {
+  pub fn initialize(
+    e: Env,
+    admin: Address,
+    decimal: u32,
+    name: String,
+    symbol: String,
+    max_supply: Option<i128>,
+  ) {
     if has_administrator(&e) {
       panic!("already initialized")
     }
@@ -29,6 +49,10 @@ impl Token {
     if decimal > 18 {
       panic!("Decimal must not be greater than 18");
     }
+    if let Some(max_supply) = max_supply {
+      check_nonnegative_amount(max_supply);
+    }
+    write_max_supply(&e, max_supply);
 
     write_metadata(
       &e,
@@ -49,10 +73,73 @@ impl Token {
       .instance()
       .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
 
+    check_recipient_allowed(&e, &to);
+    check_not_frozen(&e, &to);
     receive_balance(&e, to.clone(), amount);
+    increase_total_supply(&e, amount);
     TokenUtils::new(&e).events().mint(admin, to, amount);
   }
 
+  pub fn batch_mint(e: Env, recipients: Vec<(Address, i128)>) {
+    let admin = read_administrator(&e);
+    admin.require_auth();
+
+    e.storage()
+      .instance()
+      .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+
+    // any invalid entry panics and reverts the whole batch
+    for (to, amount) in recipients.iter() {
+      check_nonnegative_amount(amount);
+      check_recipient_allowed(&e, &to);
+      check_not_frozen(&e, &to);
+      receive_balance(&e, to.clone(), amount);
+      increase_total_supply(&e, amount);
+      TokenUtils::new(&e).events().mint(admin.clone(), to, amount);
+    }
+  }
+
+  pub fn minter_mint(e: Env, minter: Address, to: Address, amount: i128) {
+    check_nonnegative_amount(amount);
+    minter.require_auth();
+
+    e.storage()
+      .instance()
+      .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+
+    spend_minter_quota(&e, minter.clone(), amount);
+    check_recipient_allowed(&e, &to);
+    check_not_frozen(&e, &to);
+    receive_balance(&e, to.clone(), amount);
+    increase_total_supply(&e, amount);
+    TokenUtils::new(&e).events().mint(minter, to, amount);
+  }
+
+  pub fn set_minter(e: Env, minter: Address, limit: i128) {
+    check_nonnegative_amount(limit);
+    let admin = read_administrator(&e);
+    admin.require_auth();
+
+    e.storage()
+      .instance()
+      .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+
+    write_minter_quota(&e, minter.clone(), limit);
+    e.events()
+      .publish((Symbol::new(&e, "set_minter"), admin, minter), limit);
+  }
+
+  pub fn minter_quota(e: Env, minter: Address) -> i128 {
+    e.storage()
+      .instance()
+      .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+    read_minter_quota(&e, minter)
+  }
+
+  pub fn max_supply(e: Env) -> Option<i128> {
+    read_max_supply(&e)
+  }
+
   pub fn set_admin(e: Env, new_admin: Address) {
     let admin = read_administrator(&e);
     admin.require_auth();
@@ -64,6 +151,149 @@ impl Token {
     write_administrator(&e, &new_admin);
     TokenUtils::new(&e).events().set_admin(admin, new_admin);
   }
+
+  pub fn set_allowlist_enabled(e: Env, enabled: bool) {
+    let admin = read_administrator(&e);
+    admin.require_auth();
+
+    e.storage()
+      .instance()
+      .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+
+    write_allowlist_enabled(&e, enabled);
+    e.events()
+      .publish((symbol_short!("allowlist"), admin), enabled);
+  }
+
+  pub fn set_allowlisted(e: Env, id: Address, allowed: bool) {
+    let admin = read_administrator(&e);
+    admin.require_auth();
+
+    e.storage()
+      .instance()
+      .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+
+    write_allowlisted(&e, id.clone(), allowed);
+    e.events()
+      .publish((symbol_short!("allowed"), admin, id), allowed);
+  }
+
+  pub fn clawback(e: Env, from: Address, amount: i128) {
+    check_nonnegative_amount(amount);
+    let admin = read_administrator(&e);
+    admin.require_auth();
+
+    e.storage()
+      .instance()
+      .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+
+    spend_balance(&e, from.clone(), amount);
+    decrease_total_supply(&e, amount);
+    TokenUtils::new(&e).events().clawback(admin, from, amount);
+  }
+
+  pub fn total_supply(e: Env) -> i128 {
+    e.storage()
+      .instance()
+      .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+    read_total_supply(&e)
+  }
+
+  pub fn freeze(e: Env, id: Address) {
+    let admin = read_administrator(&e);
+    admin.require_auth();
+
+    e.storage()
+      .instance()
+      .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+
+    write_frozen(&e, id.clone(), true);
+    e.events().publish((symbol_short!("freeze"), admin, id), ());
+  }
+
+  pub fn unfreeze(e: Env, id: Address) {
+    let admin = read_administrator(&e);
+    admin.require_auth();
+
+    e.storage()
+      .instance()
+      .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+
+    write_frozen(&e, id.clone(), false);
+    e.events()
+      .publish((symbol_short!("unfreeze"), admin, id), ());
+  }
+
+  pub fn is_frozen(e: Env, id: Address) -> bool {
+    is_frozen(&e, id)
+  }
+
+  pub fn allowlist_enabled(e: Env) -> bool {
+    read_allowlist_enabled(&e)
+  }
+
+  pub fn allowlisted(e: Env, id: Address) -> bool {
+    is_allowlisted(&e, id)
+  }
+
+  // moves the voting power of the whole balance, now and for every future transfer
+  pub fn delegate(e: Env, delegator: Address, delegatee: Address) {
+    delegator.require_auth();
+
+    e.storage()
+      .instance()
+      .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+
+    let previous = read_delegate(&e, delegator.clone());
+    write_delegate(&e, delegator.clone(), delegatee.clone());
+    move_votes(
+      &e,
+      Some(previous.clone()),
+      Some(delegatee.clone()),
+      read_balance(&e, delegator.clone()),
+    );
+    e.events().publish(
+      (symbol_short!("delegate"), delegator),
+      (previous, delegatee),
+    );
+  }
+
+  pub fn delegates(e: Env, account: Address) -> Address {
+    read_delegate(&e, account)
+  }
+
+  pub fn get_votes(e: Env, account: Address) -> i128 {
+    read_votes(&e, account)
+  }
+
+  pub fn get_past_votes(e: Env, account: Address, timestamp: u64) -> i128 {
+    read_past_votes(&e, account, timestamp)
+  }
+
+  pub fn get_past_total_supply(e: Env, timestamp: u64) -> i128 {
+    read_past_total_supply(&e, timestamp)
+  }
+
+  pub fn batch_transfer(e: Env, from: Address, recipients: Vec<(Address, i128)>) {
+    from.require_auth();
+
+    e.storage()
+      .instance()
+      .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+
+    check_not_frozen(&e, &from);
+    // any invalid entry panics and reverts the whole batch
+    for (to, amount) in recipients.iter() {
+      check_nonnegative_amount(amount);
+      check_not_frozen(&e, &to);
+      check_recipient_allowed(&e, &to);
+      spend_balance(&e, from.clone(), amount);
+      receive_balance(&e, to.clone(), amount);
+      TokenUtils::new(&e)
+        .events()
+        .transfer(from.clone(), to, amount);
+    }
+  }
 }
 
 #[contractimpl]
@@ -106,6 +336,9 @@ impl token::Interface for Token {
       .instance()
       .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
 
+    check_not_frozen(&e, &from);
+    check_not_frozen(&e, &to);
+    check_recipient_allowed(&e, &to);
     spend_balance(&e, from.clone(), amount);
     receive_balance(&e, to.clone(), amount);
     TokenUtils::new(&e).events().transfer(from, to, amount);
@@ -120,6 +353,10 @@ impl token::Interface for Token {
       .instance()
       .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
 
+    check_not_frozen(&e, &spender);
+    check_not_frozen(&e, &from);
+    check_not_frozen(&e, &to);
+    check_recipient_allowed(&e, &to);
     spend_allowance(&e, from.clone(), spender, amount);
     spend_balance(&e, from.clone(), amount);
     receive_balance(&e, to.clone(), amount);
@@ -135,7 +372,9 @@ impl token::Interface for Token {
       .instance()
       .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
 
+    check_not_frozen(&e, &from);
     spend_balance(&e, from.clone(), amount);
+    decrease_total_supply(&e, amount);
     TokenUtils::new(&e).events().burn(from, amount);
   }
 
@@ -148,8 +387,11 @@ impl token::Interface for Token {
       .instance()
       .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
 
+    check_not_frozen(&e, &spender);
+    check_not_frozen(&e, &from);
     spend_allowance(&e, from.clone(), spender, amount);
     spend_balance(&e, from.clone(), amount);
+    decrease_total_supply(&e, amount);
     TokenUtils::new(&e).events().burn(from, amount)
   }
 
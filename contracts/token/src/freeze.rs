@@ -0,0 +1,32 @@
+use crate::storage_types::{DataKey, BALANCE_BUMP_AMOUNT, BALANCE_LIFETIME_THRESHOLD};
+use soroban_sdk::{Address, Env};
+
+pub fn is_frozen(e: &Env, addr: Address) -> bool {
+  let key = DataKey::Frozen(addr);
+  if let Some(frozen) = e.storage().persistent().get::<DataKey, bool>(&key) {
+    e.storage()
+      .persistent()
+      .extend_ttl(&key, BALANCE_LIFETIME_THRESHOLD, BALANCE_BUMP_AMOUNT);
+    frozen
+  } else {
+    false
+  }
+}
+
+pub fn write_frozen(e: &Env, addr: Address, frozen: bool) {
+  let key = DataKey::Frozen(addr);
+  if frozen {
+    e.storage().persistent().set(&key, &true);
+    e.storage()
+      .persistent()
+      .extend_ttl(&key, BALANCE_LIFETIME_THRESHOLD, BALANCE_BUMP_AMOUNT);
+  } else {
+    e.storage().persistent().remove(&key);
+  }
+}
+
+pub fn check_not_frozen(e: &Env, addr: &Address) {
+  if is_frozen(e, addr.clone()) {
+    panic!("account is frozen");
+  }
+}
@@ -2,10 +2,15 @@
 
 mod admin;
 mod allowance;
+mod allowlist;
 mod balance;
 mod contract;
+mod freeze;
 mod metadata;
+mod minter;
 mod storage_types;
+mod supply;
 mod test;
+mod votes;
 
 pub use crate::contract::TokenClient;
@@ -0,0 +1,34 @@
+use crate::storage_types::{DataKey, BALANCE_BUMP_AMOUNT, BALANCE_LIFETIME_THRESHOLD};
+use soroban_sdk::{Address, Env};
+
+pub fn read_minter_quota(e: &Env, minter: Address) -> i128 {
+  let key = DataKey::Minter(minter);
+  if let Some(quota) = e.storage().persistent().get::<DataKey, i128>(&key) {
+    e.storage()
+      .persistent()
+      .extend_ttl(&key, BALANCE_LIFETIME_THRESHOLD, BALANCE_BUMP_AMOUNT);
+    quota
+  } else {
+    0
+  }
+}
+
+pub fn write_minter_quota(e: &Env, minter: Address, quota: i128) {
+  let key = DataKey::Minter(minter);
+  if quota > 0 {
+    e.storage().persistent().set(&key, &quota);
+    e.storage()
+      .persistent()
+      .extend_ttl(&key, BALANCE_LIFETIME_THRESHOLD, BALANCE_BUMP_AMOUNT);
+  } else {
+    e.storage().persistent().remove(&key);
+  }
+}
+
+pub fn spend_minter_quota(e: &Env, minter: Address, amount: i128) {
+  let quota = read_minter_quota(e, minter.clone());
+  if quota < amount {
+    panic!("insufficient minter quota");
+  }
+  write_minter_quota(e, minter, quota - amount);
+}
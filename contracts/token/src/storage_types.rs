@@ -20,6 +20,13 @@ pub struct AllowanceValue {
   pub expiration_ledger: u32,
 }
 
+#[derive(Clone)]
+#[contracttype]
+pub struct Checkpoint {
+  pub timestamp: u64,
+  pub votes: i128,
+}
+
 #[derive(Clone)]
 #[contracttype]
 pub enum DataKey {
@@ -27,4 +34,15 @@ pub enum DataKey {
   Balance(Address),
   State(Address),
   Admin,
+  AllowlistEnabled,
+  Allowlisted(Address),
+  Frozen(Address),
+  TotalSupply,
+  MaxSupply,
+  Minter(Address),
+  Delegate(Address),
+  CheckpointCount(Address),
+  Checkpoint(Address, u32),
+  SupplyCheckpointCount,
+  SupplyCheckpoint(u32),
 }
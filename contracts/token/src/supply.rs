@@ -0,0 +1,41 @@
+use crate::storage_types::DataKey;
+use crate::votes::write_supply_checkpoint;
+use soroban_sdk::Env;
+
+pub fn read_total_supply(e: &Env) -> i128 {
+  let key = DataKey::TotalSupply;
+  e.storage().instance().get(&key).unwrap_or(0)
+}
+
+fn write_total_supply(e: &Env, amount: i128) {
+  let key = DataKey::TotalSupply;
+  e.storage().instance().set(&key, &amount);
+  write_supply_checkpoint(e, amount);
+}
+
+pub fn read_max_supply(e: &Env) -> Option<i128> {
+  let key = DataKey::MaxSupply;
+  e.storage().instance().get(&key)
+}
+
+pub fn write_max_supply(e: &Env, max_supply: Option<i128>) {
+  let key = DataKey::MaxSupply;
+  if let Some(max_supply) = max_supply {
+    e.storage().instance().set(&key, &max_supply);
+  }
+}
+
+pub fn increase_total_supply(e: &Env, amount: i128) {
+  let supply = read_total_supply(e);
+  if let Some(max_supply) = read_max_supply(e) {
+    if supply + amount > max_supply {
+      panic!("max supply exceeded");
+    }
+  }
+  write_total_supply(e, supply + amount);
+}
+
+pub fn decrease_total_supply(e: &Env, amount: i128) {
+  let supply = read_total_supply(e);
+  write_total_supply(e, supply - amount);
+}
@@ -1,16 +1,19 @@
 #![cfg(test)]
 extern crate std;
 
+use crate::storage_types::{DataKey, BALANCE_BUMP_AMOUNT, DAY_IN_LEDGERS};
 use crate::{contract::Token, TokenClient};
 use soroban_sdk::{
   symbol_short,
-  testutils::{Address as _, AuthorizedFunction, AuthorizedInvocation},
-  Address, Env, IntoVal, Symbol,
+  testutils::{
+    storage::Persistent, Address as _, AuthorizedFunction, AuthorizedInvocation, Events, Ledger,
+  },
+  vec, Address, Env, IntoVal, Symbol,
 };
 
 fn create_token<'a>(e: &Env, admin: &Address) -> TokenClient<'a> {
   let token = TokenClient::new(e, &e.register_contract(None, Token {}));
-  token.initialize(admin, &7, &"name".into_val(e), &"symbol".into_val(e));
+  token.initialize(admin, &7, &"name".into_val(e), &"symbol".into_val(e), &None);
   token
 }
 
@@ -238,7 +241,13 @@ fn initialize_already_initialized() {
   let admin = Address::generate(&e);
   let token = create_token(&e, &admin);
 
-  token.initialize(&admin, &10, &"name".into_val(&e), &"symbol".into_val(&e));
+  token.initialize(
+    &admin,
+    &10,
+    &"name".into_val(&e),
+    &"symbol".into_val(&e),
+    &None,
+  );
 }
 
 #[test]
@@ -247,5 +256,680 @@ fn decimal_is_over_eighteen() {
   let e = Env::default();
   let admin = Address::generate(&e);
   let token = TokenClient::new(&e, &e.register_contract(None, Token {}));
-  token.initialize(&admin, &19, &"name".into_val(&e), &"symbol".into_val(&e));
+  token.initialize(
+    &admin,
+    &19,
+    &"name".into_val(&e),
+    &"symbol".into_val(&e),
+    &None,
+  );
+}
+
+#[test]
+fn test_allowlist() {
+  let e = Env::default();
+  e.mock_all_auths();
+
+  let admin = Address::generate(&e);
+  let user1 = Address::generate(&e);
+  let user2 = Address::generate(&e);
+  let user3 = Address::generate(&e);
+  let token = create_token(&e, &admin);
+
+  assert!(!token.allowlist_enabled());
+
+  token.set_allowlist_enabled(&true);
+  token.set_allowlisted(&user1, &true);
+  token.set_allowlisted(&user2, &true);
+  assert!(token.allowlist_enabled());
+  assert!(token.allowlisted(&user1));
+  assert!(!token.allowlisted(&user3));
+
+  token.mint(&user1, &1000);
+  token.transfer(&user1, &user2, &400);
+  assert_eq!(token.balance(&user2), 400);
+
+  // a removed holder can still send its tokens away or burn them
+  token.set_allowlisted(&user2, &false);
+  assert!(!token.allowlisted(&user2));
+  token.transfer(&user2, &user1, &100);
+  token.burn(&user2, &100);
+  assert_eq!(token.balance(&user1), 700);
+  assert_eq!(token.balance(&user2), 200);
+
+  token.set_allowlist_enabled(&false);
+  token.transfer(&user1, &user3, &100);
+  assert_eq!(token.balance(&user3), 100);
+}
+
+#[test]
+#[should_panic(expected = "recipient is not allowlisted")]
+fn transfer_to_non_allowlisted() {
+  let e = Env::default();
+  e.mock_all_auths();
+
+  let admin = Address::generate(&e);
+  let user1 = Address::generate(&e);
+  let user2 = Address::generate(&e);
+  let token = create_token(&e, &admin);
+
+  token.mint(&user1, &1000);
+  token.set_allowlist_enabled(&true);
+
+  token.transfer(&user1, &user2, &100);
+}
+
+#[test]
+#[should_panic(expected = "recipient is not allowlisted")]
+fn transfer_from_to_non_allowlisted() {
+  let e = Env::default();
+  e.mock_all_auths();
+
+  let admin = Address::generate(&e);
+  let user1 = Address::generate(&e);
+  let user2 = Address::generate(&e);
+  let user3 = Address::generate(&e);
+  let token = create_token(&e, &admin);
+
+  token.mint(&user1, &1000);
+  token.approve(&user1, &user3, &500, &200);
+  token.set_allowlist_enabled(&true);
+  token.set_allowlisted(&user3, &true);
+
+  token.transfer_from(&user3, &user1, &user2, &100);
+}
+
+#[test]
+fn test_freeze() {
+  let e = Env::default();
+  e.mock_all_auths();
+
+  let admin = Address::generate(&e);
+  let user1 = Address::generate(&e);
+  let user2 = Address::generate(&e);
+  let token = create_token(&e, &admin);
+
+  token.mint(&user1, &1000);
+
+  token.freeze(&user1);
+  assert_eq!(
+    e.auths(),
+    std::vec![(
+      admin.clone(),
+      AuthorizedInvocation {
+        function: AuthorizedFunction::Contract((
+          token.address.clone(),
+          symbol_short!("freeze"),
+          (&user1,).into_val(&e),
+        )),
+        sub_invocations: std::vec![]
+      }
+    )]
+  );
+  assert!(token.is_frozen(&user1));
+  assert!(!token.is_frozen(&user2));
+
+  assert!(token.try_transfer(&user1, &user2, &100).is_err());
+  assert!(token.try_burn(&user1, &100).is_err());
+  assert!(token.try_mint(&user1, &100).is_err());
+
+  token.unfreeze(&user1);
+  assert!(!token.is_frozen(&user1));
+
+  token.transfer(&user1, &user2, &100);
+  assert_eq!(token.balance(&user1), 900);
+  assert_eq!(token.balance(&user2), 100);
+}
+
+#[test]
+#[should_panic(expected = "account is frozen")]
+fn transfer_to_frozen() {
+  let e = Env::default();
+  e.mock_all_auths();
+
+  let admin = Address::generate(&e);
+  let user1 = Address::generate(&e);
+  let user2 = Address::generate(&e);
+  let token = create_token(&e, &admin);
+
+  token.mint(&user1, &1000);
+  token.freeze(&user2);
+
+  token.transfer(&user1, &user2, &100);
+}
+
+#[test]
+#[should_panic(expected = "account is frozen")]
+fn transfer_from_frozen_spender() {
+  let e = Env::default();
+  e.mock_all_auths();
+
+  let admin = Address::generate(&e);
+  let user1 = Address::generate(&e);
+  let user2 = Address::generate(&e);
+  let user3 = Address::generate(&e);
+  let token = create_token(&e, &admin);
+
+  token.mint(&user1, &1000);
+  token.approve(&user1, &user3, &500, &200);
+  token.freeze(&user3);
+
+  token.transfer_from(&user3, &user1, &user2, &100);
+}
+
+#[test]
+#[should_panic(expected = "account is frozen")]
+fn burn_from_frozen() {
+  let e = Env::default();
+  e.mock_all_auths();
+
+  let admin = Address::generate(&e);
+  let user1 = Address::generate(&e);
+  let user2 = Address::generate(&e);
+  let token = create_token(&e, &admin);
+
+  token.mint(&user1, &1000);
+  token.approve(&user1, &user2, &500, &200);
+  token.freeze(&user1);
+
+  token.burn_from(&user2, &user1, &100);
+}
+
+#[test]
+fn test_clawback() {
+  let e = Env::default();
+  e.mock_all_auths();
+
+  let admin = Address::generate(&e);
+  let user1 = Address::generate(&e);
+  let token = create_token(&e, &admin);
+
+  token.mint(&user1, &1000);
+
+  // works on frozen accounts too, only the admin signs
+  token.freeze(&user1);
+  token.clawback(&user1, &400);
+  assert_eq!(
+    e.auths(),
+    std::vec![(
+      admin.clone(),
+      AuthorizedInvocation {
+        function: AuthorizedFunction::Contract((
+          token.address.clone(),
+          symbol_short!("clawback"),
+          (&user1, 400_i128).into_val(&e),
+        )),
+        sub_invocations: std::vec![]
+      }
+    )]
+  );
+  assert_eq!(token.balance(&user1), 600);
+}
+
+#[test]
+#[should_panic(expected = "insufficient balance")]
+fn clawback_insufficient_balance() {
+  let e = Env::default();
+  e.mock_all_auths();
+
+  let admin = Address::generate(&e);
+  let user1 = Address::generate(&e);
+  let token = create_token(&e, &admin);
+
+  token.mint(&user1, &1000);
+
+  token.clawback(&user1, &1001);
+}
+
+#[test]
+fn test_total_supply() {
+  let e = Env::default();
+  e.mock_all_auths();
+
+  let admin = Address::generate(&e);
+  let user1 = Address::generate(&e);
+  let user2 = Address::generate(&e);
+  let token = create_token(&e, &admin);
+
+  assert_eq!(token.total_supply(), 0);
+
+  token.mint(&user1, &1000);
+  token.mint(&user2, &500);
+  assert_eq!(token.total_supply(), 1500);
+
+  token.transfer(&user1, &user2, &300);
+  assert_eq!(token.total_supply(), 1500);
+
+  token.burn(&user2, &200);
+  token.approve(&user1, &user2, &100, &200);
+  token.burn_from(&user2, &user1, &100);
+  token.clawback(&user2, &50);
+  assert_eq!(token.total_supply(), 1150);
+  assert_eq!(token.balance(&user1) + token.balance(&user2), 1150);
+}
+
+#[test]
+fn test_total_supply_matches_balances_randomized() {
+  let e = Env::default();
+  e.mock_all_auths();
+  e.budget().reset_unlimited();
+
+  let admin = Address::generate(&e);
+  let users: std::vec::Vec<Address> = (0..4).map(|_| Address::generate(&e)).collect();
+  let token = create_token(&e, &admin);
+
+  for seed in 1..=8_u64 {
+    // xorshift, so every failing sequence can be replayed from its seed
+    let mut state = seed;
+    let mut next = || {
+      state ^= state << 13;
+      state ^= state >> 7;
+      state ^= state << 17;
+      state
+    };
+
+    for _ in 0..40 {
+      let from = &users[(next() % 4) as usize];
+      let to = &users[(next() % 4) as usize];
+      let balance = token.balance(from);
+      let amount = (next() % 500) as i128;
+
+      match next() % 5 {
+        0 => token.mint(to, &amount),
+        1 => token.transfer(from, to, &amount.min(balance)),
+        2 => token.burn(from, &amount.min(balance)),
+        3 => {
+          token.approve(from, to, &amount.min(balance), &200);
+          token.burn_from(to, from, &amount.min(balance));
+        }
+        _ => token.clawback(from, &amount.min(balance)),
+      }
+
+      let sum: i128 = users.iter().map(|user| token.balance(user)).sum();
+      assert_eq!(token.total_supply(), sum, "seed {}", seed);
+    }
+  }
+}
+
+#[test]
+fn test_max_supply() {
+  let e = Env::default();
+  e.mock_all_auths();
+
+  let admin = Address::generate(&e);
+  let user1 = Address::generate(&e);
+  let token = TokenClient::new(&e, &e.register_contract(None, Token {}));
+  token.initialize(
+    &admin,
+    &7,
+    &"name".into_val(&e),
+    &"symbol".into_val(&e),
+    &Some(1000),
+  );
+  assert_eq!(token.max_supply(), Some(1000));
+
+  token.mint(&user1, &1000);
+  assert_eq!(token.total_supply(), 1000);
+
+  // burning frees room under the cap again
+  token.burn(&user1, &100);
+  token.mint(&user1, &100);
+  assert_eq!(token.total_supply(), 1000);
+
+  assert_eq!(create_token(&e, &admin).max_supply(), None);
+}
+
+#[test]
+#[should_panic(expected = "max supply exceeded")]
+fn mint_over_max_supply() {
+  let e = Env::default();
+  e.mock_all_auths();
+
+  let admin = Address::generate(&e);
+  let user1 = Address::generate(&e);
+  let token = TokenClient::new(&e, &e.register_contract(None, Token {}));
+  token.initialize(
+    &admin,
+    &7,
+    &"name".into_val(&e),
+    &"symbol".into_val(&e),
+    &Some(1000),
+  );
+
+  token.mint(&user1, &600);
+  token.mint(&user1, &401);
+}
+
+#[test]
+fn test_minter() {
+  let e = Env::default();
+  e.mock_all_auths();
+
+  let admin = Address::generate(&e);
+  let minter = Address::generate(&e);
+  let user1 = Address::generate(&e);
+  let token = create_token(&e, &admin);
+
+  token.set_minter(&minter, &500);
+  assert_eq!(
+    e.auths(),
+    std::vec![(
+      admin.clone(),
+      AuthorizedInvocation {
+        function: AuthorizedFunction::Contract((
+          token.address.clone(),
+          Symbol::new(&e, "set_minter"),
+          (&minter, 500_i128).into_val(&e),
+        )),
+        sub_invocations: std::vec![]
+      }
+    )]
+  );
+  assert_eq!(token.minter_quota(&minter), 500);
+
+  token.minter_mint(&minter, &user1, &300);
+  assert_eq!(
+    e.auths(),
+    std::vec![(
+      minter.clone(),
+      AuthorizedInvocation {
+        function: AuthorizedFunction::Contract((
+          token.address.clone(),
+          Symbol::new(&e, "minter_mint"),
+          (&minter, &user1, 300_i128).into_val(&e),
+        )),
+        sub_invocations: std::vec![]
+      }
+    )]
+  );
+  assert_eq!(token.balance(&user1), 300);
+  assert_eq!(token.total_supply(), 300);
+  assert_eq!(token.minter_quota(&minter), 200);
+
+  // setting the limit to 0 revokes the role
+  token.set_minter(&minter, &0);
+  assert_eq!(token.minter_quota(&minter), 0);
+}
+
+#[test]
+#[should_panic(expected = "insufficient minter quota")]
+fn minter_mint_over_quota() {
+  let e = Env::default();
+  e.mock_all_auths();
+
+  let admin = Address::generate(&e);
+  let minter = Address::generate(&e);
+  let user1 = Address::generate(&e);
+  let token = create_token(&e, &admin);
+
+  token.set_minter(&minter, &500);
+  token.minter_mint(&minter, &user1, &300);
+  token.minter_mint(&minter, &user1, &201);
+}
+
+#[test]
+#[should_panic(expected = "insufficient minter quota")]
+fn non_minter_cannot_mint() {
+  let e = Env::default();
+  e.mock_all_auths();
+
+  let admin = Address::generate(&e);
+  let user1 = Address::generate(&e);
+  let token = create_token(&e, &admin);
+
+  token.minter_mint(&user1, &user1, &1);
+}
+
+#[test]
+fn test_batch_transfer() {
+  let e = Env::default();
+  e.mock_all_auths();
+
+  let admin = Address::generate(&e);
+  let user1 = Address::generate(&e);
+  let user2 = Address::generate(&e);
+  let user3 = Address::generate(&e);
+  let token = create_token(&e, &admin);
+
+  token.mint(&user1, &1000);
+
+  let events_before = e.events().all().len();
+  let recipients = vec![&e, (user2.clone(), 300_i128), (user3.clone(), 200_i128)];
+  token.batch_transfer(&user1, &recipients);
+  assert_eq!(
+    e.auths(),
+    std::vec![(
+      user1.clone(),
+      AuthorizedInvocation {
+        function: AuthorizedFunction::Contract((
+          token.address.clone(),
+          Symbol::new(&e, "batch_transfer"),
+          (&user1, recipients.clone()).into_val(&e),
+        )),
+        sub_invocations: std::vec![]
+      }
+    )]
+  );
+  // one transfer event per recipient
+  assert_eq!(e.events().all().len(), events_before + 2);
+
+  assert_eq!(token.balance(&user1), 500);
+  assert_eq!(token.balance(&user2), 300);
+  assert_eq!(token.balance(&user3), 200);
+}
+
+#[test]
+fn batch_transfer_is_atomic() {
+  let e = Env::default();
+  e.mock_all_auths();
+
+  let admin = Address::generate(&e);
+  let user1 = Address::generate(&e);
+  let user2 = Address::generate(&e);
+  let user3 = Address::generate(&e);
+  let token = create_token(&e, &admin);
+
+  token.mint(&user1, &1000);
+
+  // the second entry overdraws, so the first must not go through either
+  let recipients = vec![&e, (user2.clone(), 600_i128), (user3.clone(), 600_i128)];
+  assert!(token.try_batch_transfer(&user1, &recipients).is_err());
+
+  let recipients = vec![&e, (user2.clone(), 100_i128), (user3.clone(), -1_i128)];
+  assert!(token.try_batch_transfer(&user1, &recipients).is_err());
+
+  assert_eq!(token.balance(&user1), 1000);
+  assert_eq!(token.balance(&user2), 0);
+  assert_eq!(token.balance(&user3), 0);
+}
+
+#[test]
+fn test_batch_mint() {
+  let e = Env::default();
+  e.mock_all_auths();
+
+  let admin = Address::generate(&e);
+  let user1 = Address::generate(&e);
+  let user2 = Address::generate(&e);
+  let token = create_token(&e, &admin);
+
+  let recipients = vec![&e, (user1.clone(), 300_i128), (user2.clone(), 200_i128)];
+  token.batch_mint(&recipients);
+  assert_eq!(
+    e.auths(),
+    std::vec![(
+      admin.clone(),
+      AuthorizedInvocation {
+        function: AuthorizedFunction::Contract((
+          token.address.clone(),
+          Symbol::new(&e, "batch_mint"),
+          (recipients.clone(),).into_val(&e),
+        )),
+        sub_invocations: std::vec![]
+      }
+    )]
+  );
+  assert_eq!(e.events().all().len(), 2);
+
+  assert_eq!(token.balance(&user1), 300);
+  assert_eq!(token.balance(&user2), 200);
+  assert_eq!(token.total_supply(), 500);
+}
+
+#[test]
+fn batch_mint_is_atomic() {
+  let e = Env::default();
+  e.mock_all_auths();
+
+  let admin = Address::generate(&e);
+  let user1 = Address::generate(&e);
+  let user2 = Address::generate(&e);
+  let token = create_token(&e, &admin);
+
+  token.freeze(&user2);
+
+  let recipients = vec![&e, (user1.clone(), 300_i128), (user2.clone(), 200_i128)];
+  assert!(token.try_batch_mint(&recipients).is_err());
+
+  assert_eq!(token.balance(&user1), 0);
+  assert_eq!(token.total_supply(), 0);
+}
+
+#[test]
+fn test_votes_follow_balances() {
+  let e = Env::default();
+  e.mock_all_auths();
+
+  let admin = Address::generate(&e);
+  let user1 = Address::generate(&e);
+  let user2 = Address::generate(&e);
+  let token = create_token(&e, &admin);
+
+  e.ledger().with_mut(|li| li.timestamp = 100);
+  token.mint(&user1, &1000);
+
+  e.ledger().with_mut(|li| li.timestamp = 200);
+  token.transfer(&user1, &user2, &400);
+
+  e.ledger().with_mut(|li| li.timestamp = 300);
+  token.burn(&user2, &100);
+
+  e.ledger().with_mut(|li| li.timestamp = 400);
+
+  // holders vote for themselves until they delegate
+  assert_eq!(token.delegates(&user1), user1);
+  assert_eq!(token.get_votes(&user1), 600);
+  assert_eq!(token.get_votes(&user2), 300);
+
+  assert_eq!(token.get_past_votes(&user1, &99), 0);
+  assert_eq!(token.get_past_votes(&user1, &100), 1000);
+  assert_eq!(token.get_past_votes(&user1, &150), 1000);
+  assert_eq!(token.get_past_votes(&user1, &200), 600);
+  assert_eq!(token.get_past_votes(&user2, &250), 400);
+  assert_eq!(token.get_past_votes(&user2, &300), 300);
+
+  assert_eq!(token.get_past_total_supply(&99), 0);
+  assert_eq!(token.get_past_total_supply(&250), 1000);
+  assert_eq!(token.get_past_total_supply(&399), 900);
+}
+
+#[test]
+fn test_delegate() {
+  let e = Env::default();
+  e.mock_all_auths();
+
+  let admin = Address::generate(&e);
+  let user1 = Address::generate(&e);
+  let user2 = Address::generate(&e);
+  let delegatee = Address::generate(&e);
+  let token = create_token(&e, &admin);
+
+  token.mint(&user1, &1000);
+  token.mint(&user2, &500);
+
+  e.ledger().with_mut(|li| li.timestamp = 100);
+  token.delegate(&user1, &delegatee);
+  assert_eq!(
+    e.auths(),
+    std::vec![(
+      user1.clone(),
+      AuthorizedInvocation {
+        function: AuthorizedFunction::Contract((
+          token.address.clone(),
+          symbol_short!("delegate"),
+          (&user1, &delegatee).into_val(&e),
+        )),
+        sub_invocations: std::vec![]
+      }
+    )]
+  );
+
+  assert_eq!(token.delegates(&user1), delegatee);
+  assert_eq!(token.get_votes(&user1), 0);
+  assert_eq!(token.get_votes(&delegatee), 1000);
+
+  // later balance changes follow the delegation
+  e.ledger().with_mut(|li| li.timestamp = 200);
+  token.transfer(&user2, &user1, &200);
+  assert_eq!(token.get_votes(&delegatee), 1200);
+  assert_eq!(token.get_votes(&user2), 300);
+
+  // delegating back to yourself takes the votes back
+  e.ledger().with_mut(|li| li.timestamp = 300);
+  token.delegate(&user1, &user1);
+  assert_eq!(token.get_votes(&delegatee), 0);
+  assert_eq!(token.get_votes(&user1), 1200);
+
+  e.ledger().with_mut(|li| li.timestamp = 400);
+  assert_eq!(token.get_past_votes(&delegatee, &150), 1000);
+  assert_eq!(token.get_past_votes(&delegatee, &250), 1200);
+  assert_eq!(token.get_past_votes(&user1, &250), 0);
+  assert_eq!(token.get_past_total_supply(&350), 1500);
+}
+
+#[test]
+fn test_vote_reads_extend_ttl() {
+  let e = Env::default();
+  e.mock_all_auths();
+
+  let admin = Address::generate(&e);
+  let user1 = Address::generate(&e);
+  let delegatee = Address::generate(&e);
+  let token = create_token(&e, &admin);
+
+  token.mint(&user1, &1000);
+  token.delegate(&user1, &delegatee);
+
+  e.ledger()
+    .with_mut(|li| li.sequence_number += 2 * DAY_IN_LEDGERS);
+
+  assert_eq!(token.delegates(&user1), delegatee);
+  assert_eq!(token.get_votes(&delegatee), 1000);
+
+  // the delegation and the checkpoint count live as long as the checkpoints they point to
+  e.as_contract(&token.address, || {
+    for key in [
+      DataKey::Delegate(user1.clone()),
+      DataKey::CheckpointCount(delegatee.clone()),
+      DataKey::Checkpoint(delegatee.clone(), 0),
+    ] {
+      assert_eq!(e.storage().persistent().get_ttl(&key), BALANCE_BUMP_AMOUNT);
+    }
+  });
+}
+
+#[test]
+#[should_panic(expected = "timestamp must be in the past")]
+fn past_votes_of_current_timestamp() {
+  let e = Env::default();
+  e.mock_all_auths();
+
+  let admin = Address::generate(&e);
+  let user1 = Address::generate(&e);
+  let token = create_token(&e, &admin);
+
+  e.ledger().with_mut(|li| li.timestamp = 100);
+  token.mint(&user1, &1000);
+
+  token.get_past_votes(&user1, &100);
 }
@@ -0,0 +1,151 @@
+use crate::storage_types::{Checkpoint, DataKey, BALANCE_BUMP_AMOUNT, BALANCE_LIFETIME_THRESHOLD};
+use soroban_sdk::{Address, Env};
+
+// `None` is the total supply, `Some` the votes of a delegatee
+fn count_key(subject: &Option<Address>) -> DataKey {
+  match subject {
+    Some(addr) => DataKey::CheckpointCount(addr.clone()),
+    None => DataKey::SupplyCheckpointCount,
+  }
+}
+
+fn checkpoint_key(subject: &Option<Address>, index: u32) -> DataKey {
+  match subject {
+    Some(addr) => DataKey::Checkpoint(addr.clone(), index),
+    None => DataKey::SupplyCheckpoint(index),
+  }
+}
+
+fn read_count(e: &Env, subject: &Option<Address>) -> u32 {
+  let key = count_key(subject);
+  if let Some(count) = e.storage().persistent().get::<DataKey, u32>(&key) {
+    e.storage()
+      .persistent()
+      .extend_ttl(&key, BALANCE_LIFETIME_THRESHOLD, BALANCE_BUMP_AMOUNT);
+    count
+  } else {
+    0
+  }
+}
+
+fn read_checkpoint(e: &Env, subject: &Option<Address>, index: u32) -> Checkpoint {
+  let key = checkpoint_key(subject, index);
+  let checkpoint = e.storage().persistent().get(&key).unwrap();
+  e.storage()
+    .persistent()
+    .extend_ttl(&key, BALANCE_LIFETIME_THRESHOLD, BALANCE_BUMP_AMOUNT);
+  checkpoint
+}
+
+fn write_checkpoint(e: &Env, subject: &Option<Address>, votes: i128) {
+  let count = read_count(e, subject);
+  let timestamp = e.ledger().timestamp();
+
+  // several changes in the same ledger only keep the last value
+  let index = if count > 0 && read_checkpoint(e, subject, count - 1).timestamp == timestamp {
+    count - 1
+  } else {
+    let key = count_key(subject);
+    e.storage().persistent().set(&key, &(count + 1));
+    e.storage()
+      .persistent()
+      .extend_ttl(&key, BALANCE_LIFETIME_THRESHOLD, BALANCE_BUMP_AMOUNT);
+    count
+  };
+
+  let key = checkpoint_key(subject, index);
+  e.storage()
+    .persistent()
+    .set(&key, &Checkpoint { timestamp, votes });
+  e.storage()
+    .persistent()
+    .extend_ttl(&key, BALANCE_LIFETIME_THRESHOLD, BALANCE_BUMP_AMOUNT);
+}
+
+fn read_latest(e: &Env, subject: &Option<Address>) -> i128 {
+  let count = read_count(e, subject);
+  if count == 0 {
+    0
+  } else {
+    read_checkpoint(e, subject, count - 1).votes
+  }
+}
+
+// binary search for the last checkpoint at or before `timestamp`
+fn read_past(e: &Env, subject: &Option<Address>, timestamp: u64) -> i128 {
+  if timestamp >= e.ledger().timestamp() {
+    panic!("timestamp must be in the past");
+  }
+
+  let mut low = 0;
+  let mut high = read_count(e, subject);
+  while low < high {
+    let mid = (low + high) / 2;
+    if read_checkpoint(e, subject, mid).timestamp > timestamp {
+      high = mid;
+    } else {
+      low = mid + 1;
+    }
+  }
+
+  if low == 0 {
+    0
+  } else {
+    read_checkpoint(e, subject, low - 1).votes
+  }
+}
+
+// accounts vote for themselves until they delegate
+pub fn read_delegate(e: &Env, addr: Address) -> Address {
+  let key = DataKey::Delegate(addr.clone());
+  if let Some(delegatee) = e.storage().persistent().get::<DataKey, Address>(&key) {
+    e.storage()
+      .persistent()
+      .extend_ttl(&key, BALANCE_LIFETIME_THRESHOLD, BALANCE_BUMP_AMOUNT);
+    delegatee
+  } else {
+    addr
+  }
+}
+
+pub fn write_delegate(e: &Env, delegator: Address, delegatee: Address) {
+  let key = DataKey::Delegate(delegator.clone());
+  if delegator == delegatee {
+    e.storage().persistent().remove(&key);
+  } else {
+    e.storage().persistent().set(&key, &delegatee);
+    e.storage()
+      .persistent()
+      .extend_ttl(&key, BALANCE_LIFETIME_THRESHOLD, BALANCE_BUMP_AMOUNT);
+  }
+}
+
+pub fn move_votes(e: &Env, from: Option<Address>, to: Option<Address>, amount: i128) {
+  if from == to || amount == 0 {
+    return;
+  }
+  if from.is_some() {
+    let votes = read_latest(e, &from);
+    write_checkpoint(e, &from, votes - amount);
+  }
+  if to.is_some() {
+    let votes = read_latest(e, &to);
+    write_checkpoint(e, &to, votes + amount);
+  }
+}
+
+pub fn read_votes(e: &Env, addr: Address) -> i128 {
+  read_latest(e, &Some(addr))
+}
+
+pub fn read_past_votes(e: &Env, addr: Address, timestamp: u64) -> i128 {
+  read_past(e, &Some(addr), timestamp)
+}
+
+pub fn write_supply_checkpoint(e: &Env, supply: i128) {
+  write_checkpoint(e, &None, supply);
+}
+
+pub fn read_past_total_supply(e: &Env, timestamp: u64) -> i128 {
+  read_past(e, &None, timestamp)
+}
@@ -0,0 +1,171 @@
+use crate::{errors::Error, staking, storage};
+use soroban_sdk::auth::{ContractContext, InvokerContractAuthEntry, SubContractInvocation};
+use soroban_sdk::{contract, contractimpl, token, vec, Address, Env, IntoVal, Symbol};
+
+// staking position of a single vesting beneficiary. The token vesting contract deploys one account
+// per beneficiary, so caps, early unstake penalties and fees apply to each position on its own
+#[contract]
+pub struct VestingAccount;
+
+#[contractimpl]
+impl VestingAccount {
+  pub fn initialize(
+    env: Env,
+    vesting: Address,
+    beneficiary: Address,
+  ) -> Result<storage::AccountState, Error> {
+    vesting.require_auth();
+
+    let mut state = env
+      .storage()
+      .instance()
+      .get(&storage::ACCOUNT_STATE)
+      .unwrap_or(storage::StorageClient::get_default_state(env.clone()));
+
+    if state.initialized {
+      return Err(Error::AlreadyInitialized);
+    }
+
+    state.vesting = vesting;
+    state.beneficiary = beneficiary;
+    state.initialized = true;
+
+    env
+      .storage()
+      .instance()
+      .set(&storage::ACCOUNT_STATE, &state);
+
+    Self::extend_instance(&env);
+
+    Ok(state)
+  }
+
+  // stakes base tokens the vesting contract sent to the account, returns the pool balance added
+  pub fn stake(env: Env, staking_contract: Address, amount: i128) -> Result<i128, Error> {
+    Self::authorize_vesting(&env)?;
+
+    let this = env.current_contract_address();
+    let staking_client = staking::Client::new(&env, &staking_contract);
+    let base_token = staking_client.get_staking_state().base_token;
+    let pool_balance_before = staking_client.get_user_position(&this).balance;
+
+    env.authorize_as_current_contract(vec![
+      &env,
+      InvokerContractAuthEntry::Contract(SubContractInvocation {
+        context: ContractContext {
+          contract: base_token,
+          fn_name: Symbol::new(&env, "transfer"),
+          args: (this.clone(), staking_contract.clone(), amount).into_val(&env),
+        },
+        sub_invocations: vec![&env],
+      }),
+    ]);
+
+    staking_client.stake(&this, &amount);
+
+    Ok(staking_client.get_user_position(&this).balance - pool_balance_before)
+  }
+
+  // unstakes and sends the base tokens received back to the vesting contract, where they stay
+  // locked. Returns what was received after unstake fees and penalties
+  pub fn unstake(env: Env, staking_contract: Address, amount: i128) -> Result<i128, Error> {
+    let state = Self::authorize_vesting(&env)?;
+
+    let this = env.current_contract_address();
+    let staking_client = staking::Client::new(&env, &staking_contract);
+    let staking_state = staking_client.get_staking_state();
+    let base_token = token::Client::new(&env, &staking_state.base_token);
+
+    let balance_before = base_token.balance(&this);
+
+    env.authorize_as_current_contract(vec![
+      &env,
+      InvokerContractAuthEntry::Contract(SubContractInvocation {
+        context: ContractContext {
+          contract: staking_state.share_token,
+          fn_name: Symbol::new(&env, "burn"),
+          args: (this.clone(), amount).into_val(&env),
+        },
+        sub_invocations: vec![&env],
+      }),
+    ]);
+
+    staking_client.unstake(&this, &amount);
+
+    let received = base_token.balance(&this) - balance_before;
+
+    if received > 0 {
+      base_token.transfer(&this, &state.vesting, &received);
+    }
+
+    Ok(received)
+  }
+
+  // pool rewards and redistributed fees are not locked, they go straight to the beneficiary
+  pub fn claim(env: Env, staking_contract: Address) -> Result<storage::Claimed, Error> {
+    let state = Self::authorize_vesting(&env)?;
+
+    let this = env.current_contract_address();
+    let staking_client = staking::Client::new(&env, &staking_contract);
+    let staking_state = staking_client.get_staking_state();
+    let reward_token = token::Client::new(&env, &staking_state.reward_token);
+    let base_token = token::Client::new(&env, &staking_state.base_token);
+
+    let rewards_before = reward_token.balance(&this);
+    let fees_before = staking_client.earned_fees(&this);
+
+    // nothing to claim is not an error here
+    let _ = staking_client.try_claim_rewards(&this);
+
+    let fees = fees_before - staking_client.earned_fees(&this);
+    let mut rewards = reward_token.balance(&this) - rewards_before;
+
+    // both come out of the same balance when the pool rewards in its base token
+    if staking_state.reward_token == staking_state.base_token {
+      rewards -= fees;
+    }
+
+    let claimed = storage::Claimed { rewards, fees };
+
+    if claimed.rewards > 0 {
+      reward_token.transfer(&this, &state.beneficiary, &claimed.rewards);
+    }
+
+    if claimed.fees > 0 {
+      base_token.transfer(&this, &state.beneficiary, &claimed.fees);
+    }
+
+    Ok(claimed)
+  }
+
+  pub fn get_account_state(env: Env) -> Result<storage::AccountState, Error> {
+    let state: storage::AccountState = env
+      .storage()
+      .instance()
+      .get(&storage::ACCOUNT_STATE)
+      .unwrap_or(storage::StorageClient::get_default_state(env.clone()));
+
+    if !state.initialized {
+      return Err(Error::NotInitialized);
+    }
+
+    Ok(state)
+  }
+
+  fn authorize_vesting(env: &Env) -> Result<storage::AccountState, Error> {
+    let state = Self::get_account_state(env.clone())?;
+
+    state.vesting.require_auth();
+
+    Self::extend_instance(env);
+
+    Ok(state)
+  }
+
+  fn extend_instance(env: &Env) {
+    env.storage().instance().extend_ttl(
+      storage::INSTANCE_LIFETIME_THRESHOLD,
+      storage::INSTANCE_BUMP_AMOUNT,
+    );
+  }
+}
@@ -0,0 +1,7 @@
+#![no_std]
+
+mod contract;
+mod errors;
+mod staking;
+mod storage;
+mod test;
@@ -0,0 +1,35 @@
+#![allow(unused)]
+use soroban_sdk::{contracttype, symbol_short, Address, Env, Symbol};
+
+pub(crate) const DAY_IN_LEDGERS: u32 = 17280;
+pub(crate) const INSTANCE_BUMP_AMOUNT: u32 = 30 * DAY_IN_LEDGERS;
+pub(crate) const INSTANCE_LIFETIME_THRESHOLD: u32 = INSTANCE_BUMP_AMOUNT - DAY_IN_LEDGERS;
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AccountState {
+  pub vesting: Address, // s_vesting - token vesting contract that deployed and drives the account
+  pub beneficiary: Address, // s_beneficiary - receives the pool rewards and fees of the position
+  pub initialized: bool,
+}
+
+pub const ACCOUNT_STATE: Symbol = symbol_short!("state");
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Claimed {
+  pub rewards: i128, // reward tokens sent to the beneficiary
+  pub fees: i128,    // redistributed base token fees sent to the beneficiary
+}
+
+pub struct StorageClient;
+
+impl StorageClient {
+  pub fn get_default_state(env: Env) -> AccountState {
+    AccountState {
+      vesting: env.current_contract_address().clone(),
+      beneficiary: env.current_contract_address().clone(),
+      initialized: false,
+    }
+  }
+}
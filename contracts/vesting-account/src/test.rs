@@ -0,0 +1,166 @@
+#![cfg(test)]
+extern crate std;
+
+use super::*;
+use soroban_sdk::testutils::{Address as _, Ledger};
+use soroban_sdk::{token, Address, BytesN, Env};
+
+use crate::errors::Error;
+
+mod share_token {
+  soroban_sdk::contractimport!(
+    file = "../liquid-staking-contract/token/soroban_token_contract.wasm"
+  );
+}
+
+fn create_token_contract<'a>(e: &Env, admin: &Address) -> token::StellarAssetClient<'a> {
+  let asset_contract_registration = e.register_stellar_asset_contract_v2(admin.clone());
+
+  token::StellarAssetClient::new(e, &asset_contract_registration.address())
+}
+
+fn install_share_token_wasm(e: &Env) -> BytesN<32> {
+  e.deployer().upload_contract_wasm(share_token::WASM)
+}
+
+fn create_staking_contract<'a>(
+  e: &Env,
+  base_token: &Address,
+  reward_token: &Address,
+  owner: &Address,
+) -> staking::Client<'a> {
+  let staking_pool = staking::Client::new(e, &e.register_contract_wasm(None, staking::WASM));
+
+  staking_pool.initialize(
+    base_token,
+    reward_token,
+    owner,
+    &install_share_token_wasm(e),
+  );
+
+  staking_pool
+}
+
+fn create_account_contract<'a>(e: &Env) -> contract::VestingAccountClient<'a> {
+  contract::VestingAccountClient::new(e, &e.register_contract(None, contract::VestingAccount {}))
+}
+
+#[test]
+fn test_contract_initialize() {
+  let env = Env::default();
+  env.mock_all_auths();
+
+  let vesting = Address::generate(&env);
+  let beneficiary = Address::generate(&env);
+  let account = create_account_contract(&env);
+
+  assert_eq!(
+    account.try_get_account_state(),
+    Err(Ok(Error::NotInitialized))
+  );
+
+  let state = account.initialize(&vesting, &beneficiary);
+  assert_eq!(state.vesting, vesting);
+  assert_eq!(state.beneficiary, beneficiary);
+
+  assert_eq!(
+    account.try_initialize(&vesting, &beneficiary),
+    Err(Ok(Error::AlreadyInitialized))
+  );
+}
+
+#[test]
+fn test_position_flows() {
+  let env = Env::default();
+  env.mock_all_auths_allowing_non_root_auth();
+  env.budget().reset_unlimited();
+
+  let owner = Address::generate(&env);
+  let vesting = Address::generate(&env);
+  let beneficiary = Address::generate(&env);
+  let base_token = create_token_contract(&env, &owner);
+  let reward_token = create_token_contract(&env, &owner);
+  let base_token_client = token::Client::new(&env, &base_token.address);
+  let reward_token_client = token::Client::new(&env, &reward_token.address);
+  let staking_pool =
+    create_staking_contract(&env, &base_token.address, &reward_token.address, &owner);
+  let account = create_account_contract(&env);
+
+  reward_token.mint(&owner, &1_000_000);
+  staking_pool.add_reward_funds(&owner, &1_000_000);
+
+  account.initialize(&vesting, &beneficiary);
+
+  // the vesting contract sends the tokens first, the account only stakes what it holds
+  base_token.mint(&account.address, &1000);
+  assert_eq!(account.stake(&staking_pool.address, &1000), 1000);
+  assert_eq!(
+    staking_pool.get_user_position(&account.address).balance,
+    1000
+  );
+
+  env.ledger().with_mut(|li| {
+    li.timestamp += 100;
+  });
+
+  let claimed = account.claim(&staking_pool.address);
+  assert_eq!(claimed.rewards, 10_000);
+  assert_eq!(claimed.fees, 0);
+  assert_eq!(reward_token_client.balance(&beneficiary), 10_000);
+  assert_eq!(reward_token_client.balance(&account.address), 0);
+
+  // the principal goes back to the vesting contract, never to the beneficiary
+  assert_eq!(account.unstake(&staking_pool.address, &1000), 1000);
+  assert!(env.auths().iter().any(|(address, _)| *address == vesting));
+  assert_eq!(base_token_client.balance(&vesting), 1000);
+  assert_eq!(base_token_client.balance(&beneficiary), 0);
+
+  // nothing left to claim is not an error
+  let claimed = account.claim(&staking_pool.address);
+  assert_eq!(claimed.rewards, 0);
+  assert_eq!(claimed.fees, 0);
+}
+
+#[test]
+fn test_claim_when_reward_token_is_base_token() {
+  let env = Env::default();
+  env.mock_all_auths_allowing_non_root_auth();
+  env.budget().reset_unlimited();
+
+  let owner = Address::generate(&env);
+  let vesting = Address::generate(&env);
+  let beneficiary = Address::generate(&env);
+  let staker = Address::generate(&env);
+  let base_token = create_token_contract(&env, &owner);
+  let base_token_client = token::Client::new(&env, &base_token.address);
+  let staking_pool =
+    create_staking_contract(&env, &base_token.address, &base_token.address, &owner);
+  let account = create_account_contract(&env);
+
+  base_token.mint(&owner, &1_000_000);
+  staking_pool.add_reward_funds(&owner, &1_000_000);
+  staking_pool.set_stake_fees(&owner, &100, &0, &true);
+
+  account.initialize(&vesting, &beneficiary);
+
+  base_token.mint(&account.address, &1000);
+  account.stake(&staking_pool.address, &1000);
+
+  // the fee the second staker pays goes to the account
+  base_token.mint(&staker, &1000);
+  staking_pool.stake(&staker, &1000);
+
+  env.ledger().with_mut(|li| {
+    li.timestamp += 100;
+  });
+
+  // rewards and fees arrive in the same token, each is only counted once
+  let claimed = account.claim(&staking_pool.address);
+  assert_eq!(claimed.rewards, 4999);
+  assert_eq!(claimed.fees, 9);
+  assert_eq!(base_token_client.balance(&beneficiary), 5008);
+  assert_eq!(base_token_client.balance(&account.address), 0);
+
+  assert_eq!(account.unstake(&staking_pool.address, &990), 990);
+  assert_eq!(base_token_client.balance(&vesting), 990);
+}